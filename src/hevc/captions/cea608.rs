@@ -0,0 +1,274 @@
+use super::CaptionCue;
+
+const ROWS: usize = 15;
+const COLUMNS: usize = 32;
+
+/// 0x11 0x30..=0x3F
+const SPECIAL_CHARACTERS: &str = "®°½¿™¢£♪à èâêîôû";
+/// 0x12 0x20..=0x3F
+const EXTENDED_CHARACTERS_1: &str = "ÁÉÓÚÜü‘¡*’—©℠•“”ÀÂÇÈÊËëÎÏïÔÙùÛ«»";
+/// 0x13 0x20..=0x3F
+const EXTENDED_CHARACTERS_2: &str = "ÃãÍÌìÒòÕõ{}\\^_|~ÄäÖöß¥¤│ÅåØø┌┐└┘";
+
+/// Row of the preamble address codes, indexed by the first byte without the channel bit
+const PAC_ROWS: [usize; 8] = [10, 0, 2, 11, 13, 4, 6, 8];
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    #[default]
+    PopOn,
+    RollUp(usize),
+    PaintOn,
+}
+
+#[derive(Debug, Clone)]
+struct Memory([[char; COLUMNS]; ROWS]);
+
+/// Minimal CEA-608 decoder of the CC1 channel, producing the text of the displayed captions.
+/// Styles and positions are ignored.
+#[derive(Default, Debug)]
+pub(crate) struct Cea608Decoder {
+    mode: Mode,
+    displayed: Memory,
+    non_displayed: Memory,
+    row: usize,
+    column: usize,
+
+    /// Data channel selected by the last control code, 0 for CC1
+    channel: u8,
+    last_control_code: Option<[u8; 2]>,
+
+    display_start: Option<f64>,
+    cues: Vec<CaptionCue>,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self([[' '; COLUMNS]; ROWS])
+    }
+}
+
+impl Memory {
+    fn text(&self) -> String {
+        self.0
+            .iter()
+            .map(|row| row.iter().collect::<String>().trim().to_owned())
+            .filter(|row| !row.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Cea608Decoder {
+    /// Byte pair with parity bits, displayed at `time` in seconds
+    pub(crate) fn push(&mut self, time: f64, pair: [u8; 2]) {
+        let [b1, b2] = pair.map(|b| b & 0x7F);
+
+        if b1 == 0 && b2 == 0 {
+            return;
+        }
+
+        if (0x10..=0x1F).contains(&b1) {
+            // Control codes are usually sent twice
+            if self.last_control_code == Some([b1, b2]) {
+                self.last_control_code = None;
+                return;
+            }
+
+            self.last_control_code = Some([b1, b2]);
+            self.channel = (b1 & 0x08) >> 3;
+
+            if self.channel == 0 {
+                self.control_code(time, b1, b2);
+            }
+        } else {
+            self.last_control_code = None;
+
+            if self.channel == 0 && b1 >= 0x20 {
+                self.write(time, basic_character(b1));
+
+                if b2 >= 0x20 {
+                    self.write(time, basic_character(b2));
+                }
+            }
+        }
+    }
+
+    /// Ends the displayed caption and returns the cues
+    pub(crate) fn finish(mut self, time: f64) -> Vec<CaptionCue> {
+        self.end_display(time);
+
+        self.cues
+    }
+
+    fn control_code(&mut self, time: f64, b1: u8, b2: u8) {
+        let b1 = b1 & 0x17;
+
+        match (b1, b2) {
+            (0x14 | 0x15, 0x20..=0x2F) => self.miscellaneous_control_code(time, b2),
+            (0x17, 0x21..=0x23) => {
+                self.column = (self.column + (b2 - 0x20) as usize).min(COLUMNS - 1);
+            }
+            (0x11, 0x20..=0x2F) => {
+                // Mid-row codes are displayed as a space
+                self.write(time, ' ');
+            }
+            (0x11, 0x30..=0x3F) => {
+                self.write(time, table_character(SPECIAL_CHARACTERS, b2 - 0x30));
+            }
+            (0x12 | 0x13, 0x20..=0x3F) => {
+                let table = if b1 == 0x12 {
+                    EXTENDED_CHARACTERS_1
+                } else {
+                    EXTENDED_CHARACTERS_2
+                };
+
+                // Replaces the standard character sent before
+                self.column = self.column.saturating_sub(1);
+                self.write(time, table_character(table, b2 - 0x20));
+            }
+            (_, 0x40..=0x7F) => {
+                // Preamble address code
+                if !matches!(self.mode, Mode::RollUp(_)) {
+                    self.row = (PAC_ROWS[(b1 & 0x07) as usize] + ((b2 & 0x20) >> 5) as usize)
+                        .min(ROWS - 1);
+                }
+
+                self.column = if b2 & 0x10 != 0 {
+                    ((b2 & 0x0E) >> 1) as usize * 4
+                } else {
+                    0
+                };
+            }
+            _ => (),
+        }
+    }
+
+    fn miscellaneous_control_code(&mut self, time: f64, code: u8) {
+        match code {
+            // RCL
+            0x20 => self.mode = Mode::PopOn,
+            // BS
+            0x21 => {
+                self.column = self.column.saturating_sub(1);
+                let (row, column) = (self.row, self.column);
+                self.memory().0[row][column] = ' ';
+            }
+            // DER
+            0x24 => {
+                let (row, column) = (self.row, self.column);
+                self.memory().0[row][column..].fill(' ');
+            }
+            // RU2, RU3, RU4
+            0x25..=0x27 => {
+                if !matches!(self.mode, Mode::RollUp(_)) {
+                    self.end_display(time);
+                    self.displayed.clear();
+                    self.non_displayed.clear();
+                }
+
+                self.mode = Mode::RollUp((code - 0x23) as usize);
+                self.row = ROWS - 1;
+                self.column = 0;
+            }
+            // RDC
+            0x29 => self.mode = Mode::PaintOn,
+            // EDM
+            0x2C => {
+                self.end_display(time);
+                self.displayed.clear();
+            }
+            // CR
+            0x2D => match self.mode {
+                Mode::RollUp(rows) => {
+                    self.end_display(time);
+
+                    let memory = &mut self.displayed.0;
+                    memory.rotate_left(1);
+                    memory[ROWS - 1] = [' '; COLUMNS];
+                    memory[..ROWS - rows].fill([' '; COLUMNS]);
+
+                    self.column = 0;
+                    self.start_display(time);
+                }
+                _ => {
+                    self.row = (self.row + 1).min(ROWS - 1);
+                    self.column = 0;
+                }
+            },
+            // ENM
+            0x2E => self.non_displayed.clear(),
+            // EOC
+            0x2F => {
+                self.end_display(time);
+                std::mem::swap(&mut self.displayed, &mut self.non_displayed);
+                self.start_display(time);
+
+                self.mode = Mode::PopOn;
+            }
+            _ => (),
+        }
+    }
+
+    /// Memory written to in the current mode
+    fn memory(&mut self) -> &mut Memory {
+        match self.mode {
+            Mode::PopOn => &mut self.non_displayed,
+            Mode::RollUp(_) | Mode::PaintOn => &mut self.displayed,
+        }
+    }
+
+    fn write(&mut self, time: f64, c: char) {
+        let (row, column) = (self.row, self.column);
+        self.memory().0[row][column] = c;
+        self.column = (column + 1).min(COLUMNS - 1);
+
+        if self.mode != Mode::PopOn && self.display_start.is_none() {
+            self.display_start = Some(time);
+        }
+    }
+
+    fn start_display(&mut self, time: f64) {
+        self.display_start = Some(time);
+    }
+
+    fn end_display(&mut self, time: f64) {
+        let Some(start) = self.display_start.take() else {
+            return;
+        };
+
+        let text = self.displayed.text();
+
+        if !text.is_empty() && time > start {
+            self.cues.push(CaptionCue {
+                start,
+                end: time,
+                text,
+            });
+        }
+    }
+}
+
+fn basic_character(b: u8) -> char {
+    match b {
+        0x2A => 'á',
+        0x5C => 'é',
+        0x5E => 'í',
+        0x5F => 'ó',
+        0x60 => 'ú',
+        0x7B => 'ç',
+        0x7C => '÷',
+        0x7D => 'Ñ',
+        0x7E => 'ñ',
+        0x7F => '█',
+        _ => b as char,
+    }
+}
+
+fn table_character(table: &str, index: u8) -> char {
+    table.chars().nth(index as usize).unwrap_or(' ')
+}
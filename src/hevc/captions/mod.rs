@@ -0,0 +1,270 @@
+use std::io::Write;
+
+use anyhow::{Result, bail};
+
+use super::sei::a53::{
+    CC_TYPE_DTVCC_PACKET_START, CC_TYPE_NTSC_FIELD_1, CC_TYPE_NTSC_FIELD_2, CcConstruct,
+};
+use super::timing::{FrameRate, FrameTimestamp};
+use super::{Frame, SeiPayload};
+
+mod cea608;
+
+use cea608::Cea608Decoder;
+
+/// Frame rate assumed when the frames have no timestamps
+const DEFAULT_FRAME_RATE: FrameRate = FrameRate {
+    num: 30000,
+    den: 1001,
+};
+
+/// ATSC A/53 closed captions of a stream, in presentation order
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ClosedCaptions {
+    pub frames: Vec<FrameCaptions>,
+    /// From the timestamps of the frames, otherwise 30000/1001
+    pub frame_rate: Option<FrameRate>,
+}
+
+/// Valid cc_data constructs of a frame
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct FrameCaptions {
+    pub presentation_number: u64,
+    pub timestamp: Option<FrameTimestamp>,
+
+    /// CEA-608 byte pairs, parity bits included
+    pub field_1: Vec<[u8; 2]>,
+    pub field_2: Vec<[u8; 2]>,
+
+    /// CEA-708 DTVCC constructs, of type packet start or packet data
+    pub dtvcc: Vec<CcConstruct>,
+}
+
+/// Caption displayed from `start` to `end`, in seconds
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct CaptionCue {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+impl ClosedCaptions {
+    /// The frames must be in presentation order, usually `HevcParser::ordered_frames`.
+    /// Frames without cc_data are kept, so that the cadence is preserved.
+    pub fn from_frames(frames: &[Frame]) -> Self {
+        let frame_rate = frames
+            .iter()
+            .find_map(|f| f.timestamp)
+            .filter(|ts| ts.duration > 0)
+            .map(|ts| FrameRate::new(ts.time_scale, ts.duration as u32).reduced());
+
+        let frames = frames
+            .iter()
+            .map(|frame| {
+                let mut captions = FrameCaptions {
+                    presentation_number: frame.presentation_number,
+                    timestamp: frame.timestamp,
+                    ..Default::default()
+                };
+
+                let constructs = frame
+                    .prefix_sei
+                    .iter()
+                    .filter_map(|sei| match sei {
+                        SeiPayload::CcData(cc_data) if cc_data.process_cc_data_flag => {
+                            Some(&cc_data.constructs)
+                        }
+                        _ => None,
+                    })
+                    .flatten()
+                    .filter(|cc| cc.cc_valid);
+
+                for cc in constructs {
+                    let pair = [cc.cc_data_1, cc.cc_data_2];
+
+                    match cc.cc_type {
+                        CC_TYPE_NTSC_FIELD_1 => captions.field_1.push(pair),
+                        CC_TYPE_NTSC_FIELD_2 => captions.field_2.push(pair),
+                        // CC_TYPE_DTVCC_PACKET_DATA or CC_TYPE_DTVCC_PACKET_START
+                        _ => captions.dtvcc.push(*cc),
+                    }
+                }
+
+                captions
+            })
+            .collect();
+
+        Self { frames, frame_rate }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames
+            .iter()
+            .all(|f| f.field_1.is_empty() && f.field_2.is_empty() && f.dtvcc.is_empty())
+    }
+
+    /// CEA-608 byte stream of field 1 (CC1/CC2), or field 2 (CC3/CC4)
+    pub fn cea608(&self, field_2: bool) -> Vec<u8> {
+        self.frames
+            .iter()
+            .flat_map(|f| if field_2 { &f.field_2 } else { &f.field_1 })
+            .flatten()
+            .copied()
+            .collect()
+    }
+
+    /// CEA-708 DTVCC byte stream
+    pub fn cea708(&self) -> Vec<u8> {
+        self.frames
+            .iter()
+            .flat_map(|f| &f.dtvcc)
+            .flat_map(|cc| [cc.cc_data_1, cc.cc_data_2])
+            .collect()
+    }
+
+    /// CEA-708 DTVCC packets, split on the packet start constructs.
+    /// Data preceding the first packet start is dropped.
+    pub fn dtvcc_packets(&self) -> Vec<Vec<u8>> {
+        let mut packets: Vec<Vec<u8>> = Vec::new();
+
+        for cc in self.frames.iter().flat_map(|f| &f.dtvcc) {
+            if cc.cc_type == CC_TYPE_DTVCC_PACKET_START {
+                packets.push(Vec::new());
+            }
+
+            if let Some(packet) = packets.last_mut() {
+                packet.extend([cc.cc_data_1, cc.cc_data_2]);
+            }
+        }
+
+        packets
+    }
+
+    /// Writes the field 1 data as a Scenarist SCC file.
+    /// Each line holds a run of frames with non padding data, starting at the time code of its first frame.
+    pub fn write_scc(&self, writer: &mut dyn Write) -> Result<()> {
+        if self.frames.iter().all(|f| f.field_1.is_empty()) {
+            bail!("No CEA-608 field 1 data found");
+        }
+
+        let frame_rate = self.frame_rate.unwrap_or(DEFAULT_FRAME_RATE);
+
+        writeln!(writer, "Scenarist_SCC V1.0")?;
+
+        let mut line: Option<(String, Vec<String>)> = None;
+
+        for (i, frame) in self.frames.iter().enumerate() {
+            let pairs = frame
+                .field_1
+                .iter()
+                .filter(|pair| pair[0] & 0x7F != 0 || pair[1] & 0x7F != 0)
+                .map(|pair| format!("{:02x}{:02x}", pair[0], pair[1]));
+
+            let mut pairs = pairs.peekable();
+
+            if pairs.peek().is_none() {
+                if let Some((timecode, words)) = line.take() {
+                    writeln!(writer, "\n{timecode}\t{}", words.join(" "))?;
+                }
+
+                continue;
+            }
+
+            line.get_or_insert_with(|| (smpte_timecode(i as u64, frame_rate), Vec::new()))
+                .1
+                .extend(pairs);
+        }
+
+        if let Some((timecode, words)) = line {
+            writeln!(writer, "\n{timecode}\t{}", words.join(" "))?;
+        }
+
+        Ok(())
+    }
+
+    /// Decodes the CC1 channel into cues
+    pub fn cea608_cues(&self) -> Vec<CaptionCue> {
+        let frame_duration = 1.0 / self.frame_rate.unwrap_or(DEFAULT_FRAME_RATE).as_f64();
+        let frame_time = |i: usize, frame: &FrameCaptions| {
+            frame
+                .timestamp
+                .map_or(i as f64 * frame_duration, |ts| ts.pts_ms() / 1000.0)
+        };
+
+        let mut decoder = Cea608Decoder::default();
+
+        for (i, frame) in self.frames.iter().enumerate() {
+            let time = frame_time(i, frame);
+
+            for pair in &frame.field_1 {
+                decoder.push(time, *pair);
+            }
+        }
+
+        let end = self.frames.last().map_or(0.0, |frame| {
+            frame_time(self.frames.len() - 1, frame)
+                + frame
+                    .timestamp
+                    .map_or(frame_duration, |ts| ts.duration_ms() / 1000.0)
+        });
+
+        decoder.finish(end)
+    }
+
+    /// Writes the CC1 channel as SubRip subtitles
+    pub fn write_srt(&self, writer: &mut dyn Write) -> Result<()> {
+        for (i, cue) in self.cea608_cues().iter().enumerate() {
+            writeln!(
+                writer,
+                "{}\n{} --> {}\n{}\n",
+                i + 1,
+                srt_timestamp(cue.start),
+                srt_timestamp(cue.end),
+                cue.text
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `HH:MM:SS:FF`, or drop frame `HH:MM:SS;FF` for 30000/1001
+fn smpte_timecode(frame_number: u64, frame_rate: FrameRate) -> String {
+    let frame_rate = frame_rate.reduced();
+
+    if frame_rate == DEFAULT_FRAME_RATE {
+        let (d, m) = (frame_number / 17982, frame_number % 17982);
+        let dropped = 18 * d + if m > 1 { 2 * ((m - 2) / 1798) } else { 0 };
+        let n = frame_number + dropped;
+
+        format!(
+            "{:02}:{:02}:{:02};{:02}",
+            n / 108000,
+            (n / 1800) % 60,
+            (n / 30) % 60,
+            n % 30
+        )
+    } else {
+        let fps = frame_rate.as_f64().round().max(1.0) as u64;
+
+        format!(
+            "{:02}:{:02}:{:02}:{:02}",
+            frame_number / (fps * 3600),
+            (frame_number / (fps * 60)) % 60,
+            (frame_number / fps) % 60,
+            frame_number % fps
+        )
+    }
+}
+
+fn srt_timestamp(seconds: f64) -> String {
+    let ms = (seconds.max(0.0) * 1000.0).round() as u64;
+
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        ms / 3_600_000,
+        (ms / 60_000) % 60,
+        (ms / 1000) % 60,
+        ms % 1000
+    )
+}
@@ -0,0 +1,235 @@
+use super::NALUnit;
+use super::slice::SliceNAL;
+
+/// Reference picture set of a picture, as POC values, 8.3.2
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct RefPicSet {
+    pub st_curr_before: Vec<i32>,
+    pub st_curr_after: Vec<i32>,
+    pub st_foll: Vec<i32>,
+    pub lt_curr: Vec<LongTermRef>,
+    pub lt_foll: Vec<LongTermRef>,
+}
+
+/// Long term reference picture.
+/// When the MSB is not present, only the POC LSB can be used to identify the picture.
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct LongTermRef {
+    pub poc: i32,
+    pub msb_present: bool,
+}
+
+/// Picture kept in the DPB, marked as used for reference
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct ReferencePicture {
+    pub poc: i32,
+    pub decoded_number: u64,
+    pub long_term: bool,
+}
+
+/// Decoded picture buffer, only tracking the reference marking process
+#[derive(Default, Debug, Clone)]
+pub(crate) struct DecodedPictureBuffer {
+    pub(crate) pictures: Vec<ReferencePicture>,
+
+    /// Set once a picture was decoded, reset on end of sequence
+    pub(crate) started: bool,
+}
+
+impl RefPicSet {
+    /// NumPicTotalCurr
+    pub fn num_pic_total_curr(&self) -> usize {
+        self.st_curr_before.len() + self.st_curr_after.len() + self.lt_curr.len()
+    }
+}
+
+impl LongTermRef {
+    fn matches(&self, poc: i32, max_poc_lsb: i32) -> bool {
+        if self.msb_present {
+            self.poc == poc
+        } else {
+            (poc & (max_poc_lsb - 1)) == self.poc
+        }
+    }
+}
+
+impl DecodedPictureBuffer {
+    /// Decoding process for the RPS, 8.3.2
+    /// Removes the pictures not included in the RPS of the current picture.
+    ///
+    /// Returns `NoRaslOutputFlag` and the RPS with the long term POCs resolved.
+    pub(crate) fn apply_rps(
+        &mut self,
+        slice: &SliceNAL,
+        nal: &NALUnit,
+        max_poc_lsb: i32,
+    ) -> (bool, RefPicSet) {
+        let mut rps = slice.rps.clone();
+
+        let no_rasl_output_flag =
+            slice.key_frame && (!self.started || nal.is_idr() || nal.is_bla());
+
+        if no_rasl_output_flag {
+            self.pictures.clear();
+        } else {
+            for lt in rps.lt_curr.iter_mut().chain(rps.lt_foll.iter_mut()) {
+                if let Some(pic) = self
+                    .pictures
+                    .iter_mut()
+                    .find(|pic| lt.matches(pic.poc, max_poc_lsb))
+                {
+                    pic.long_term = true;
+
+                    lt.poc = pic.poc;
+                    lt.msb_present = true;
+                }
+            }
+
+            self.pictures.retain(|pic| {
+                if pic.long_term {
+                    rps.lt_curr
+                        .iter()
+                        .chain(rps.lt_foll.iter())
+                        .any(|lt| lt.msb_present && lt.poc == pic.poc)
+                } else {
+                    rps.st_curr_before
+                        .iter()
+                        .chain(rps.st_curr_after.iter())
+                        .chain(rps.st_foll.iter())
+                        .any(|poc| *poc == pic.poc)
+                }
+            });
+        }
+
+        self.started = true;
+
+        (no_rasl_output_flag, rps)
+    }
+
+    /// The current picture is marked as short term reference after decoding
+    pub(crate) fn add_picture(&mut self, poc: i32, decoded_number: u64) {
+        self.pictures.push(ReferencePicture {
+            poc,
+            decoded_number,
+            long_term: false,
+        });
+    }
+
+    pub(crate) fn end_of_sequence(&mut self) {
+        self.started = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hevc::{NAL_CRA_NUT, NAL_IDR_W_RADL, NAL_TRAIL_R};
+
+    const MAX_POC_LSB: i32 = 16;
+
+    fn nal(nal_type: u8) -> NALUnit {
+        NALUnit {
+            nal_type,
+            ..Default::default()
+        }
+    }
+
+    fn slice(key_frame: bool, rps: RefPicSet) -> SliceNAL {
+        let mut slice = SliceNAL::default();
+        slice.key_frame = key_frame;
+        slice.rps = rps;
+
+        slice
+    }
+
+    fn dpb_with(pocs: &[i32]) -> DecodedPictureBuffer {
+        let mut dpb = DecodedPictureBuffer::default();
+        dpb.apply_rps(
+            &slice(true, RefPicSet::default()),
+            &nal(NAL_IDR_W_RADL),
+            MAX_POC_LSB,
+        );
+
+        for (i, poc) in pocs.iter().enumerate() {
+            dpb.add_picture(*poc, i as u64);
+        }
+
+        dpb
+    }
+
+    fn pocs(dpb: &DecodedPictureBuffer) -> Vec<(i32, bool)> {
+        dpb.pictures.iter().map(|p| (p.poc, p.long_term)).collect()
+    }
+
+    #[test]
+    fn long_term_msb_resolution() {
+        let mut dpb = dpb_with(&[0, 16, 33]);
+
+        // POC LSB 1 only matches POC 33
+        let rps = RefPicSet {
+            st_curr_before: vec![16],
+            lt_curr: vec![LongTermRef {
+                poc: 1,
+                msb_present: false,
+            }],
+            ..Default::default()
+        };
+
+        let (no_rasl_output_flag, rps) =
+            dpb.apply_rps(&slice(false, rps), &nal(NAL_TRAIL_R), MAX_POC_LSB);
+
+        assert!(!no_rasl_output_flag);
+        assert_eq!(
+            rps.lt_curr,
+            [LongTermRef {
+                poc: 33,
+                msb_present: true,
+            }]
+        );
+        assert_eq!(pocs(&dpb), [(16, false), (33, true)]);
+
+        // Long term with the MSB, the short term picture is no longer referenced
+        let rps = RefPicSet {
+            lt_foll: vec![LongTermRef {
+                poc: 33,
+                msb_present: true,
+            }],
+            ..Default::default()
+        };
+
+        dpb.apply_rps(&slice(false, rps), &nal(NAL_TRAIL_R), MAX_POC_LSB);
+        assert_eq!(pocs(&dpb), [(33, true)]);
+    }
+
+    #[test]
+    fn flush_at_idr_and_end_of_sequence() {
+        let rps = RefPicSet {
+            st_curr_before: vec![8],
+            ..Default::default()
+        };
+
+        // IDR
+        let mut dpb = dpb_with(&[4, 8]);
+        let (no_rasl_output_flag, _) =
+            dpb.apply_rps(&slice(true, rps.clone()), &nal(NAL_IDR_W_RADL), MAX_POC_LSB);
+
+        assert!(no_rasl_output_flag);
+        assert!(dpb.pictures.is_empty());
+
+        // CRA in the middle of the sequence only applies the RPS
+        let mut dpb = dpb_with(&[4, 8]);
+        let (no_rasl_output_flag, _) =
+            dpb.apply_rps(&slice(true, rps.clone()), &nal(NAL_CRA_NUT), MAX_POC_LSB);
+
+        assert!(!no_rasl_output_flag);
+        assert_eq!(pocs(&dpb), [(8, false)]);
+
+        // CRA after an end of sequence NAL
+        dpb.end_of_sequence();
+        let (no_rasl_output_flag, _) =
+            dpb.apply_rps(&slice(true, rps), &nal(NAL_CRA_NUT), MAX_POC_LSB);
+
+        assert!(no_rasl_output_flag);
+        assert!(dpb.pictures.is_empty());
+    }
+}
@@ -0,0 +1,547 @@
+use super::sei::{ContentLightLevelInfo, MasteringDisplayColourVolume, SeiPayload};
+use super::stream_info::StreamInfo;
+use super::{Frame, NAL_UNSPEC62, NAL_UNSPEC63};
+
+/// Transfer characteristics, H.273
+const TRANSFER_PQ: u8 = 16;
+const TRANSFER_HLG: u8 = 18;
+const TRANSFER_BT709: u8 = 1;
+const TRANSFER_BT601: u8 = 6;
+const TRANSFER_BT2020_10: u8 = 14;
+const TRANSFER_BT2020_12: u8 = 15;
+
+const PRIMARIES_BT2020: u8 = 9;
+
+#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+pub enum HdrFormat {
+    Sdr,
+    /// PQ with mastering display metadata
+    Hdr10,
+    /// HDR10 with ST 2094-40 dynamic metadata
+    Hdr10Plus,
+    Hlg,
+    PqWithoutStaticMetadata,
+    DolbyVision(DolbyVisionProfile),
+}
+
+/// Dolby Vision profile, inferred from the layers and the base layer signalling
+#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+pub enum DolbyVisionProfile {
+    /// Single layer, IPTPQc2 without compatible base layer
+    Profile5,
+    /// Dual layer, HDR10 compatible base layer
+    Profile7,
+    /// Single layer, HDR10 compatible base layer
+    Profile8_1,
+    /// Single layer, SDR compatible base layer
+    Profile8_2,
+    /// Single layer, HLG compatible base layer
+    Profile8_4,
+}
+
+/// Problem in the HDR signalling of a stream
+#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+pub enum HdrInconsistency {
+    PqWithoutMasteringDisplay,
+    PqWithoutContentLightLevel,
+    /// Mastering display or content light level SEI on SDR content
+    StaticMetadataWithoutHdrTransfer,
+    Hdr10PlusWithoutPq,
+    /// HDR transfer with primaries other than BT.2020
+    HdrWithoutBt2020Primaries,
+    HdrWith8BitDepth,
+    /// The static metadata is not the same in every frame that has it
+    StaticMetadataChanges,
+    /// MaxCLL above the peak luminance of the mastering display
+    MaxCllAboveMasteringLuminance,
+    DolbyVisionEnhancementLayerWithoutRpu,
+    /// Some frames have no RPU
+    DolbyVisionMissingRpu,
+    /// Some frames have HDR10+ metadata, others not
+    Hdr10PlusMissingFrames,
+}
+
+/// HDR format of a stream, from the parameter sets and SEI messages
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub struct HdrClassification {
+    pub format: HdrFormat,
+    /// Transfer characteristics used for the classification, see `StreamInfo::preferred_transfer_characteristics`
+    pub transfer_characteristics: u8,
+
+    pub mastering_display: Option<MasteringDisplayColourVolume>,
+    pub content_light_level: Option<ContentLightLevelInfo>,
+
+    /// Number of frames with a Dolby Vision RPU, in `NAL_UNSPEC62`
+    pub dolby_vision_rpu_frames: usize,
+    /// Number of frames with a Dolby Vision enhancement layer, in `NAL_UNSPEC63`
+    pub dolby_vision_el_frames: usize,
+    pub hdr10plus_frames: usize,
+
+    pub inconsistencies: Vec<HdrInconsistency>,
+}
+
+impl HdrFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HdrFormat::Sdr => "SDR",
+            HdrFormat::Hdr10 => "HDR10",
+            HdrFormat::Hdr10Plus => "HDR10+",
+            HdrFormat::Hlg => "HLG",
+            HdrFormat::PqWithoutStaticMetadata => "PQ",
+            HdrFormat::DolbyVision(_) => "Dolby Vision",
+        }
+    }
+}
+
+impl DolbyVisionProfile {
+    /// e.g. `8.1`
+    pub fn name(&self) -> &'static str {
+        match self {
+            DolbyVisionProfile::Profile5 => "5",
+            DolbyVisionProfile::Profile7 => "7",
+            DolbyVisionProfile::Profile8_1 => "8.1",
+            DolbyVisionProfile::Profile8_2 => "8.2",
+            DolbyVisionProfile::Profile8_4 => "8.4",
+        }
+    }
+}
+
+impl HdrClassification {
+    /// Classifies the stream from its summary and SEI messages, without decoding the pictures.
+    pub fn new(info: &StreamInfo, frames: &[Frame]) -> Self {
+        let transfer = info.preferred_transfer_characteristics;
+        let vui_transfer = info.transfer_characteristics;
+
+        let mut classification = HdrClassification {
+            format: HdrFormat::Sdr,
+            transfer_characteristics: transfer,
+            mastering_display: None,
+            content_light_level: None,
+            dolby_vision_rpu_frames: 0,
+            dolby_vision_el_frames: 0,
+            hdr10plus_frames: 0,
+            inconsistencies: Vec::new(),
+        };
+
+        let mut static_metadata_changes = false;
+
+        for frame in frames {
+            if frame.nals.iter().any(|nal| nal.nal_type == NAL_UNSPEC62) {
+                classification.dolby_vision_rpu_frames += 1;
+            }
+
+            if frame.nals.iter().any(|nal| nal.nal_type == NAL_UNSPEC63) {
+                classification.dolby_vision_el_frames += 1;
+            }
+
+            if frame
+                .prefix_sei
+                .iter()
+                .any(|sei| matches!(sei, SeiPayload::Hdr10Plus(_)))
+            {
+                classification.hdr10plus_frames += 1;
+            }
+
+            for sei in &frame.prefix_sei {
+                match sei {
+                    SeiPayload::MasteringDisplayColourVolume(mdcv) => {
+                        match classification.mastering_display {
+                            Some(first) => static_metadata_changes |= first != *mdcv,
+                            None => classification.mastering_display = Some(*mdcv),
+                        }
+                    }
+                    SeiPayload::ContentLightLevelInfo(cll) => {
+                        match classification.content_light_level {
+                            Some(first) => static_metadata_changes |= first != *cll,
+                            None => classification.content_light_level = Some(*cll),
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        let is_pq = transfer == TRANSFER_PQ;
+        let is_hlg = transfer == TRANSFER_HLG;
+        let has_rpu = classification.dolby_vision_rpu_frames > 0;
+        let has_el = classification.dolby_vision_el_frames > 0;
+
+        classification.format = if has_rpu {
+            let profile = if has_el {
+                DolbyVisionProfile::Profile7
+            } else if is_pq {
+                DolbyVisionProfile::Profile8_1
+            } else if is_hlg {
+                DolbyVisionProfile::Profile8_4
+            } else if matches!(
+                vui_transfer,
+                TRANSFER_BT709 | TRANSFER_BT601 | TRANSFER_BT2020_10 | TRANSFER_BT2020_12
+            ) {
+                DolbyVisionProfile::Profile8_2
+            } else {
+                // Unspecified colour signalling, the base layer is IPTPQc2
+                DolbyVisionProfile::Profile5
+            };
+
+            HdrFormat::DolbyVision(profile)
+        } else if is_hlg {
+            HdrFormat::Hlg
+        } else if is_pq && classification.hdr10plus_frames > 0 {
+            HdrFormat::Hdr10Plus
+        } else if is_pq && classification.mastering_display.is_some() {
+            HdrFormat::Hdr10
+        } else if is_pq {
+            HdrFormat::PqWithoutStaticMetadata
+        } else {
+            HdrFormat::Sdr
+        };
+
+        let inconsistencies = &mut classification.inconsistencies;
+        let hdr_transfer = is_pq || is_hlg;
+
+        if is_pq && classification.mastering_display.is_none() {
+            inconsistencies.push(HdrInconsistency::PqWithoutMasteringDisplay);
+        }
+
+        if is_pq && classification.content_light_level.is_none() {
+            inconsistencies.push(HdrInconsistency::PqWithoutContentLightLevel);
+        }
+
+        let has_static_metadata = classification.mastering_display.is_some()
+            || classification.content_light_level.is_some();
+        let profile_5 =
+            classification.format == HdrFormat::DolbyVision(DolbyVisionProfile::Profile5);
+
+        if has_static_metadata && !hdr_transfer && !profile_5 {
+            inconsistencies.push(HdrInconsistency::StaticMetadataWithoutHdrTransfer);
+        }
+
+        if classification.hdr10plus_frames > 0 && !is_pq {
+            inconsistencies.push(HdrInconsistency::Hdr10PlusWithoutPq);
+        }
+
+        if hdr_transfer && info.colour_primaries != PRIMARIES_BT2020 {
+            inconsistencies.push(HdrInconsistency::HdrWithoutBt2020Primaries);
+        }
+
+        if (hdr_transfer || has_rpu) && info.bit_depth_luma < 10 {
+            inconsistencies.push(HdrInconsistency::HdrWith8BitDepth);
+        }
+
+        if static_metadata_changes {
+            inconsistencies.push(HdrInconsistency::StaticMetadataChanges);
+        }
+
+        if let (Some(mdcv), Some(cll)) = (
+            classification.mastering_display,
+            classification.content_light_level,
+        ) && cll.max_content_light_level as u32 * 10_000 > mdcv.max_display_mastering_luminance
+            && mdcv.max_display_mastering_luminance > 0
+        {
+            inconsistencies.push(HdrInconsistency::MaxCllAboveMasteringLuminance);
+        }
+
+        if has_el && !has_rpu {
+            inconsistencies.push(HdrInconsistency::DolbyVisionEnhancementLayerWithoutRpu);
+        }
+
+        if has_rpu && classification.dolby_vision_rpu_frames < frames.len() {
+            inconsistencies.push(HdrInconsistency::DolbyVisionMissingRpu);
+        }
+
+        if classification.hdr10plus_frames > 0 && classification.hdr10plus_frames < frames.len() {
+            inconsistencies.push(HdrInconsistency::Hdr10PlusMissingFrames);
+        }
+
+        classification
+    }
+
+    /// e.g. `HDR10+` or `Dolby Vision 8.1`
+    pub fn label(&self) -> String {
+        match self.format {
+            HdrFormat::DolbyVision(profile) => format!("Dolby Vision {}", profile.name()),
+            format => format.name().to_string(),
+        }
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistencies.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hevc::NALUnit;
+    use crate::hevc::sei::Hdr10PlusMetadata;
+
+    use DolbyVisionProfile::*;
+    use HdrInconsistency::*;
+
+    /// Name, stream, frames, expected format and inconsistencies
+    type Case = (
+        &'static str,
+        StreamInfo,
+        Vec<Frame>,
+        HdrFormat,
+        Vec<HdrInconsistency>,
+    );
+
+    const TRANSFER_UNSPECIFIED: u8 = 2;
+    const PRIMARIES_BT709: u8 = 1;
+
+    /// 1000 cd/m² mastering display
+    const MDCV: MasteringDisplayColourVolume = MasteringDisplayColourVolume {
+        display_primaries_x: [13250, 7500, 34000],
+        display_primaries_y: [34500, 3000, 16000],
+        white_point_x: 15635,
+        white_point_y: 16450,
+        max_display_mastering_luminance: 10_000_000,
+        min_display_mastering_luminance: 50,
+    };
+
+    fn cll(max_content_light_level: u16) -> SeiPayload {
+        SeiPayload::ContentLightLevelInfo(ContentLightLevelInfo {
+            max_content_light_level,
+            max_pic_average_light_level: 400,
+        })
+    }
+
+    fn hdr10() -> Vec<SeiPayload> {
+        vec![SeiPayload::MasteringDisplayColourVolume(MDCV), cll(1000)]
+    }
+
+    fn hdr10plus() -> SeiPayload {
+        SeiPayload::Hdr10Plus(Hdr10PlusMetadata::default())
+    }
+
+    fn info(
+        colour_primaries: u8,
+        transfer_characteristics: u8,
+        preferred_transfer_characteristics: u8,
+        bit_depth: u64,
+    ) -> StreamInfo {
+        StreamInfo {
+            width: 1920,
+            height: 1080,
+            conformance_window: Default::default(),
+            chroma_format_idc: 1,
+            bit_depth_luma: bit_depth,
+            bit_depth_chroma: bit_depth,
+            colour_primaries,
+            transfer_characteristics,
+            preferred_transfer_characteristics,
+            matrix_coefficients: 9,
+            full_range: false,
+            frame_rate: None,
+            coding: Default::default(),
+            field_order: Default::default(),
+            stereo: None,
+            spherical: None,
+            multi_layer: false,
+            alpha_channel: None,
+        }
+    }
+
+    fn pq() -> StreamInfo {
+        info(PRIMARIES_BT2020, TRANSFER_PQ, TRANSFER_PQ, 10)
+    }
+
+    /// HLG through the alternative transfer characteristics SEI
+    fn hlg() -> StreamInfo {
+        info(PRIMARIES_BT2020, TRANSFER_BT2020_10, TRANSFER_HLG, 10)
+    }
+
+    fn sdr() -> StreamInfo {
+        info(PRIMARIES_BT709, TRANSFER_BT709, TRANSFER_BT709, 8)
+    }
+
+    fn frame(nal_types: &[u8], prefix_sei: Vec<SeiPayload>) -> Frame {
+        Frame {
+            nals: [1]
+                .iter()
+                .chain(nal_types)
+                .map(|&nal_type| NALUnit {
+                    nal_type,
+                    ..Default::default()
+                })
+                .collect(),
+            prefix_sei,
+            ..Default::default()
+        }
+    }
+
+    /// Two frames with the same NAL units and SEI messages
+    fn frames(nal_types: &[u8], prefix_sei: Vec<SeiPayload>) -> Vec<Frame> {
+        vec![
+            frame(nal_types, prefix_sei.clone()),
+            frame(nal_types, prefix_sei),
+        ]
+    }
+
+    #[test]
+    fn classification() {
+        let rpu = &[NAL_UNSPEC62];
+        let rpu_el = &[NAL_UNSPEC62, NAL_UNSPEC63];
+
+        let cases: Vec<Case> = vec![
+            ("SDR", sdr(), frames(&[], vec![]), HdrFormat::Sdr, vec![]),
+            (
+                "HDR10",
+                pq(),
+                frames(&[], hdr10()),
+                HdrFormat::Hdr10,
+                vec![],
+            ),
+            (
+                "HDR10+",
+                pq(),
+                frames(&[], [hdr10(), vec![hdr10plus()]].concat()),
+                HdrFormat::Hdr10Plus,
+                vec![],
+            ),
+            ("HLG", hlg(), frames(&[], vec![]), HdrFormat::Hlg, vec![]),
+            (
+                "PQ",
+                pq(),
+                frames(&[], vec![]),
+                HdrFormat::PqWithoutStaticMetadata,
+                vec![PqWithoutMasteringDisplay, PqWithoutContentLightLevel],
+            ),
+            (
+                "DV 5",
+                info(2, TRANSFER_UNSPECIFIED, TRANSFER_UNSPECIFIED, 10),
+                frames(rpu, hdr10()),
+                HdrFormat::DolbyVision(Profile5),
+                vec![],
+            ),
+            (
+                "DV 7",
+                pq(),
+                frames(rpu_el, hdr10()),
+                HdrFormat::DolbyVision(Profile7),
+                vec![],
+            ),
+            (
+                "DV 8.1",
+                pq(),
+                frames(rpu, hdr10()),
+                HdrFormat::DolbyVision(Profile8_1),
+                vec![],
+            ),
+            (
+                "DV 8.2",
+                info(PRIMARIES_BT709, TRANSFER_BT709, TRANSFER_BT709, 10),
+                frames(rpu, vec![]),
+                HdrFormat::DolbyVision(Profile8_2),
+                vec![],
+            ),
+            (
+                "DV 8.4",
+                hlg(),
+                frames(rpu, vec![]),
+                HdrFormat::DolbyVision(Profile8_4),
+                vec![],
+            ),
+            (
+                "static metadata on SDR",
+                sdr(),
+                frames(&[], vec![cll(1000)]),
+                HdrFormat::Sdr,
+                vec![StaticMetadataWithoutHdrTransfer],
+            ),
+            (
+                "HDR10+ on SDR",
+                sdr(),
+                frames(&[], vec![hdr10plus()]),
+                HdrFormat::Sdr,
+                vec![Hdr10PlusWithoutPq],
+            ),
+            (
+                "HLG in BT.709",
+                info(PRIMARIES_BT709, TRANSFER_HLG, TRANSFER_HLG, 10),
+                frames(&[], vec![]),
+                HdrFormat::Hlg,
+                vec![HdrWithoutBt2020Primaries],
+            ),
+            (
+                "8 bits HLG",
+                info(PRIMARIES_BT2020, TRANSFER_HLG, TRANSFER_HLG, 8),
+                frames(&[], vec![]),
+                HdrFormat::Hlg,
+                vec![HdrWith8BitDepth],
+            ),
+            (
+                "changing MaxCLL",
+                pq(),
+                vec![
+                    frame(&[], hdr10()),
+                    frame(
+                        &[],
+                        vec![SeiPayload::MasteringDisplayColourVolume(MDCV), cll(900)],
+                    ),
+                ],
+                HdrFormat::Hdr10,
+                vec![StaticMetadataChanges],
+            ),
+            (
+                "MaxCLL above the mastering display",
+                pq(),
+                frames(
+                    &[],
+                    vec![SeiPayload::MasteringDisplayColourVolume(MDCV), cll(4000)],
+                ),
+                HdrFormat::Hdr10,
+                vec![MaxCllAboveMasteringLuminance],
+            ),
+            (
+                "DV EL without RPU",
+                pq(),
+                frames(&[NAL_UNSPEC63], hdr10()),
+                HdrFormat::Hdr10,
+                vec![DolbyVisionEnhancementLayerWithoutRpu],
+            ),
+            (
+                "DV missing RPU",
+                pq(),
+                vec![frame(rpu, hdr10()), frame(&[], hdr10())],
+                HdrFormat::DolbyVision(Profile8_1),
+                vec![DolbyVisionMissingRpu],
+            ),
+            (
+                "HDR10+ missing frames",
+                pq(),
+                vec![
+                    frame(&[], [hdr10(), vec![hdr10plus()]].concat()),
+                    frame(&[], hdr10()),
+                ],
+                HdrFormat::Hdr10Plus,
+                vec![Hdr10PlusMissingFrames],
+            ),
+        ];
+
+        for (name, info, frames, format, inconsistencies) in cases {
+            let classification = HdrClassification::new(&info, &frames);
+
+            assert_eq!(classification.format, format, "{name}");
+            assert_eq!(classification.inconsistencies, inconsistencies, "{name}");
+        }
+    }
+
+    #[test]
+    fn frame_counts_and_label() {
+        let frames = vec![
+            frame(&[NAL_UNSPEC62, NAL_UNSPEC63], hdr10()),
+            frame(&[NAL_UNSPEC62], hdr10()),
+            frame(&[NAL_UNSPEC62], [hdr10(), vec![hdr10plus()]].concat()),
+        ];
+        let classification = HdrClassification::new(&pq(), &frames);
+
+        assert_eq!(classification.dolby_vision_rpu_frames, 3);
+        assert_eq!(classification.dolby_vision_el_frames, 1);
+        assert_eq!(classification.hdr10plus_frames, 1);
+        assert_eq!(classification.mastering_display, Some(MDCV));
+        assert_eq!(classification.label(), "Dolby Vision 7");
+        assert!(!classification.is_consistent());
+    }
+}
@@ -1,94 +1,185 @@
 use super::BsIoVecReader;
 use anyhow::Result;
 
-#[derive(Default)]
-pub struct HrdParameters {}
+/// hrd_parameters, E.2.2
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub struct HrdParameters {
+    pub(crate) nal_hrd_parameters_present_flag: bool,
+    pub(crate) vcl_hrd_parameters_present_flag: bool,
+    pub(crate) sub_pic_hrd_params_present_flag: bool,
+
+    pub(crate) tick_divisor_minus2: u8,
+    pub(crate) du_cpb_removal_delay_increment_length_minus1: u8,
+    pub(crate) sub_pic_cpb_params_in_pic_timing_sei_flag: bool,
+    pub(crate) dpb_output_delay_du_length_minus1: u8,
+
+    pub(crate) bit_rate_scale: u8,
+    pub(crate) cpb_size_scale: u8,
+    pub(crate) cpb_size_du_scale: u8,
+
+    pub(crate) initial_cpb_removal_delay_length_minus1: u8,
+    pub(crate) au_cpb_removal_delay_length_minus1: u8,
+    pub(crate) dpb_output_delay_length_minus1: u8,
+
+    /// One per sub-layer
+    pub(crate) sub_layers: Vec<SubLayerHrd>,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct SubLayerHrd {
+    pub(crate) fixed_pic_rate_general_flag: bool,
+    pub(crate) fixed_pic_rate_within_cvs_flag: bool,
+    pub(crate) elemental_duration_in_tc_minus1: u64,
+    pub(crate) low_delay_hrd_flag: bool,
+    pub(crate) cpb_cnt_minus1: u64,
 
-pub struct SubLayerHrdParameter {}
+    pub(crate) nal: Vec<SubLayerHrdParameter>,
+    pub(crate) vcl: Vec<SubLayerHrdParameter>,
+}
+
+/// sub_layer_hrd_parameters of a single CPB
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct SubLayerHrdParameter {
+    pub(crate) bit_rate_value_minus1: u64,
+    pub(crate) cpb_size_value_minus1: u64,
+    pub(crate) cpb_size_du_value_minus1: u64,
+    pub(crate) bit_rate_du_value_minus1: u64,
+    pub(crate) cbr_flag: bool,
+}
+
+impl Default for HrdParameters {
+    fn default() -> Self {
+        Self {
+            nal_hrd_parameters_present_flag: false,
+            vcl_hrd_parameters_present_flag: false,
+            sub_pic_hrd_params_present_flag: false,
+            tick_divisor_minus2: 0,
+            du_cpb_removal_delay_increment_length_minus1: 0,
+            sub_pic_cpb_params_in_pic_timing_sei_flag: false,
+            dpb_output_delay_du_length_minus1: 0,
+            bit_rate_scale: 0,
+            cpb_size_scale: 0,
+            cpb_size_du_scale: 0,
+            // Inferred values when not present
+            initial_cpb_removal_delay_length_minus1: 23,
+            au_cpb_removal_delay_length_minus1: 23,
+            dpb_output_delay_length_minus1: 23,
+            sub_layers: Vec::new(),
+        }
+    }
+}
 
 impl HrdParameters {
     pub fn parse(
         bs: &mut BsIoVecReader,
         common_inf_present: bool,
         vps_max_sub_layers: u8,
-    ) -> Result<()> {
-        let mut nal_params_present = false;
-        let mut vcl_params_present = false;
-        let mut subpic_params_present = false;
+    ) -> Result<HrdParameters> {
+        let mut hrd = HrdParameters::default();
 
         if common_inf_present {
-            nal_params_present = bs.read_bit()?;
-            vcl_params_present = bs.read_bit()?;
+            hrd.nal_hrd_parameters_present_flag = bs.read_bit()?;
+            hrd.vcl_hrd_parameters_present_flag = bs.read_bit()?;
 
-            if nal_params_present || vcl_params_present {
-                subpic_params_present = bs.read_bit()?;
+            if hrd.nal_hrd_parameters_present_flag || hrd.vcl_hrd_parameters_present_flag {
+                hrd.sub_pic_hrd_params_present_flag = bs.read_bit()?;
 
-                if subpic_params_present {
-                    bs.skip_n(8)?; // tick_divisor_minus2
-                    bs.skip_n(5)?; // du_cpb_removal_delay_increment_length_minus1
-                    bs.skip_n(1)?; // sub_pic_cpb_params_in_pic_timing_sei_flag
-                    bs.skip_n(5)?; // dpb_output_delay_du_length_minus1
+                if hrd.sub_pic_hrd_params_present_flag {
+                    hrd.tick_divisor_minus2 = bs.read::<8, u8>()?;
+                    hrd.du_cpb_removal_delay_increment_length_minus1 = bs.read::<5, u8>()?;
+                    hrd.sub_pic_cpb_params_in_pic_timing_sei_flag = bs.read_bit()?;
+                    hrd.dpb_output_delay_du_length_minus1 = bs.read::<5, u8>()?;
                 }
 
-                bs.skip_n(4)?; // bit_rate_scale
-                bs.skip_n(4)?; // cpb_size_scale
+                hrd.bit_rate_scale = bs.read::<4, u8>()?;
+                hrd.cpb_size_scale = bs.read::<4, u8>()?;
 
-                if subpic_params_present {
-                    bs.skip_n(4)?; // cpb_size_du_scale
+                if hrd.sub_pic_hrd_params_present_flag {
+                    hrd.cpb_size_du_scale = bs.read::<4, u8>()?;
                 }
 
-                bs.skip_n(5)?; // initial_cpb_removal_delay_length_minus1
-                bs.skip_n(5)?; // au_cpb_removal_delay_length_minus1
-                bs.skip_n(5)?; // dpb_output_delay_length_minus1
+                hrd.initial_cpb_removal_delay_length_minus1 = bs.read::<5, u8>()?;
+                hrd.au_cpb_removal_delay_length_minus1 = bs.read::<5, u8>()?;
+                hrd.dpb_output_delay_length_minus1 = bs.read::<5, u8>()?;
             }
         }
 
         for _ in 0..vps_max_sub_layers {
-            let mut low_delay = false;
-            let mut nb_cpb = 1;
-            let mut fixed_rate = bs.read_bit()?;
+            let mut sub_layer = SubLayerHrd {
+                fixed_pic_rate_general_flag: bs.read_bit()?,
+                ..Default::default()
+            };
+
+            sub_layer.fixed_pic_rate_within_cvs_flag = sub_layer.fixed_pic_rate_general_flag;
 
-            if !fixed_rate {
-                fixed_rate = bs.read_bit()?;
+            if !sub_layer.fixed_pic_rate_general_flag {
+                sub_layer.fixed_pic_rate_within_cvs_flag = bs.read_bit()?;
             }
 
-            if fixed_rate {
-                bs.read_ue()?;
+            if sub_layer.fixed_pic_rate_within_cvs_flag {
+                sub_layer.elemental_duration_in_tc_minus1 = bs.read_ue()?;
             } else {
-                low_delay = bs.read_bit()?;
+                sub_layer.low_delay_hrd_flag = bs.read_bit()?;
             }
 
-            if !low_delay {
-                nb_cpb = bs.read_ue()? + 1;
+            if !sub_layer.low_delay_hrd_flag {
+                sub_layer.cpb_cnt_minus1 = bs.read_ue()?;
             }
 
-            if nal_params_present {
-                SubLayerHrdParameter::parse(bs, nb_cpb, subpic_params_present)?;
+            let nb_cpb = sub_layer.cpb_cnt_minus1 + 1;
+
+            if hrd.nal_hrd_parameters_present_flag {
+                sub_layer.nal =
+                    SubLayerHrdParameter::parse(bs, nb_cpb, hrd.sub_pic_hrd_params_present_flag)?;
             }
 
-            if vcl_params_present {
-                SubLayerHrdParameter::parse(bs, nb_cpb, subpic_params_present)?;
+            if hrd.vcl_hrd_parameters_present_flag {
+                sub_layer.vcl =
+                    SubLayerHrdParameter::parse(bs, nb_cpb, hrd.sub_pic_hrd_params_present_flag)?;
             }
+
+            hrd.sub_layers.push(sub_layer);
         }
 
-        Ok(())
+        Ok(hrd)
+    }
+
+    /// CpbDpbDelaysPresentFlag
+    pub(crate) fn cpb_dpb_delays_present(&self) -> bool {
+        self.nal_hrd_parameters_present_flag || self.vcl_hrd_parameters_present_flag
+    }
+
+    /// Number of CPBs of the highest sub-layer
+    pub(crate) fn cpb_cnt(&self) -> u64 {
+        self.sub_layers
+            .last()
+            .map_or(1, |sub_layer| sub_layer.cpb_cnt_minus1 + 1)
     }
 }
 
 impl SubLayerHrdParameter {
-    pub fn parse(bs: &mut BsIoVecReader, nb_cpb: u64, subpic_params_present: bool) -> Result<()> {
-        for _ in 0..nb_cpb {
-            bs.read_ue()?; // bit_rate_value_minus1
-            bs.read_ue()?; // cpb_size_value_minus1
-
-            if subpic_params_present {
-                bs.read_ue()?; // cpb_size_du_value_minus1
-                bs.read_ue()?; // bit_rate_du_value_minus1
-            }
+    pub fn parse(
+        bs: &mut BsIoVecReader,
+        nb_cpb: u64,
+        subpic_params_present: bool,
+    ) -> Result<Vec<SubLayerHrdParameter>> {
+        (0..nb_cpb)
+            .map(|_| {
+                let mut params = SubLayerHrdParameter {
+                    bit_rate_value_minus1: bs.read_ue()?,
+                    cpb_size_value_minus1: bs.read_ue()?,
+                    ..Default::default()
+                };
 
-            bs.skip_n(1)?; // cbr_flag
-        }
+                if subpic_params_present {
+                    params.cpb_size_du_value_minus1 = bs.read_ue()?;
+                    params.bit_rate_du_value_minus1 = bs.read_ue()?;
+                }
+
+                params.cbr_flag = bs.read_bit()?;
 
-        Ok(())
+                Ok(params)
+            })
+            .collect()
     }
 }
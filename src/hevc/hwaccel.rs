@@ -0,0 +1,618 @@
+use super::NALUnit;
+use super::pps::PPSNAL;
+use super::slice::{SLICE_TYPE_B, SLICE_TYPE_I, SliceNAL};
+use super::sps::SPSNAL;
+
+pub use super::dpb::ReferencePicture;
+pub use super::pred_weight_table::{PredWeight, PredWeightTable};
+pub use super::scaling_list_data::ScalingLists;
+
+/// Parameters required by hardware accelerated decoders to decode a frame.
+///
+/// The values map to the syntax elements and variables of the spec,
+/// and can be used to fill VA-API, Vulkan Video or similar structures.
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct HwAccelParameters {
+    pub picture: PictureParameters,
+    /// Slice segments, in decoding order
+    pub slices: Vec<SliceParameters>,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct PictureParameters {
+    pub decoded_number: u64,
+    pub pic_order_cnt_val: i32,
+    pub nal_type: u8,
+
+    pub irap_pic_flag: bool,
+    pub idr_pic_flag: bool,
+    /// All the slices of the picture are I slices
+    pub intra_pic_flag: bool,
+    pub no_rasl_output_flag: bool,
+    pub is_reference: bool,
+
+    /// Pictures marked as used for reference, after the RPS of the current picture was applied
+    pub reference_frames: Vec<ReferencePicture>,
+    /// POC values of `RefPicSetStCurrBefore`
+    pub ref_pic_set_st_curr_before: Vec<i32>,
+    /// POC values of `RefPicSetStCurrAfter`
+    pub ref_pic_set_st_curr_after: Vec<i32>,
+    /// POC values of `RefPicSetLtCurr`
+    pub ref_pic_set_lt_curr: Vec<i32>,
+
+    pub vps_id: u8,
+    pub sps_id: u64,
+    pub pps_id: u64,
+
+    // SPS
+    pub pic_width_in_luma_samples: u64,
+    pub pic_height_in_luma_samples: u64,
+    pub chroma_format_idc: u64,
+    pub separate_colour_plane_flag: bool,
+    pub bit_depth_luma: u64,
+    pub bit_depth_chroma: u64,
+    pub log2_max_pic_order_cnt_lsb: u64,
+    pub sps_max_dec_pic_buffering: u64,
+    pub log2_min_luma_coding_block_size: u64,
+    pub log2_diff_max_min_luma_coding_block_size: u64,
+    pub log2_min_transform_block_size: u64,
+    pub log2_diff_max_min_transform_block_size: u64,
+    pub max_transform_hierarchy_depth_inter: u64,
+    pub max_transform_hierarchy_depth_intra: u64,
+    pub scaling_list_enabled_flag: bool,
+    pub amp_enabled_flag: bool,
+    pub sample_adaptive_offset_enabled_flag: bool,
+    pub pcm_enabled_flag: bool,
+    pub pcm_sample_bit_depth_luma: u8,
+    pub pcm_sample_bit_depth_chroma: u8,
+    pub log2_min_pcm_luma_coding_block_size: u64,
+    pub log2_max_pcm_luma_coding_block_size: u64,
+    pub pcm_loop_filter_disabled_flag: bool,
+    pub num_short_term_ref_pic_sets: u64,
+    pub long_term_ref_pics_present_flag: bool,
+    pub num_long_term_ref_pics_sps: u64,
+    pub sps_temporal_mvp_enabled_flag: bool,
+    pub strong_intra_smoothing_enabled_flag: bool,
+
+    // SPS range extension
+    pub transform_skip_rotation_enabled_flag: bool,
+    pub transform_skip_context_enabled_flag: bool,
+    pub implicit_rdpcm_enabled_flag: bool,
+    pub explicit_rdpcm_enabled_flag: bool,
+    pub extended_precision_processing_flag: bool,
+    pub intra_smoothing_disabled_flag: bool,
+    pub high_precision_offsets_enabled_flag: bool,
+    pub persistent_rice_adaptation_enabled_flag: bool,
+    pub cabac_bypass_alignment_enabled_flag: bool,
+
+    // PPS
+    pub dependent_slice_segments_enabled_flag: bool,
+    pub output_flag_present_flag: bool,
+    pub num_extra_slice_header_bits: u8,
+    pub sign_data_hiding_enabled_flag: bool,
+    pub cabac_init_present_flag: bool,
+    pub num_ref_idx_l0_default_active: u64,
+    pub num_ref_idx_l1_default_active: u64,
+    pub init_qp_minus26: i64,
+    pub constrained_intra_pred_flag: bool,
+    pub transform_skip_enabled_flag: bool,
+    pub cu_qp_delta_enabled_flag: bool,
+    pub diff_cu_qp_delta_depth: u64,
+    pub pps_cb_qp_offset: i64,
+    pub pps_cr_qp_offset: i64,
+    pub pps_slice_chroma_qp_offsets_present_flag: bool,
+    pub weighted_pred_flag: bool,
+    pub weighted_bipred_flag: bool,
+    pub transquant_bypass_enabled_flag: bool,
+    pub tiles_enabled_flag: bool,
+    pub entropy_coding_sync_enabled_flag: bool,
+    pub uniform_spacing_flag: bool,
+    pub num_tile_columns: u64,
+    pub num_tile_rows: u64,
+    /// Tile column widths in CTBs, also derived for uniform spacing
+    pub column_widths: Vec<u64>,
+    /// Tile row heights in CTBs, also derived for uniform spacing
+    pub row_heights: Vec<u64>,
+    pub loop_filter_across_tiles_enabled_flag: bool,
+    pub pps_loop_filter_across_slices_enabled_flag: bool,
+    pub deblocking_filter_override_enabled_flag: bool,
+    pub pps_deblocking_filter_disabled_flag: bool,
+    pub pps_beta_offset_div2: i64,
+    pub pps_tc_offset_div2: i64,
+    pub lists_modification_present_flag: bool,
+    pub log2_parallel_merge_level: u64,
+    pub slice_segment_header_extension_present_flag: bool,
+
+    // PPS range extension
+    pub log2_max_transform_skip_block_size: u64,
+    pub cross_component_prediction_enabled_flag: bool,
+    pub chroma_qp_offset_list_enabled_flag: bool,
+    pub diff_cu_chroma_qp_offset_depth: u64,
+    pub cb_qp_offset_list: Vec<i64>,
+    pub cr_qp_offset_list: Vec<i64>,
+    pub log2_sao_offset_scale_luma: u64,
+    pub log2_sao_offset_scale_chroma: u64,
+
+    // Slice header of the first slice
+    pub short_term_ref_pic_set_sps_flag: bool,
+    pub short_term_ref_pic_set_idx: u64,
+    /// Number of bits of the `st_ref_pic_set` in the slice header
+    pub st_rps_bits: u64,
+    /// NumDeltaPocs of the short term RPS coded in the slice header
+    pub num_delta_pocs_of_ref_rps_idx: u64,
+    pub num_long_term_sps: u64,
+    pub num_long_term_pics: u64,
+
+    /// Effective scaling lists, `None` when scaling lists are disabled
+    pub scaling_lists: Option<ScalingLists>,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct SliceParameters {
+    /// Location of the slice NAL in the processed chunk, as in `NALUnit`
+    pub nal_start: usize,
+    pub nal_end: usize,
+
+    /// Size of the slice NAL, including the 2 bytes NAL header
+    pub slice_data_size: usize,
+    /// Offset to the first byte of the slice data, from the start of the NAL header.
+    /// Accounts for the `emulation_prevention_three_byte`s in the header.
+    pub slice_data_byte_offset: usize,
+
+    pub first_slice_segment_in_pic_flag: bool,
+    pub dependent_slice_segment_flag: bool,
+    /// Last slice segment of the picture
+    pub last_slice_of_pic: bool,
+    pub slice_segment_address: u64,
+    pub slice_type: u64,
+    pub pic_output_flag: bool,
+    pub colour_plane_id: u8,
+
+    pub slice_sao_luma_flag: bool,
+    pub slice_sao_chroma_flag: bool,
+    pub slice_temporal_mvp_enabled_flag: bool,
+
+    pub num_ref_idx_l0_active: u64,
+    pub num_ref_idx_l1_active: u64,
+    /// RefPicList0
+    pub ref_pic_list0: Vec<RefPicListEntry>,
+    /// RefPicList1
+    pub ref_pic_list1: Vec<RefPicListEntry>,
+
+    pub mvd_l1_zero_flag: bool,
+    pub cabac_init_flag: bool,
+    pub collocated_from_l0_flag: bool,
+    pub collocated_ref_idx: u64,
+
+    pub pred_weight_table: Option<PredWeightTable>,
+
+    pub max_num_merge_cand: u64,
+    pub slice_qp_delta: i64,
+    pub slice_cb_qp_offset: i64,
+    pub slice_cr_qp_offset: i64,
+    pub cu_chroma_qp_offset_enabled_flag: bool,
+
+    pub deblocking_filter_override_flag: bool,
+    pub slice_deblocking_filter_disabled_flag: bool,
+    pub slice_beta_offset_div2: i64,
+    pub slice_tc_offset_div2: i64,
+    pub slice_loop_filter_across_slices_enabled_flag: bool,
+
+    pub num_entry_point_offsets: u64,
+    pub offset_len: u64,
+}
+
+/// Entry of a reference picture list
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct RefPicListEntry {
+    pub poc: i32,
+    pub long_term: bool,
+    /// Index in `PictureParameters::reference_frames`.
+    /// `None` when the reference picture is missing from the DPB.
+    pub reference_index: Option<usize>,
+}
+
+impl PictureParameters {
+    pub(crate) fn new(
+        sps: &SPSNAL,
+        pps: &PPSNAL,
+        slice: &SliceNAL,
+        nal: &NALUnit,
+        decoded_number: u64,
+    ) -> Self {
+        let sps_ext = &sps.range_extension;
+        let pps_ext = &pps.range_extension;
+
+        let (column_widths, row_heights) = tile_sizes(sps, pps);
+
+        let scaling_lists = sps.scaling_list_enabled_flag.then(|| {
+            if pps.scaling_list_data_present_flag {
+                pps.scaling_list_data.scaling_lists()
+            } else if sps.scaling_list_data_present_flag {
+                sps.scaling_list_data.scaling_lists()
+            } else {
+                ScalingLists::default_lists()
+            }
+        });
+
+        let st_rps = &slice.short_term_ref_pic_set;
+
+        Self {
+            decoded_number,
+            pic_order_cnt_val: slice.pic_order_cnt_val,
+            nal_type: nal.nal_type,
+
+            irap_pic_flag: nal.is_irap(),
+            idr_pic_flag: nal.is_idr(),
+            intra_pic_flag: slice.slice_type == SLICE_TYPE_I,
+            no_rasl_output_flag: false,
+            is_reference: !nal.is_sub_layer_non_reference(),
+
+            reference_frames: Vec::new(),
+            ref_pic_set_st_curr_before: Vec::new(),
+            ref_pic_set_st_curr_after: Vec::new(),
+            ref_pic_set_lt_curr: Vec::new(),
+
+            vps_id: sps.vps_id,
+            sps_id: sps.sps_id,
+            pps_id: pps.pps_id,
+
+            pic_width_in_luma_samples: sps.width,
+            pic_height_in_luma_samples: sps.height,
+            chroma_format_idc: if sps.separate_colour_plane_flag {
+                3
+            } else {
+                sps.chroma_format_idc
+            },
+            separate_colour_plane_flag: sps.separate_colour_plane_flag,
+            bit_depth_luma: sps.bit_depth,
+            bit_depth_chroma: sps.bit_depth_chroma,
+            log2_max_pic_order_cnt_lsb: sps.log2_max_poc_lsb,
+            sps_max_dec_pic_buffering: sps.max_dec_pic_buffering.last().copied().unwrap_or(0),
+            log2_min_luma_coding_block_size: sps.log2_min_cb_size,
+            log2_diff_max_min_luma_coding_block_size: sps.log2_diff_max_min_coding_block_size,
+            log2_min_transform_block_size: sps.log2_min_tb_size,
+            log2_diff_max_min_transform_block_size: sps.log2_diff_max_min_transform_block_size,
+            max_transform_hierarchy_depth_inter: sps.max_transform_hierarchy_depth_inter,
+            max_transform_hierarchy_depth_intra: sps.max_transform_hierarchy_depth_intra,
+            scaling_list_enabled_flag: sps.scaling_list_enabled_flag,
+            amp_enabled_flag: sps.amp_enabled_flag,
+            sample_adaptive_offset_enabled_flag: sps.sao_enabled_flag,
+            pcm_enabled_flag: sps.pcm_enabled_flag,
+            pcm_sample_bit_depth_luma: sps.pcm_bit_depth,
+            pcm_sample_bit_depth_chroma: sps.pcm_bit_depth_chroma,
+            log2_min_pcm_luma_coding_block_size: sps.pcm_log2_min_pcm_cb_size,
+            log2_max_pcm_luma_coding_block_size: sps.pcm_log2_max_pcm_cb_size,
+            pcm_loop_filter_disabled_flag: sps.pcm_loop_filter_disable_flag,
+            num_short_term_ref_pic_sets: sps.nb_st_rps,
+            long_term_ref_pics_present_flag: sps.long_term_ref_pics_present_flag,
+            num_long_term_ref_pics_sps: sps.num_long_term_ref_pics_sps,
+            sps_temporal_mvp_enabled_flag: sps.sps_temporal_mvp_enabled_flag,
+            strong_intra_smoothing_enabled_flag: sps.sps_strong_intra_smoothing_enable_flag,
+
+            transform_skip_rotation_enabled_flag: sps_ext.transform_skip_rotation_enabled_flag,
+            transform_skip_context_enabled_flag: sps_ext.transform_skip_context_enabled_flag,
+            implicit_rdpcm_enabled_flag: sps_ext.implicit_rdpcm_enabled_flag,
+            explicit_rdpcm_enabled_flag: sps_ext.explicit_rdpcm_enabled_flag,
+            extended_precision_processing_flag: sps_ext.extended_precision_processing_flag,
+            intra_smoothing_disabled_flag: sps_ext.intra_smoothing_disabled_flag,
+            high_precision_offsets_enabled_flag: sps_ext.high_precision_offsets_enabled_flag,
+            persistent_rice_adaptation_enabled_flag: sps_ext
+                .persistent_rice_adaptation_enabled_flag,
+            cabac_bypass_alignment_enabled_flag: sps_ext.cabac_bypass_alignment_enabled_flag,
+
+            dependent_slice_segments_enabled_flag: pps.dependent_slice_segments_enabled_flag,
+            output_flag_present_flag: pps.output_flag_present_flag,
+            num_extra_slice_header_bits: pps.num_extra_slice_header_bits,
+            sign_data_hiding_enabled_flag: pps.sign_data_hiding_flag,
+            cabac_init_present_flag: pps.cabac_init_present_flag,
+            num_ref_idx_l0_default_active: pps.num_ref_idx_l0_default_active,
+            num_ref_idx_l1_default_active: pps.num_ref_idx_l1_default_active,
+            init_qp_minus26: pps.pic_init_qp_minus26,
+            constrained_intra_pred_flag: pps.constrained_intra_pred_flag,
+            transform_skip_enabled_flag: pps.transform_skip_enabled_flag,
+            cu_qp_delta_enabled_flag: pps.cu_qp_delta_enabled_flag,
+            diff_cu_qp_delta_depth: pps.diff_cu_qp_delta_depth,
+            pps_cb_qp_offset: pps.cb_qp_offset,
+            pps_cr_qp_offset: pps.cr_qp_offset,
+            pps_slice_chroma_qp_offsets_present_flag: pps
+                .pic_slice_level_chroma_qp_offsets_present_flag,
+            weighted_pred_flag: pps.weighted_pred_flag,
+            weighted_bipred_flag: pps.weighted_bipred_flag,
+            transquant_bypass_enabled_flag: pps.transquant_bypass_enable_flag,
+            tiles_enabled_flag: pps.tiles_enabled_flag,
+            entropy_coding_sync_enabled_flag: pps.entropy_coding_sync_enabled_flag,
+            uniform_spacing_flag: pps.uniform_spacing_flag,
+            num_tile_columns: pps.num_tile_columns.max(1),
+            num_tile_rows: pps.num_tile_rows.max(1),
+            column_widths,
+            row_heights,
+            loop_filter_across_tiles_enabled_flag: pps.loop_filter_across_tiles_enabled_flag,
+            pps_loop_filter_across_slices_enabled_flag: pps
+                .seq_loop_filter_across_slices_enabled_flag,
+            deblocking_filter_override_enabled_flag: pps.deblocking_filter_override_enabled_flag,
+            pps_deblocking_filter_disabled_flag: pps.disable_dbf,
+            pps_beta_offset_div2: pps.beta_offset / 2,
+            pps_tc_offset_div2: pps.tc_offset / 2,
+            lists_modification_present_flag: pps.lists_modification_present_flag,
+            log2_parallel_merge_level: pps.log2_parallel_merge_level,
+            slice_segment_header_extension_present_flag: pps.slice_header_extension_present_flag,
+
+            log2_max_transform_skip_block_size: pps_ext.log2_max_transform_skip_block_size,
+            cross_component_prediction_enabled_flag: pps_ext
+                .cross_component_prediction_enabled_flag,
+            chroma_qp_offset_list_enabled_flag: pps_ext.chroma_qp_offset_list_enabled_flag,
+            diff_cu_chroma_qp_offset_depth: pps_ext.diff_cu_chroma_qp_offset_depth,
+            cb_qp_offset_list: pps_ext.cb_qp_offset_list.clone(),
+            cr_qp_offset_list: pps_ext.cr_qp_offset_list.clone(),
+            log2_sao_offset_scale_luma: pps_ext.log2_sao_offset_scale_luma,
+            log2_sao_offset_scale_chroma: pps_ext.log2_sao_offset_scale_chroma,
+
+            short_term_ref_pic_set_sps_flag: slice.short_term_ref_pic_set_sps_flag,
+            short_term_ref_pic_set_idx: slice.short_term_ref_pic_set_idx,
+            st_rps_bits: slice.st_rps_bits,
+            num_delta_pocs_of_ref_rps_idx: if slice.short_term_ref_pic_set_sps_flag {
+                0
+            } else {
+                st_rps.num_delta_pocs
+            },
+            num_long_term_sps: slice.num_long_term_sps,
+            num_long_term_pics: slice.num_long_term_pics,
+
+            scaling_lists,
+        }
+    }
+
+    /// Index of the reference picture with this POC in `reference_frames`
+    pub fn reference_index(&self, poc: i32) -> Option<usize> {
+        self.reference_frames.iter().position(|pic| pic.poc == poc)
+    }
+}
+
+impl SliceParameters {
+    pub(crate) fn new(slice: &SliceNAL, nal: &NALUnit, picture: &PictureParameters) -> Self {
+        let (ref_pic_list0, ref_pic_list1) = ref_pic_lists(slice, picture);
+
+        Self {
+            nal_start: nal.start,
+            nal_end: nal.end,
+            slice_data_size: nal.end - nal.start,
+            slice_data_byte_offset: 0,
+
+            first_slice_segment_in_pic_flag: slice.first_slice_in_pic_flag,
+            dependent_slice_segment_flag: slice.dependent_slice_segment_flag,
+            last_slice_of_pic: false,
+            slice_segment_address: slice.slice_segment_addr,
+            slice_type: slice.slice_type,
+            pic_output_flag: slice.pic_output_flag,
+            colour_plane_id: slice.colour_plane_id,
+
+            slice_sao_luma_flag: slice.slice_sao_luma_flag,
+            slice_sao_chroma_flag: slice.slice_sao_chroma_flag,
+            slice_temporal_mvp_enabled_flag: slice.slice_temporal_mvp_enabled_flag,
+
+            num_ref_idx_l0_active: slice.num_ref_idx_l0_active,
+            num_ref_idx_l1_active: slice.num_ref_idx_l1_active,
+            ref_pic_list0,
+            ref_pic_list1,
+
+            mvd_l1_zero_flag: slice.mvd_l1_zero_flag,
+            cabac_init_flag: slice.cabac_init_flag,
+            collocated_from_l0_flag: slice.collocated_from_l0_flag,
+            collocated_ref_idx: slice.collocated_ref_idx,
+
+            pred_weight_table: slice.pred_weight_table.as_deref().cloned(),
+
+            max_num_merge_cand: slice.max_num_merge_cand,
+            slice_qp_delta: slice.slice_qp_delta,
+            slice_cb_qp_offset: slice.slice_cb_qp_offset,
+            slice_cr_qp_offset: slice.slice_cr_qp_offset,
+            cu_chroma_qp_offset_enabled_flag: slice.cu_chroma_qp_offset_enabled_flag,
+
+            deblocking_filter_override_flag: slice.deblocking_filter_override_flag,
+            slice_deblocking_filter_disabled_flag: slice.slice_deblocking_filter_disabled_flag,
+            slice_beta_offset_div2: slice.slice_beta_offset / 2,
+            slice_tc_offset_div2: slice.slice_tc_offset / 2,
+            slice_loop_filter_across_slices_enabled_flag: slice
+                .slice_loop_filter_across_slices_enabled_flag,
+
+            num_entry_point_offsets: slice.num_entry_point_offsets,
+            offset_len: slice.offset_len,
+        }
+    }
+}
+
+/// Derives the tile column widths and row heights in CTBs, 6.5.1
+fn tile_sizes(sps: &SPSNAL, pps: &PPSNAL) -> (Vec<u64>, Vec<u64>) {
+    if !pps.tiles_enabled_flag {
+        return (vec![sps.ctb_width], vec![sps.ctb_height]);
+    }
+
+    let derive = |num: u64, total: u64, explicit: &[u64]| -> Vec<u64> {
+        if pps.uniform_spacing_flag {
+            (0..num)
+                .map(|i| ((i + 1) * total) / num - (i * total) / num)
+                .collect()
+        } else {
+            let mut sizes = explicit.to_vec();
+            sizes.push(total.saturating_sub(explicit.iter().sum()));
+
+            sizes
+        }
+    };
+
+    (
+        derive(pps.num_tile_columns, sps.ctb_width, &pps.column_widths),
+        derive(pps.num_tile_rows, sps.ctb_height, &pps.row_heights),
+    )
+}
+
+/// Construction process for the reference picture lists, 8.3.4
+fn ref_pic_lists(
+    slice: &SliceNAL,
+    picture: &PictureParameters,
+) -> (Vec<RefPicListEntry>, Vec<RefPicListEntry>) {
+    let entry = |poc: i32, long_term: bool| RefPicListEntry {
+        poc,
+        long_term,
+        reference_index: picture.reference_index(poc),
+    };
+
+    let st_before = picture
+        .ref_pic_set_st_curr_before
+        .iter()
+        .map(|poc| entry(*poc, false));
+    let st_after = picture
+        .ref_pic_set_st_curr_after
+        .iter()
+        .map(|poc| entry(*poc, false));
+    let lt = picture
+        .ref_pic_set_lt_curr
+        .iter()
+        .map(|poc| entry(*poc, true));
+
+    let num_pic_total_curr = picture.ref_pic_set_st_curr_before.len()
+        + picture.ref_pic_set_st_curr_after.len()
+        + picture.ref_pic_set_lt_curr.len();
+
+    if num_pic_total_curr == 0 || slice.slice_type == SLICE_TYPE_I {
+        return (Vec::new(), Vec::new());
+    }
+
+    let build_list = |temp: Vec<RefPicListEntry>,
+                      num_active: u64,
+                      modification: bool,
+                      list_entry: &[u64]|
+     -> Vec<RefPicListEntry> {
+        let num_rps_curr_temp_list = (num_active as usize).max(num_pic_total_curr);
+        let temp: Vec<RefPicListEntry> = temp
+            .into_iter()
+            .cycle()
+            .take(num_rps_curr_temp_list)
+            .collect();
+
+        (0..num_active as usize)
+            .map(|i| {
+                let idx = if modification {
+                    list_entry.get(i).copied().unwrap_or(0) as usize
+                } else {
+                    i
+                };
+
+                temp[idx.min(temp.len() - 1)]
+            })
+            .collect()
+    };
+
+    let temp0 = st_before
+        .clone()
+        .chain(st_after.clone())
+        .chain(lt.clone())
+        .collect();
+    let list0 = build_list(
+        temp0,
+        slice.num_ref_idx_l0_active,
+        slice.ref_pic_list_modification_flag_l0,
+        &slice.list_entry_l0,
+    );
+
+    let list1 = if slice.slice_type == SLICE_TYPE_B {
+        let temp1 = st_after.chain(st_before).chain(lt).collect();
+
+        build_list(
+            temp1,
+            slice.num_ref_idx_l1_active,
+            slice.ref_pic_list_modification_flag_l1,
+            &slice.list_entry_l1,
+        )
+    } else {
+        Vec::new()
+    };
+
+    (list0, list1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hevc::SLICE_TYPE_P;
+
+    /// RefPicSetStCurrBefore [8, 4], RefPicSetStCurrAfter [16] and RefPicSetLtCurr [0]
+    fn picture() -> PictureParameters {
+        PictureParameters {
+            reference_frames: [0, 4, 8, 16]
+                .iter()
+                .enumerate()
+                .map(|(i, poc)| ReferencePicture {
+                    poc: *poc,
+                    decoded_number: i as u64,
+                    long_term: *poc == 0,
+                })
+                .collect(),
+            ref_pic_set_st_curr_before: vec![8, 4],
+            ref_pic_set_st_curr_after: vec![16],
+            ref_pic_set_lt_curr: vec![0],
+            ..Default::default()
+        }
+    }
+
+    fn pocs(list: &[RefPicListEntry]) -> Vec<(i32, bool, Option<usize>)> {
+        list.iter()
+            .map(|e| (e.poc, e.long_term, e.reference_index))
+            .collect()
+    }
+
+    #[test]
+    fn default_lists() {
+        let mut slice = SliceNAL::default();
+        slice.slice_type = SLICE_TYPE_B;
+        slice.num_ref_idx_l0_active = 5;
+        slice.num_ref_idx_l1_active = 2;
+
+        let (list0, list1) = ref_pic_lists(&slice, &picture());
+
+        // The initial list is repeated when there are more active entries
+        assert_eq!(
+            pocs(&list0),
+            [
+                (8, false, Some(2)),
+                (4, false, Some(1)),
+                (16, false, Some(3)),
+                (0, true, Some(0)),
+                (8, false, Some(2)),
+            ]
+        );
+        assert_eq!(pocs(&list1), [(16, false, Some(3)), (8, false, Some(2))]);
+    }
+
+    #[test]
+    fn list_modification() {
+        let mut slice = SliceNAL::default();
+        slice.slice_type = SLICE_TYPE_B;
+        slice.num_ref_idx_l0_active = 3;
+        slice.ref_pic_list_modification_flag_l0 = true;
+        slice.list_entry_l0 = vec![3, 0, 2];
+        slice.num_ref_idx_l1_active = 2;
+        slice.ref_pic_list_modification_flag_l1 = true;
+        slice.list_entry_l1 = vec![1, 1];
+
+        let (list0, list1) = ref_pic_lists(&slice, &picture());
+
+        assert_eq!(
+            pocs(&list0),
+            [
+                (0, true, Some(0)),
+                (8, false, Some(2)),
+                (16, false, Some(3))
+            ]
+        );
+        assert_eq!(pocs(&list1), [(8, false, Some(2)), (8, false, Some(2))]);
+
+        // P slices don't have a second list
+        slice.slice_type = SLICE_TYPE_P;
+
+        let (list0, list1) = ref_pic_lists(&slice, &picture());
+        assert_eq!(list0.len(), 3);
+        assert!(list1.is_empty());
+    }
+}
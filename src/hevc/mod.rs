@@ -1,16 +1,27 @@
+use self::hwaccel::HwAccelParameters;
+use self::sei::{AlphaChannelInfo, PicTiming, RecoveryPoint};
 use self::slice::SliceNAL;
+use self::timing::FrameTimestamp;
 
 use super::{BsIoVecReader, NALUStartCode};
 
+pub mod captions;
 pub mod config;
+pub(crate) mod dpb;
+pub mod hdr_format;
 pub(crate) mod hrd_parameters;
+pub mod hwaccel;
 pub(crate) mod pps;
+pub(crate) mod pred_weight_table;
 pub(crate) mod profile_tier_level;
 pub(crate) mod scaling_list_data;
 pub mod sei;
 pub(crate) mod short_term_rps;
 pub(crate) mod slice;
 pub(crate) mod sps;
+pub mod stats;
+pub mod stream_info;
+pub mod timing;
 pub(crate) mod vps;
 pub(crate) mod vui_parameters;
 
@@ -44,9 +55,32 @@ pub const NAL_SEI_SUFFIX: u8 = 40;
 pub const NAL_UNSPEC62: u8 = 62;
 pub const NAL_UNSPEC63: u8 = 63;
 
-pub const USER_DATA_REGISTERED_ITU_T_35: u8 = 4;
+pub const BUFFERING_PERIOD: u32 = 0;
+pub const PIC_TIMING: u32 = 1;
+pub const USER_DATA_REGISTERED_ITU_T_35: u32 = 4;
+pub const USER_DATA_UNREGISTERED: u32 = 5;
+pub const RECOVERY_POINT: u32 = 6;
+pub const FILM_GRAIN_CHARACTERISTICS: u32 = 19;
+pub const FRAME_PACKING_ARRANGEMENT: u32 = 45;
+pub const SOP_DESCRIPTION: u32 = 128;
+pub const DECODING_UNIT_INFO: u32 = 130;
+pub const SCALABLE_NESTING: u32 = 133;
+pub const DECODED_PICTURE_HASH: u32 = 132;
+pub const TIME_CODE: u32 = 136;
+pub const MASTERING_DISPLAY_COLOUR_VOLUME: u32 = 137;
+pub const CONTENT_LIGHT_LEVEL_INFO: u32 = 144;
+pub const ALTERNATIVE_TRANSFER_CHARACTERISTICS: u32 = 147;
+pub const AMBIENT_VIEWING_ENVIRONMENT: u32 = 148;
+pub const CONTENT_COLOUR_VOLUME: u32 = 149;
+pub const EQUIRECTANGULAR_PROJECTION: u32 = 150;
+pub const CUBEMAP_PROJECTION: u32 = 151;
+pub const SPHERE_ROTATION: u32 = 154;
+pub const REGION_WISE_PACKING: u32 = 155;
+pub const ALPHA_CHANNEL_INFO: u32 = 165;
 
-pub use sei::SeiMessage;
+pub use sei::{SeiMessage, SeiPayload};
+pub use slice::{SLICE_TYPE_B, SLICE_TYPE_I, SLICE_TYPE_P, SliceInfo};
+pub use stream_info::StreamInfo;
 
 #[derive(Default, Debug, Clone)]
 pub struct NALUnit {
@@ -73,6 +107,36 @@ pub struct Frame {
 
     pub nals: Vec<NALUnit>,
     pub first_slice: SliceNAL,
+    /// Every slice segment of the frame, in decoding order
+    pub slices: Vec<SliceInfo>,
+
+    /// Set once the frame is in presentation order, if timing info is available
+    pub timestamp: Option<FrameTimestamp>,
+
+    /// Decoded SEI messages of the access unit
+    pub prefix_sei: Vec<SeiPayload>,
+    pub suffix_sei: Vec<SeiPayload>,
+
+    /// Only present when the SPS signals `frame_field_info_present_flag`
+    pub pic_timing: Option<PicTiming>,
+
+    /// NAL units of the layers above the base layer, such as an alpha auxiliary layer.
+    /// They are not part of `nals`, and only their SEI messages are decoded.
+    pub layer_nals: Vec<NALUnit>,
+    /// Decoded SEI messages of the layers above the base layer, prefix and suffix
+    pub layer_sei: Vec<SeiPayload>,
+
+    /// Only present when `HevcParser::export_hwaccel_params` is enabled,
+    /// and the slice headers of the picture could be fully parsed
+    pub hwaccel_params: Option<Box<HwAccelParameters>>,
+}
+
+/// Output frame, made of either a frame picture or two field pictures
+#[derive(Debug, Clone, Copy)]
+pub struct PairedFrame<'a> {
+    pub first: &'a Frame,
+    /// Second field, when the first picture is a field and its pair was found
+    pub second: Option<&'a Frame>,
 }
 
 impl NALUnit {
@@ -101,4 +165,73 @@ impl NALUnit {
     pub fn is_slice(&self) -> bool {
         Self::is_type_slice(self.nal_type)
     }
+
+    pub fn is_irap(&self) -> bool {
+        (NAL_BLA_W_LP..=NAL_IRAP_VCL23).contains(&self.nal_type)
+    }
+
+    pub fn is_idr(&self) -> bool {
+        matches!(self.nal_type, NAL_IDR_W_RADL | NAL_IDR_N_LP)
+    }
+
+    pub fn is_bla(&self) -> bool {
+        matches!(self.nal_type, NAL_BLA_W_LP | NAL_BLA_W_RADL | NAL_BLA_N_LP)
+    }
+
+    /// Sub-layer non-reference picture
+    pub fn is_sub_layer_non_reference(&self) -> bool {
+        self.nal_type <= NAL_RASL_R && self.nal_type % 2 == 0
+    }
+}
+
+impl Frame {
+    /// The picture is a single field, according to the pic_timing SEI
+    pub fn is_field(&self) -> bool {
+        self.pic_timing.as_ref().is_some_and(|pt| pt.is_field())
+    }
+
+    pub fn recovery_point(&self) -> Option<&RecoveryPoint> {
+        self.prefix_sei.iter().find_map(|sei| match sei {
+            SeiPayload::RecoveryPoint(recovery_point) => Some(recovery_point),
+            _ => None,
+        })
+    }
+
+    /// Alpha channel info of the access unit, from either the base or the auxiliary layer
+    pub fn alpha_channel_info(&self) -> Option<&AlphaChannelInfo> {
+        self.prefix_sei
+            .iter()
+            .chain(&self.layer_sei)
+            .find_map(|sei| match sei {
+                SeiPayload::AlphaChannelInfo(info) => Some(info),
+                _ => None,
+            })
+    }
+
+    /// Decoding can start at this frame, either an IRAP picture or one with a recovery point SEI
+    pub fn is_random_access_point(&self) -> bool {
+        self.first_slice.key_frame || self.recovery_point().is_some()
+    }
+
+    /// The frame is an IDR or BLA picture, starting a new CLVS.
+    /// Persistent SEI messages stop applying from there.
+    pub fn starts_clvs(&self) -> bool {
+        self.nals
+            .iter()
+            .find(|nal| nal.is_slice())
+            .is_some_and(|nal| nal.is_idr() || nal.is_bla())
+    }
+}
+
+impl PairedFrame<'_> {
+    /// Timestamp of the first field, lasting for both fields
+    pub fn timestamp(&self) -> Option<FrameTimestamp> {
+        let mut timestamp = self.first.timestamp?;
+
+        if let Some(second) = self.second.and_then(|f| f.timestamp) {
+            timestamp.duration += second.duration;
+        }
+
+        Some(timestamp)
+    }
 }
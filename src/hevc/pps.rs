@@ -9,46 +9,64 @@ pub struct PPSNAL {
     pub(crate) dependent_slice_segments_enabled_flag: bool,
     pub(crate) output_flag_present_flag: bool,
     pub(crate) num_extra_slice_header_bits: u8,
-    sign_data_hiding_flag: bool,
-    cabac_init_present_flag: bool,
-    num_ref_idx_l0_default_active: u64,
-    num_ref_idx_l1_default_active: u64,
-    pic_init_qp_minus26: i64,
-    constrained_intra_pred_flag: bool,
-    transform_skip_enabled_flag: bool,
-    cu_qp_delta_enabled_flag: bool,
-    diff_cu_qp_delta_depth: u64,
-    cb_qp_offset: i64,
-    cr_qp_offset: i64,
-    pic_slice_level_chroma_qp_offsets_present_flag: bool,
-    weighted_pred_flag: bool,
-    weighted_bipred_flag: bool,
-    transquant_bypass_enable_flag: bool,
-    tiles_enabled_flag: bool,
-    entropy_coding_sync_enabled_flag: bool,
-
-    num_tile_columns: u64,
-    num_tile_rows: u64,
-    uniform_spacing_flag: bool,
-
-    column_widths: Vec<u64>,
-    row_heights: Vec<u64>,
-
-    loop_filter_across_tiles_enabled_flag: bool,
-    seq_loop_filter_across_slices_enabled_flag: bool,
-    deblocking_filter_control_present_flag: bool,
-    deblocking_filter_override_enabled_flag: bool,
-    disable_dbf: bool,
-    beta_offset: i64,
-    tc_offset: i64,
-
-    scaling_list_data_present_flag: bool,
-    scaling_list_data: ScalingListData,
-
-    lists_modification_present_flag: bool,
-    log2_parallel_merge_level: u64,
-    slice_header_extension_present_flag: bool,
-    pps_extension_present_flag: bool,
+    pub(crate) sign_data_hiding_flag: bool,
+    pub(crate) cabac_init_present_flag: bool,
+    pub(crate) num_ref_idx_l0_default_active: u64,
+    pub(crate) num_ref_idx_l1_default_active: u64,
+    pub(crate) pic_init_qp_minus26: i64,
+    pub(crate) constrained_intra_pred_flag: bool,
+    pub(crate) transform_skip_enabled_flag: bool,
+    pub(crate) cu_qp_delta_enabled_flag: bool,
+    pub(crate) diff_cu_qp_delta_depth: u64,
+    pub(crate) cb_qp_offset: i64,
+    pub(crate) cr_qp_offset: i64,
+    pub(crate) pic_slice_level_chroma_qp_offsets_present_flag: bool,
+    pub(crate) weighted_pred_flag: bool,
+    pub(crate) weighted_bipred_flag: bool,
+    pub(crate) transquant_bypass_enable_flag: bool,
+    pub(crate) tiles_enabled_flag: bool,
+    pub(crate) entropy_coding_sync_enabled_flag: bool,
+
+    pub(crate) num_tile_columns: u64,
+    pub(crate) num_tile_rows: u64,
+    pub(crate) uniform_spacing_flag: bool,
+
+    pub(crate) column_widths: Vec<u64>,
+    pub(crate) row_heights: Vec<u64>,
+
+    pub(crate) loop_filter_across_tiles_enabled_flag: bool,
+    pub(crate) seq_loop_filter_across_slices_enabled_flag: bool,
+    pub(crate) deblocking_filter_control_present_flag: bool,
+    pub(crate) deblocking_filter_override_enabled_flag: bool,
+    pub(crate) disable_dbf: bool,
+    pub(crate) beta_offset: i64,
+    pub(crate) tc_offset: i64,
+
+    pub(crate) scaling_list_data_present_flag: bool,
+    pub(crate) scaling_list_data: ScalingListData,
+
+    pub(crate) lists_modification_present_flag: bool,
+    pub(crate) log2_parallel_merge_level: u64,
+    pub(crate) slice_header_extension_present_flag: bool,
+    pub(crate) pps_extension_present_flag: bool,
+    pps_range_extension_flag: bool,
+    pps_multilayer_extension_flag: bool,
+    pps_3d_extension_flag: bool,
+    pps_scc_extension_flag: bool,
+    pub(crate) range_extension: PpsRangeExtension,
+}
+
+#[derive(Default, Debug, PartialEq, Eq)]
+pub(crate) struct PpsRangeExtension {
+    pub(crate) log2_max_transform_skip_block_size: u64,
+    pub(crate) cross_component_prediction_enabled_flag: bool,
+    pub(crate) chroma_qp_offset_list_enabled_flag: bool,
+    pub(crate) diff_cu_chroma_qp_offset_depth: u64,
+    pub(crate) chroma_qp_offset_list_len: u64,
+    pub(crate) cb_qp_offset_list: Vec<i64>,
+    pub(crate) cr_qp_offset_list: Vec<i64>,
+    pub(crate) log2_sao_offset_scale_luma: u64,
+    pub(crate) log2_sao_offset_scale_chroma: u64,
 }
 
 impl PPSNAL {
@@ -131,6 +149,50 @@ impl PPSNAL {
         pps.slice_header_extension_present_flag = bs.read_bit()?;
         pps.pps_extension_present_flag = bs.read_bit()?;
 
+        if pps.pps_extension_present_flag {
+            pps.pps_range_extension_flag = bs.read_bit()?;
+            pps.pps_multilayer_extension_flag = bs.read_bit()?;
+            pps.pps_3d_extension_flag = bs.read_bit()?;
+            pps.pps_scc_extension_flag = bs.read_bit()?;
+            bs.skip_n(4)?; // pps_extension_4bits
+
+            if pps.pps_range_extension_flag {
+                pps.range_extension =
+                    PpsRangeExtension::parse(bs, pps.transform_skip_enabled_flag)?;
+            }
+        }
+
         Ok(pps)
     }
 }
+
+impl PpsRangeExtension {
+    pub fn parse(
+        bs: &mut BsIoVecReader,
+        transform_skip_enabled_flag: bool,
+    ) -> Result<PpsRangeExtension> {
+        let mut ext = PpsRangeExtension::default();
+
+        if transform_skip_enabled_flag {
+            ext.log2_max_transform_skip_block_size = bs.read_ue()? + 2;
+        }
+
+        ext.cross_component_prediction_enabled_flag = bs.read_bit()?;
+        ext.chroma_qp_offset_list_enabled_flag = bs.read_bit()?;
+
+        if ext.chroma_qp_offset_list_enabled_flag {
+            ext.diff_cu_chroma_qp_offset_depth = bs.read_ue()?;
+            ext.chroma_qp_offset_list_len = bs.read_ue()? + 1;
+
+            for _ in 0..ext.chroma_qp_offset_list_len {
+                ext.cb_qp_offset_list.push(bs.read_se()?);
+                ext.cr_qp_offset_list.push(bs.read_se()?);
+            }
+        }
+
+        ext.log2_sao_offset_scale_luma = bs.read_ue()?;
+        ext.log2_sao_offset_scale_chroma = bs.read_ue()?;
+
+        Ok(ext)
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::{Result, ensure};
+
+use super::BsIoVecReader;
+use super::sps::SPSNAL;
+
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct PredWeightTable {
+    pub luma_log2_weight_denom: u64,
+    pub delta_chroma_log2_weight_denom: i64,
+
+    pub l0: Vec<PredWeight>,
+    pub l1: Vec<PredWeight>,
+
+    // Computed values
+    pub chroma_log2_weight_denom: i64,
+}
+
+/// Weights for a single reference index
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct PredWeight {
+    pub luma_weight_flag: bool,
+    pub chroma_weight_flag: bool,
+    pub delta_luma_weight: i64,
+    pub luma_offset: i64,
+    pub delta_chroma_weight: [i64; 2],
+    pub delta_chroma_offset: [i64; 2],
+
+    // Computed values, LumaWeightLX, ChromaWeightLX and ChromaOffsetLX
+    pub luma_weight: i64,
+    pub chroma_weight: [i64; 2],
+    pub chroma_offset: [i64; 2],
+}
+
+impl PredWeightTable {
+    pub fn parse(
+        bs: &mut BsIoVecReader,
+        sps: &SPSNAL,
+        num_ref_idx_l0_active: u64,
+        num_ref_idx_l1_active: u64,
+        is_b_slice: bool,
+    ) -> Result<PredWeightTable> {
+        let chroma = sps.chroma_array_type() != 0;
+
+        let mut pwt = PredWeightTable {
+            luma_log2_weight_denom: bs.read_ue()?,
+            ..Default::default()
+        };
+
+        if chroma {
+            pwt.delta_chroma_log2_weight_denom = bs.read_se()?;
+        }
+
+        ensure!(
+            pwt.luma_log2_weight_denom <= 7,
+            "Invalid luma_log2_weight_denom {}",
+            pwt.luma_log2_weight_denom
+        );
+
+        pwt.chroma_log2_weight_denom =
+            pwt.luma_log2_weight_denom as i64 + pwt.delta_chroma_log2_weight_denom;
+
+        ensure!(
+            (0..=7).contains(&pwt.chroma_log2_weight_denom),
+            "Invalid chroma_log2_weight_denom {}",
+            pwt.chroma_log2_weight_denom
+        );
+
+        pwt.l0 = pwt.parse_list(bs, sps, num_ref_idx_l0_active)?;
+
+        if is_b_slice {
+            pwt.l1 = pwt.parse_list(bs, sps, num_ref_idx_l1_active)?;
+        }
+
+        Ok(pwt)
+    }
+
+    fn parse_list(
+        &self,
+        bs: &mut BsIoVecReader,
+        sps: &SPSNAL,
+        num_ref_idx_active: u64,
+    ) -> Result<Vec<PredWeight>> {
+        let chroma = sps.chroma_array_type() != 0;
+        let mut weights = vec![PredWeight::default(); num_ref_idx_active as usize];
+
+        for w in weights.iter_mut() {
+            w.luma_weight_flag = bs.read_bit()?;
+        }
+
+        if chroma {
+            for w in weights.iter_mut() {
+                w.chroma_weight_flag = bs.read_bit()?;
+            }
+        }
+
+        let wp_offset_half_range_c: i64 = if sps.range_extension.high_precision_offsets_enabled_flag
+        {
+            1 << (sps.bit_depth_chroma - 1)
+        } else {
+            1 << 7
+        };
+
+        for w in weights.iter_mut() {
+            if w.luma_weight_flag {
+                w.delta_luma_weight = bs.read_se()?;
+                w.luma_offset = bs.read_se()?;
+            }
+
+            if w.chroma_weight_flag {
+                for j in 0..2 {
+                    w.delta_chroma_weight[j] = bs.read_se()?;
+                    w.delta_chroma_offset[j] = bs.read_se()?;
+                }
+            }
+
+            w.luma_weight = (1 << self.luma_log2_weight_denom) + w.delta_luma_weight;
+
+            for j in 0..2 {
+                w.chroma_weight[j] =
+                    (1 << self.chroma_log2_weight_denom) + w.delta_chroma_weight[j];
+
+                w.chroma_offset[j] = (wp_offset_half_range_c
+                    - ((wp_offset_half_range_c * w.chroma_weight[j])
+                        >> self.chroma_log2_weight_denom)
+                    + w.delta_chroma_offset[j])
+                    .clamp(-wp_offset_half_range_c, wp_offset_half_range_c - 1);
+            }
+        }
+
+        Ok(weights)
+    }
+}
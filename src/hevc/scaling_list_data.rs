@@ -3,6 +3,18 @@ use std::cmp::min;
 
 use super::BsIoVecReader;
 
+// Table 7-6, in up-right diagonal scan order
+const DEFAULT_SCALING_LIST_INTRA: [u8; 64] = [
+    16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 17, 16, 17, 16, 17, 18, 17, 18, 18, 17, 18, 21, 19, 20,
+    21, 20, 19, 21, 24, 22, 22, 24, 24, 22, 22, 24, 25, 25, 27, 30, 27, 25, 25, 29, 31, 35, 35, 31,
+    29, 36, 41, 44, 41, 36, 47, 54, 54, 47, 65, 70, 65, 88, 88, 115,
+];
+const DEFAULT_SCALING_LIST_INTER: [u8; 64] = [
+    16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 17, 17, 17, 17, 17, 18, 18, 18, 18, 18, 18, 20, 20, 20,
+    20, 20, 20, 20, 24, 24, 24, 24, 24, 24, 24, 24, 25, 25, 25, 25, 25, 25, 25, 28, 28, 28, 28, 28,
+    28, 33, 33, 33, 33, 33, 41, 41, 41, 41, 54, 54, 54, 71, 71, 91,
+];
+
 #[derive(Default, Debug, PartialEq, Clone, Eq)]
 pub struct ScalingListData {
     scaling_list_pred_mode_flag: Vec<Vec<bool>>,
@@ -55,4 +67,155 @@ impl ScalingListData {
 
         Ok(scl)
     }
+
+    /// Derives the `ScalingList` values, 7.4.5
+    pub fn scaling_lists(&self) -> ScalingLists {
+        let mut lists = ScalingLists::default();
+
+        for size_id in 0..4 {
+            let matrix_size = if size_id == 3 { 2 } else { 6 };
+            let coef_num = min(64, 1 << (4 + (size_id << 1)));
+
+            for matrix_id in 0..matrix_size {
+                let mut list = [16; 64];
+                let mut dc = 16;
+
+                if !self.scaling_list_pred_mode_flag[size_id][matrix_id] {
+                    let delta = self.scaling_list_pred_matrix_id_delta[size_id][matrix_id] as usize;
+
+                    if delta == 0 {
+                        list = ScalingLists::default_list(size_id, matrix_id);
+                    } else if let Some(ref_matrix_id) = matrix_id.checked_sub(delta) {
+                        list = lists.list(size_id, ref_matrix_id);
+
+                        if size_id > 1 {
+                            dc = lists.dc(size_id, ref_matrix_id);
+                        }
+                    }
+                } else {
+                    let mut next_coef = 8;
+
+                    if size_id > 1 {
+                        next_coef = self.scaling_list_dc_coef_minus8[size_id - 2][matrix_id] + 8;
+                        dc = next_coef as u8;
+                    }
+
+                    for (i, value) in list.iter_mut().take(coef_num).enumerate() {
+                        let delta_coef = self.scaling_list_delta_coef[size_id][matrix_id][i];
+                        next_coef = (next_coef + delta_coef + 256).rem_euclid(256);
+
+                        *value = next_coef as u8;
+                    }
+                }
+
+                lists.set(size_id, matrix_id, list, dc);
+            }
+        }
+
+        lists
+    }
+}
+
+/// Scaling lists in coded (up-right diagonal) order, indexed by `matrixId`.
+/// The 32x32 lists only have the two luma matrices.
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub struct ScalingLists {
+    pub scaling_list_4x4: [[u8; 16]; 6],
+    pub scaling_list_8x8: [[u8; 64]; 6],
+    pub scaling_list_16x16: [[u8; 64]; 6],
+    pub scaling_list_32x32: [[u8; 64]; 2],
+    pub scaling_list_dc_16x16: [u8; 6],
+    pub scaling_list_dc_32x32: [u8; 2],
+}
+
+impl ScalingLists {
+    /// Default lists, when scaling lists are enabled but not present in the SPS or PPS
+    pub fn default_lists() -> ScalingLists {
+        let mut lists = ScalingLists::default();
+
+        for size_id in 0..4 {
+            let matrix_size = if size_id == 3 { 2 } else { 6 };
+
+            for matrix_id in 0..matrix_size {
+                lists.set(
+                    size_id,
+                    matrix_id,
+                    Self::default_list(size_id, matrix_id),
+                    16,
+                );
+            }
+        }
+
+        lists
+    }
+
+    fn default_list(size_id: usize, matrix_id: usize) -> [u8; 64] {
+        // 32x32 only has matrixId 0 (intra) and 1 (inter)
+        let intra = if size_id == 3 {
+            matrix_id == 0
+        } else {
+            matrix_id < 3
+        };
+
+        if size_id == 0 {
+            [16; 64]
+        } else if intra {
+            DEFAULT_SCALING_LIST_INTRA
+        } else {
+            DEFAULT_SCALING_LIST_INTER
+        }
+    }
+
+    fn list(&self, size_id: usize, matrix_id: usize) -> [u8; 64] {
+        match size_id {
+            0 => {
+                let mut list = [16; 64];
+                list[..16].copy_from_slice(&self.scaling_list_4x4[matrix_id]);
+
+                list
+            }
+            1 => self.scaling_list_8x8[matrix_id],
+            2 => self.scaling_list_16x16[matrix_id],
+            3 => self.scaling_list_32x32[matrix_id],
+            _ => unreachable!(),
+        }
+    }
+
+    fn dc(&self, size_id: usize, matrix_id: usize) -> u8 {
+        if size_id == 2 {
+            self.scaling_list_dc_16x16[matrix_id]
+        } else {
+            self.scaling_list_dc_32x32[matrix_id]
+        }
+    }
+
+    fn set(&mut self, size_id: usize, matrix_id: usize, list: [u8; 64], dc: u8) {
+        match size_id {
+            0 => self.scaling_list_4x4[matrix_id].copy_from_slice(&list[..16]),
+            1 => self.scaling_list_8x8[matrix_id] = list,
+            2 => {
+                self.scaling_list_16x16[matrix_id] = list;
+                self.scaling_list_dc_16x16[matrix_id] = dc;
+            }
+            3 => {
+                self.scaling_list_32x32[matrix_id] = list;
+                self.scaling_list_dc_32x32[matrix_id] = dc;
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Flat lists, all values are 16
+impl Default for ScalingLists {
+    fn default() -> Self {
+        Self {
+            scaling_list_4x4: [[16; 16]; 6],
+            scaling_list_8x8: [[16; 64]; 6],
+            scaling_list_16x16: [[16; 64]; 6],
+            scaling_list_32x32: [[16; 64]; 2],
+            scaling_list_dc_16x16: [16; 6],
+            scaling_list_dc_32x32: [16; 2],
+        }
+    }
 }
@@ -0,0 +1,269 @@
+use anyhow::{Result, ensure};
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+pub const ATSC_COUNTRY_CODE: u8 = 0xB5;
+pub const ATSC_PROVIDER_CODE: u16 = 0x0031;
+
+/// "GA94"
+pub const GA94_USER_IDENTIFIER: u32 = 0x4741_3934;
+pub const CC_DATA_USER_DATA_TYPE_CODE: u8 = 0x03;
+pub const BAR_DATA_USER_DATA_TYPE_CODE: u8 = 0x06;
+
+/// "DTG1"
+pub const AFD_USER_IDENTIFIER: u32 = 0x4454_4731;
+
+pub const CC_TYPE_NTSC_FIELD_1: u8 = 0;
+pub const CC_TYPE_NTSC_FIELD_2: u8 = 1;
+pub const CC_TYPE_DTVCC_PACKET_DATA: u8 = 2;
+pub const CC_TYPE_DTVCC_PACKET_START: u8 = 3;
+
+/// ATSC A/53 cc_data, carried in user_data_registered_itu_t_t35
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct CcData {
+    pub process_em_data_flag: bool,
+    pub process_cc_data_flag: bool,
+    pub additional_data_flag: bool,
+    pub em_data: u8,
+
+    pub constructs: Vec<CcConstruct>,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct CcConstruct {
+    pub cc_valid: bool,
+    pub cc_type: u8,
+    pub cc_data_1: u8,
+    pub cc_data_2: u8,
+}
+
+/// Active format description, ETSI TS 101 154 annex B
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct AfdData {
+    pub active_format: Option<u8>,
+}
+
+/// ATSC A/53 bar_data.
+/// The values are line and pixel numbers of the decoded picture, in luma samples.
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct BarData {
+    pub line_number_end_of_top_bar: Option<u16>,
+    pub line_number_start_of_bottom_bar: Option<u16>,
+    pub pixel_number_end_of_left_bar: Option<u16>,
+    pub pixel_number_start_of_right_bar: Option<u16>,
+}
+
+/// Sizes of the bars, in luma samples
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct BarSizes {
+    pub top: u64,
+    pub bottom: u64,
+    pub left: u64,
+    pub right: u64,
+}
+
+/// User identifier of ATSC user data, following the provider code
+pub fn atsc_user_identifier(country_code: u8, data: &[u8]) -> Option<u32> {
+    match data {
+        [0x00, 0x31, a, b, c, d, ..] if country_code == ATSC_COUNTRY_CODE => {
+            Some(u32::from_be_bytes([*a, *b, *c, *d]))
+        }
+        _ => None,
+    }
+}
+
+impl CcData {
+    pub fn is_cc_data(country_code: u8, data: &[u8]) -> bool {
+        atsc_user_identifier(country_code, data) == Some(GA94_USER_IDENTIFIER)
+            && data.get(6) == Some(&CC_DATA_USER_DATA_TYPE_CODE)
+    }
+
+    /// Parses the T.35 data following the country code
+    pub fn parse(data: &[u8]) -> Result<CcData> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        reader.skip_n(16)?; // itu_t_t35_provider_code
+
+        let user_identifier = reader.read::<32, u32>()?;
+        let user_data_type_code = reader.read::<8, u8>()?;
+
+        ensure!(
+            user_identifier == GA94_USER_IDENTIFIER
+                && user_data_type_code == CC_DATA_USER_DATA_TYPE_CODE,
+            "Not ATSC cc_data"
+        );
+
+        let mut cc_data = CcData {
+            process_em_data_flag: reader.read_bit()?,
+            process_cc_data_flag: reader.read_bit()?,
+            additional_data_flag: reader.read_bit()?,
+            ..Default::default()
+        };
+
+        let cc_count = reader.read::<5, u8>()?;
+        cc_data.em_data = reader.read::<8, u8>()?;
+
+        for _ in 0..cc_count {
+            reader.skip_n(5)?; // marker_bits
+
+            cc_data.constructs.push(CcConstruct {
+                cc_valid: reader.read_bit()?,
+                cc_type: reader.read::<2, u8>()?,
+                cc_data_1: reader.read::<8, u8>()?,
+                cc_data_2: reader.read::<8, u8>()?,
+            });
+        }
+
+        Ok(cc_data)
+    }
+}
+
+impl AfdData {
+    pub fn is_afd_data(country_code: u8, data: &[u8]) -> bool {
+        atsc_user_identifier(country_code, data) == Some(AFD_USER_IDENTIFIER)
+    }
+
+    /// Parses the T.35 data following the country code
+    pub fn parse(data: &[u8]) -> Result<AfdData> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        reader.skip_n(16)?; // itu_t_t35_provider_code
+
+        let user_identifier = reader.read::<32, u32>()?;
+        ensure!(user_identifier == AFD_USER_IDENTIFIER, "Not AFD data");
+
+        reader.skip_n(1)?; // zero_bit
+        let active_format_flag = reader.read_bit()?;
+        reader.skip_n(6)?; // alignment_bits
+
+        let active_format = if active_format_flag {
+            reader.skip_n(4)?; // reserved
+            Some(reader.read::<4, u8>()?)
+        } else {
+            None
+        };
+
+        Ok(AfdData { active_format })
+    }
+}
+
+impl BarData {
+    pub fn is_bar_data(country_code: u8, data: &[u8]) -> bool {
+        atsc_user_identifier(country_code, data) == Some(GA94_USER_IDENTIFIER)
+            && data.get(6) == Some(&BAR_DATA_USER_DATA_TYPE_CODE)
+    }
+
+    /// Parses the T.35 data following the country code
+    pub fn parse(data: &[u8]) -> Result<BarData> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        reader.skip_n(16)?; // itu_t_t35_provider_code
+
+        let user_identifier = reader.read::<32, u32>()?;
+        let user_data_type_code = reader.read::<8, u8>()?;
+
+        ensure!(
+            user_identifier == GA94_USER_IDENTIFIER
+                && user_data_type_code == BAR_DATA_USER_DATA_TYPE_CODE,
+            "Not ATSC bar_data"
+        );
+
+        // top, bottom, left and right bar flags
+        let flags = [
+            reader.read_bit()?,
+            reader.read_bit()?,
+            reader.read_bit()?,
+            reader.read_bit()?,
+        ];
+        reader.skip_n(4)?; // reserved
+
+        let mut values = [None; 4];
+        for (value, flag) in values.iter_mut().zip(flags) {
+            if flag {
+                reader.skip_n(2)?; // marker_bits
+                *value = Some(reader.read::<14, u16>()?);
+            }
+        }
+
+        let [top, bottom, left, right] = values;
+
+        Ok(BarData {
+            line_number_end_of_top_bar: top,
+            line_number_start_of_bottom_bar: bottom,
+            pixel_number_end_of_left_bar: left,
+            pixel_number_start_of_right_bar: right,
+        })
+    }
+
+    /// Bar sizes for a decoded picture of `width` by `height` luma samples
+    pub fn bar_sizes(&self, width: u64, height: u64) -> BarSizes {
+        BarSizes {
+            top: self
+                .line_number_end_of_top_bar
+                .map_or(0, |end| (end as u64 + 1).min(height)),
+            bottom: self
+                .line_number_start_of_bottom_bar
+                .map_or(0, |start| height.saturating_sub(start as u64)),
+            left: self
+                .pixel_number_end_of_left_bar
+                .map_or(0, |end| (end as u64 + 1).min(width)),
+            right: self
+                .pixel_number_start_of_right_bar
+                .map_or(0, |start| width.saturating_sub(start as u64)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GA94: [u8; 6] = [0x00, 0x31, 0x47, 0x41, 0x39, 0x34];
+
+    #[test]
+    fn afd_data() -> Result<()> {
+        // 16:9 (8) then no active_format
+        let data = [0x00, 0x31, 0x44, 0x54, 0x47, 0x31, 0x41, 0xF8];
+        assert!(AfdData::is_afd_data(ATSC_COUNTRY_CODE, &data));
+        assert!(!AfdData::is_afd_data(0x26, &data));
+        assert_eq!(AfdData::parse(&data)?.active_format, Some(8));
+
+        let data = [0x00, 0x31, 0x44, 0x54, 0x47, 0x31, 0x01];
+        assert_eq!(AfdData::parse(&data)?.active_format, None);
+
+        assert!(AfdData::parse(&[GA94.as_slice(), &[0x41, 0xF8]].concat()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn pillarbox_bar_data() -> Result<()> {
+        // Left bar ending at 239, right bar starting at 1680
+        let data = [GA94.as_slice(), &[0x06, 0x30, 0xC0, 0xEF, 0xC6, 0x90]].concat();
+        assert!(BarData::is_bar_data(ATSC_COUNTRY_CODE, &data));
+        assert!(!CcData::is_cc_data(ATSC_COUNTRY_CODE, &data));
+
+        let bar_data = BarData::parse(&data)?;
+        assert_eq!(
+            bar_data,
+            BarData {
+                pixel_number_end_of_left_bar: Some(239),
+                pixel_number_start_of_right_bar: Some(1680),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            bar_data.bar_sizes(1920, 1080),
+            BarSizes {
+                left: 240,
+                right: 240,
+                ..Default::default()
+            }
+        );
+
+        // Bars larger than the picture
+        assert_eq!(bar_data.bar_sizes(200, 1080).left, 200);
+        assert_eq!(bar_data.bar_sizes(1280, 720).right, 0);
+
+        Ok(())
+    }
+}
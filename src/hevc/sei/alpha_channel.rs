@@ -0,0 +1,102 @@
+use anyhow::Result;
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+/// alpha_channel_info, F.14.2.8.
+/// Describes the alpha auxiliary pictures, usually in the layer with `nuh_layer_id` 1.
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct AlphaChannelInfo {
+    /// Cancels the persistence of the previous alpha channel info, nothing else is present
+    pub alpha_channel_cancel_flag: bool,
+
+    /// 0: straight alpha, 1: premultiplied alpha, 2: unspecified
+    pub alpha_channel_use_idc: u8,
+    pub alpha_channel_bit_depth_minus8: u8,
+    pub alpha_transparent_value: u16,
+    pub alpha_opaque_value: u16,
+    pub alpha_channel_incr_flag: bool,
+    pub alpha_channel_clip_flag: bool,
+    pub alpha_channel_clip_type_flag: bool,
+}
+
+impl AlphaChannelInfo {
+    pub fn parse(data: &[u8]) -> Result<AlphaChannelInfo> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        let mut info = AlphaChannelInfo {
+            alpha_channel_cancel_flag: reader.read_bit()?,
+            ..Default::default()
+        };
+
+        if !info.alpha_channel_cancel_flag {
+            info.alpha_channel_use_idc = reader.read::<3, u8>()?;
+            info.alpha_channel_bit_depth_minus8 = reader.read::<3, u8>()?;
+
+            let bits = info.alpha_channel_bit_depth_minus8 as u32 + 9;
+            info.alpha_transparent_value = reader.read_var(bits)?;
+            info.alpha_opaque_value = reader.read_var(bits)?;
+
+            info.alpha_channel_incr_flag = reader.read_bit()?;
+            info.alpha_channel_clip_flag = reader.read_bit()?;
+
+            if info.alpha_channel_clip_flag {
+                info.alpha_channel_clip_type_flag = reader.read_bit()?;
+            }
+        }
+
+        Ok(info)
+    }
+
+    pub fn bit_depth(&self) -> u8 {
+        self.alpha_channel_bit_depth_minus8 + 8
+    }
+
+    /// The colour samples are already multiplied by the alpha
+    pub fn is_premultiplied(&self) -> bool {
+        self.alpha_channel_use_idc == 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hevc::{Frame, SeiPayload};
+
+    #[test]
+    fn parse() -> Result<()> {
+        // Premultiplied 8 bits alpha, clipped
+        let info = AlphaChannelInfo::parse(&[0x10, 0x00, 0x7F, 0xB0])?;
+        assert!(info.is_premultiplied());
+        assert_eq!(info.bit_depth(), 8);
+        assert_eq!(
+            (info.alpha_transparent_value, info.alpha_opaque_value),
+            (0, 255)
+        );
+        assert!(!info.alpha_channel_incr_flag);
+        assert!(info.alpha_channel_clip_flag && info.alpha_channel_clip_type_flag);
+
+        // Straight 10 bits alpha
+        let info = AlphaChannelInfo::parse(&[0x04, 0x00, 0x1F, 0xF8])?;
+        assert!(!info.is_premultiplied());
+        assert_eq!(info.bit_depth(), 10);
+        assert_eq!(info.alpha_opaque_value, 1023);
+        assert!(!info.alpha_channel_clip_flag);
+
+        assert!(AlphaChannelInfo::parse(&[0x80])?.alpha_channel_cancel_flag);
+        assert!(AlphaChannelInfo::parse(&[0x10, 0x00]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn auxiliary_layer_sei() -> Result<()> {
+        let info = AlphaChannelInfo::parse(&[0x10, 0x00, 0x7F, 0xB0])?;
+
+        let frame = Frame {
+            layer_sei: vec![SeiPayload::AlphaChannelInfo(info)],
+            ..Default::default()
+        };
+        assert_eq!(frame.alpha_channel_info(), Some(&info));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use anyhow::Result;
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+/// alternative_transfer_characteristics, D.2.38.
+/// Transfer characteristics preferred over the VUI one, e.g. 18 (HLG) with a VUI of 14 (BT.2020).
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct AlternativeTransferCharacteristics {
+    pub preferred_transfer_characteristics: u8,
+}
+
+impl AlternativeTransferCharacteristics {
+    pub fn parse(data: &[u8]) -> Result<AlternativeTransferCharacteristics> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        Ok(AlternativeTransferCharacteristics {
+            preferred_transfer_characteristics: reader.read::<8, u8>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() -> Result<()> {
+        let atc = AlternativeTransferCharacteristics::parse(&[18])?;
+        assert_eq!(atc.preferred_transfer_characteristics, 18);
+
+        assert!(AlternativeTransferCharacteristics::parse(&[]).is_err());
+
+        Ok(())
+    }
+}
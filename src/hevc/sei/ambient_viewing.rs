@@ -0,0 +1,57 @@
+use anyhow::Result;
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+/// ambient_viewing_environment, D.2.39
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct AmbientViewingEnvironment {
+    /// In units of 0.0001 lux
+    pub ambient_illuminance: u32,
+    /// In increments of 0.00002
+    pub ambient_light_x: u16,
+    pub ambient_light_y: u16,
+}
+
+impl AmbientViewingEnvironment {
+    pub fn parse(data: &[u8]) -> Result<AmbientViewingEnvironment> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        Ok(AmbientViewingEnvironment {
+            ambient_illuminance: reader.read::<32, u32>()?,
+            ambient_light_x: reader.read::<16, u16>()?,
+            ambient_light_y: reader.read::<16, u16>()?,
+        })
+    }
+
+    /// In lux
+    pub fn illuminance(&self) -> f64 {
+        self.ambient_illuminance as f64 / 10_000.0
+    }
+
+    /// CIE 1931 chromaticity of the ambient light
+    pub fn light_chromaticity(&self) -> (f64, f64) {
+        (
+            self.ambient_light_x as f64 / 50_000.0,
+            self.ambient_light_y as f64 / 50_000.0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() -> Result<()> {
+        // 314 lux, D65
+        let data = [0x00, 0x2F, 0xE9, 0xA0, 0x3D, 0x13, 0x40, 0x42];
+        let ave = AmbientViewingEnvironment::parse(&data)?;
+
+        assert_eq!(ave.ambient_illuminance, 3_140_000);
+        assert_eq!(ave.illuminance(), 314.0);
+        assert_eq!(ave.light_chromaticity(), (0.3127, 0.329));
+
+        assert!(AmbientViewingEnvironment::parse(&data[..6]).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use anyhow::{Result, bail};
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+use super::super::sps::SPSNAL;
+
+/// buffering_period SEI, D.2.2
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct BufferingPeriod {
+    pub bp_seq_parameter_set_id: u64,
+    pub irap_cpb_params_present_flag: bool,
+    pub cpb_delay_offset: u32,
+    pub dpb_delay_offset: u32,
+    pub concatenation_flag: bool,
+    pub au_cpb_removal_delay_delta_minus1: u32,
+
+    /// One per CPB, when NAL HRD parameters are present
+    pub nal_initial_cpb_removal: Vec<InitialCpbRemoval>,
+    /// One per CPB, when VCL HRD parameters are present
+    pub vcl_initial_cpb_removal: Vec<InitialCpbRemoval>,
+}
+
+/// Initial CPB removal delay and offset, in units of a 90 kHz clock
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct InitialCpbRemoval {
+    pub initial_cpb_removal_delay: u32,
+    pub initial_cpb_removal_offset: u32,
+
+    /// Present with sub-picture HRD parameters or `irap_cpb_params_present_flag`
+    pub initial_alt_cpb_removal_delay: Option<u32>,
+    pub initial_alt_cpb_removal_offset: Option<u32>,
+}
+
+impl BufferingPeriod {
+    /// The SPS referenced by `bp_seq_parameter_set_id` is looked up in `sps_list`
+    pub fn parse(data: &[u8], sps_list: &[SPSNAL]) -> Result<BufferingPeriod> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        let mut bp = BufferingPeriod {
+            bp_seq_parameter_set_id: reader.read_ue()?,
+            ..Default::default()
+        };
+
+        let Some(sps) = sps_list
+            .iter()
+            .find(|sps| sps.sps_id == bp.bp_seq_parameter_set_id)
+        else {
+            bail!(
+                "Buffering period references unknown SPS {}",
+                bp.bp_seq_parameter_set_id
+            );
+        };
+
+        let Some(hrd) = sps.hrd_parameters() else {
+            bail!("Buffering period without HRD parameters");
+        };
+
+        if !hrd.sub_pic_hrd_params_present_flag {
+            bp.irap_cpb_params_present_flag = reader.read_bit()?;
+        }
+
+        let au_cpb_removal_delay_length = hrd.au_cpb_removal_delay_length_minus1 as u32 + 1;
+
+        if bp.irap_cpb_params_present_flag {
+            bp.cpb_delay_offset = reader.read_var(au_cpb_removal_delay_length)?;
+            bp.dpb_delay_offset = reader.read_var(hrd.dpb_output_delay_length_minus1 as u32 + 1)?;
+        }
+
+        bp.concatenation_flag = reader.read_bit()?;
+        bp.au_cpb_removal_delay_delta_minus1 = reader.read_var(au_cpb_removal_delay_length)?;
+
+        let alt_present = hrd.sub_pic_hrd_params_present_flag || bp.irap_cpb_params_present_flag;
+        let delay_length = hrd.initial_cpb_removal_delay_length_minus1 as u32 + 1;
+
+        for (present, initial_cpb_removal) in [
+            (
+                hrd.nal_hrd_parameters_present_flag,
+                &mut bp.nal_initial_cpb_removal,
+            ),
+            (
+                hrd.vcl_hrd_parameters_present_flag,
+                &mut bp.vcl_initial_cpb_removal,
+            ),
+        ] {
+            if !present {
+                continue;
+            }
+
+            for _ in 0..hrd.cpb_cnt() {
+                let mut removal = InitialCpbRemoval {
+                    initial_cpb_removal_delay: reader.read_var(delay_length)?,
+                    initial_cpb_removal_offset: reader.read_var(delay_length)?,
+                    ..Default::default()
+                };
+
+                if alt_present {
+                    removal.initial_alt_cpb_removal_delay = Some(reader.read_var(delay_length)?);
+                    removal.initial_alt_cpb_removal_offset = Some(reader.read_var(delay_length)?);
+                }
+
+                initial_cpb_removal.push(removal);
+            }
+        }
+
+        Ok(bp)
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::Result;
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+/// content_colour_volume, D.2.40
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct ContentColourVolume {
+    /// Cancels the persistence of the previous content colour volume, nothing else is present
+    pub ccv_cancel_flag: bool,
+    pub ccv_persistence_flag: bool,
+
+    /// In increments of 0.00002
+    pub ccv_primaries_x: Option<[i32; 3]>,
+    pub ccv_primaries_y: Option<[i32; 3]>,
+
+    /// In units of 0.0000001 cd/m²
+    pub ccv_min_luminance_value: Option<u32>,
+    pub ccv_max_luminance_value: Option<u32>,
+    pub ccv_avg_luminance_value: Option<u32>,
+}
+
+impl ContentColourVolume {
+    pub fn parse(data: &[u8]) -> Result<ContentColourVolume> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        let mut ccv = ContentColourVolume {
+            ccv_cancel_flag: reader.read_bit()?,
+            ..Default::default()
+        };
+
+        if ccv.ccv_cancel_flag {
+            return Ok(ccv);
+        }
+
+        ccv.ccv_persistence_flag = reader.read_bit()?;
+
+        let ccv_primaries_present_flag = reader.read_bit()?;
+        let ccv_min_luminance_value_present_flag = reader.read_bit()?;
+        let ccv_max_luminance_value_present_flag = reader.read_bit()?;
+        let ccv_avg_luminance_value_present_flag = reader.read_bit()?;
+
+        // ccv_reserved_zero_2bits
+        reader.skip_n(2)?;
+
+        if ccv_primaries_present_flag {
+            let mut x = [0; 3];
+            let mut y = [0; 3];
+
+            for c in 0..3 {
+                x[c] = reader.read::<32, u32>()? as i32;
+                y[c] = reader.read::<32, u32>()? as i32;
+            }
+
+            ccv.ccv_primaries_x = Some(x);
+            ccv.ccv_primaries_y = Some(y);
+        }
+
+        if ccv_min_luminance_value_present_flag {
+            ccv.ccv_min_luminance_value = Some(reader.read::<32, u32>()?);
+        }
+
+        if ccv_max_luminance_value_present_flag {
+            ccv.ccv_max_luminance_value = Some(reader.read::<32, u32>()?);
+        }
+
+        if ccv_avg_luminance_value_present_flag {
+            ccv.ccv_avg_luminance_value = Some(reader.read::<32, u32>()?);
+        }
+
+        Ok(ccv)
+    }
+
+    /// In cd/m²
+    pub fn min_luminance(&self) -> Option<f64> {
+        self.ccv_min_luminance_value.map(luminance)
+    }
+
+    pub fn max_luminance(&self) -> Option<f64> {
+        self.ccv_max_luminance_value.map(luminance)
+    }
+
+    pub fn avg_luminance(&self) -> Option<f64> {
+        self.ccv_avg_luminance_value.map(luminance)
+    }
+}
+
+fn luminance(value: u32) -> f64 {
+    value as f64 / 10_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_luminance() -> Result<()> {
+        // Persistent, max 400 and average 100 cd/m²
+        let data = [0x4C, 0xEE, 0x6B, 0x28, 0x00, 0x3B, 0x9A, 0xCA, 0x00];
+        let ccv = ContentColourVolume::parse(&data)?;
+
+        assert!(ccv.ccv_persistence_flag);
+        assert_eq!(ccv.ccv_primaries_x, None);
+        assert_eq!(ccv.min_luminance(), None);
+        assert_eq!(ccv.max_luminance(), Some(400.0));
+        assert_eq!(ccv.avg_luminance(), Some(100.0));
+
+        assert!(ContentColourVolume::parse(&data[..6]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_primaries() -> Result<()> {
+        // BT.2020, x then y of each primary
+        let values: [u32; 6] = [35400, 14600, 8500, 39850, 6550, 2300];
+        let data: Vec<u8> = [0x20]
+            .into_iter()
+            .chain(values.iter().flat_map(|v| v.to_be_bytes()))
+            .collect();
+
+        let ccv = ContentColourVolume::parse(&data)?;
+        assert!(!ccv.ccv_persistence_flag);
+        assert_eq!(ccv.ccv_primaries_x, Some([35400, 8500, 6550]));
+        assert_eq!(ccv.ccv_primaries_y, Some([14600, 39850, 2300]));
+        assert_eq!(ccv.max_luminance(), None);
+
+        assert_eq!(
+            ContentColourVolume::parse(&[0x80])?,
+            ContentColourVolume {
+                ccv_cancel_flag: true,
+                ..Default::default()
+            }
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use anyhow::Result;
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+/// content_light_level_info, D.2.35.
+/// The values are in cd/m², and map directly to the Matroska `MaxCLL` and `MaxFALL` elements.
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct ContentLightLevelInfo {
+    pub max_content_light_level: u16,
+    pub max_pic_average_light_level: u16,
+}
+
+impl ContentLightLevelInfo {
+    pub fn parse(data: &[u8]) -> Result<ContentLightLevelInfo> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        Ok(ContentLightLevelInfo {
+            max_content_light_level: reader.read::<16, u16>()?,
+            max_pic_average_light_level: reader.read::<16, u16>()?,
+        })
+    }
+
+    /// x265 `--max-cll` value, e.g. `1000,400`
+    pub fn to_x265_string(&self) -> String {
+        format!(
+            "{},{}",
+            self.max_content_light_level, self.max_pic_average_light_level
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() -> Result<()> {
+        let cll = ContentLightLevelInfo::parse(&[0x03, 0xE8, 0x01, 0x90])?;
+
+        assert_eq!(cll.max_content_light_level, 1000);
+        assert_eq!(cll.max_pic_average_light_level, 400);
+        assert_eq!(cll.to_x265_string(), "1000,400");
+
+        assert!(ContentLightLevelInfo::parse(&[0x03, 0xE8]).is_err());
+
+        Ok(())
+    }
+}
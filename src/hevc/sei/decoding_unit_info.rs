@@ -0,0 +1,44 @@
+use anyhow::{Result, bail};
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+use super::super::sps::SPSNAL;
+
+/// decoding_unit_info SEI, D.2.21
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct DecodingUnitInfo {
+    pub decoding_unit_idx: u64,
+    /// Absent when the decoding unit delays are in the pic_timing SEI
+    pub du_spt_cpb_removal_delay_increment: Option<u32>,
+    pub pic_spt_dpb_output_du_delay: Option<u32>,
+}
+
+impl DecodingUnitInfo {
+    pub fn parse(data: &[u8], sps: &SPSNAL) -> Result<DecodingUnitInfo> {
+        let Some(hrd) = sps
+            .hrd_parameters()
+            .filter(|hrd| hrd.sub_pic_hrd_params_present_flag)
+        else {
+            bail!("Decoding unit info without sub-picture HRD parameters");
+        };
+
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        let mut info = DecodingUnitInfo {
+            decoding_unit_idx: reader.read_ue()?,
+            ..Default::default()
+        };
+
+        if !hrd.sub_pic_cpb_params_in_pic_timing_sei_flag {
+            info.du_spt_cpb_removal_delay_increment =
+                Some(reader.read_var(hrd.du_cpb_removal_delay_increment_length_minus1 as u32 + 1)?);
+        }
+
+        let dpb_output_du_delay_present_flag = reader.read_bit()?;
+        if dpb_output_du_delay_present_flag {
+            info.pic_spt_dpb_output_du_delay =
+                Some(reader.read_var(hrd.dpb_output_delay_du_length_minus1 as u32 + 1)?);
+        }
+
+        Ok(info)
+    }
+}
@@ -0,0 +1,266 @@
+use anyhow::Result;
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+use super::super::Frame;
+use super::SeiPayload;
+
+/// film_grain_characteristics, D.2.21
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct FilmGrainCharacteristics {
+    /// Cancels the persistence of the previous film grain characteristics, nothing else is present
+    pub film_grain_characteristics_cancel_flag: bool,
+
+    /// 0: frequency filtering, 1: auto-regression
+    pub film_grain_model_id: u8,
+    pub colour_description: Option<FilmGrainColourDescription>,
+    /// 0: additive, 1: multiplicative
+    pub blending_mode_id: u8,
+    pub log2_scale_factor: u8,
+
+    /// One per colour component, `None` when `comp_model_present_flag` is not set
+    pub components: [Option<FilmGrainComponent>; 3],
+
+    pub film_grain_characteristics_persistence_flag: bool,
+}
+
+/// Colour description of the film grain, when `separate_colour_description_present_flag` is set
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct FilmGrainColourDescription {
+    pub film_grain_bit_depth_luma_minus8: u8,
+    pub film_grain_bit_depth_chroma_minus8: u8,
+    pub film_grain_full_range_flag: bool,
+    pub film_grain_colour_primaries: u8,
+    pub film_grain_transfer_characteristics: u8,
+    pub film_grain_matrix_coeffs: u8,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct FilmGrainComponent {
+    pub num_model_values_minus1: u8,
+    pub intensity_intervals: Vec<FilmGrainIntensityInterval>,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct FilmGrainIntensityInterval {
+    pub intensity_interval_lower_bound: u8,
+    pub intensity_interval_upper_bound: u8,
+    /// `num_model_values_minus1 + 1` values
+    pub comp_model_value: Vec<i64>,
+}
+
+impl FilmGrainCharacteristics {
+    pub fn parse(data: &[u8]) -> Result<FilmGrainCharacteristics> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        let mut fgc = FilmGrainCharacteristics {
+            film_grain_characteristics_cancel_flag: reader.read_bit()?,
+            ..Default::default()
+        };
+
+        if fgc.film_grain_characteristics_cancel_flag {
+            return Ok(fgc);
+        }
+
+        fgc.film_grain_model_id = reader.read::<2, u8>()?;
+
+        let separate_colour_description_present_flag = reader.read_bit()?;
+        if separate_colour_description_present_flag {
+            fgc.colour_description = Some(FilmGrainColourDescription {
+                film_grain_bit_depth_luma_minus8: reader.read::<3, u8>()?,
+                film_grain_bit_depth_chroma_minus8: reader.read::<3, u8>()?,
+                film_grain_full_range_flag: reader.read_bit()?,
+                film_grain_colour_primaries: reader.read::<8, u8>()?,
+                film_grain_transfer_characteristics: reader.read::<8, u8>()?,
+                film_grain_matrix_coeffs: reader.read::<8, u8>()?,
+            });
+        }
+
+        fgc.blending_mode_id = reader.read::<2, u8>()?;
+        fgc.log2_scale_factor = reader.read::<4, u8>()?;
+
+        let mut comp_model_present_flag = [false; 3];
+        for flag in comp_model_present_flag.iter_mut() {
+            *flag = reader.read_bit()?;
+        }
+
+        for (c, present) in comp_model_present_flag.into_iter().enumerate() {
+            if !present {
+                continue;
+            }
+
+            let num_intensity_intervals_minus1 = reader.read::<8, u8>()?;
+            let mut component = FilmGrainComponent {
+                num_model_values_minus1: reader.read::<3, u8>()?,
+                ..Default::default()
+            };
+
+            for _ in 0..=num_intensity_intervals_minus1 {
+                let mut interval = FilmGrainIntensityInterval {
+                    intensity_interval_lower_bound: reader.read::<8, u8>()?,
+                    intensity_interval_upper_bound: reader.read::<8, u8>()?,
+                    ..Default::default()
+                };
+
+                for _ in 0..=component.num_model_values_minus1 {
+                    interval.comp_model_value.push(reader.read_se()?);
+                }
+
+                component.intensity_intervals.push(interval);
+            }
+
+            fgc.components[c] = Some(component);
+        }
+
+        fgc.film_grain_characteristics_persistence_flag = reader.read_bit()?;
+
+        Ok(fgc)
+    }
+
+    pub fn model_name(&self) -> &'static str {
+        match self.film_grain_model_id {
+            0 => "frequency filtering",
+            1 => "auto-regression",
+            _ => "reserved",
+        }
+    }
+
+    pub fn blending_mode_name(&self) -> &'static str {
+        match self.blending_mode_id {
+            0 => "additive",
+            1 => "multiplicative",
+            _ => "reserved",
+        }
+    }
+}
+
+/// Film grain characteristics applying to each frame, in the order of the frames.
+/// Persistent characteristics apply to the following frames until cancelled or replaced,
+/// so the frames should be in presentation order, usually `HevcParser::ordered_frames`.
+pub fn frames_film_grain(frames: &[Frame]) -> Vec<Option<&FilmGrainCharacteristics>> {
+    let mut persistent: Option<&FilmGrainCharacteristics> = None;
+
+    frames
+        .iter()
+        .map(|f| {
+            if f.starts_clvs() {
+                persistent = None;
+            }
+
+            let fgc = f.prefix_sei.iter().find_map(|sei| match sei {
+                SeiPayload::FilmGrainCharacteristics(fgc) => Some(fgc),
+                _ => None,
+            });
+
+            match fgc {
+                Some(fgc) if fgc.film_grain_characteristics_cancel_flag => {
+                    persistent = None;
+                    None
+                }
+                Some(fgc) => {
+                    persistent = fgc
+                        .film_grain_characteristics_persistence_flag
+                        .then_some(fgc);
+                    Some(fgc)
+                }
+                None => persistent,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hevc::{NAL_IDR_W_RADL, NAL_TRAIL_R, NALUnit};
+
+    /// Frequency filtering luma model, persistent
+    const PERSISTENT: &[u8] = &[0x01, 0x60, 0x01, 0x00, 0xFF, 0x02, 0x81, 0x60];
+    const CURRENT_PICTURE: &[u8] = &[0x01, 0x60, 0x01, 0x00, 0xFF, 0x02, 0x81, 0x40];
+    const CANCEL: &[u8] = &[0x80];
+
+    fn frame(nal_type: u8, sei: Option<&[u8]>) -> Frame {
+        Frame {
+            nals: vec![NALUnit {
+                nal_type,
+                ..Default::default()
+            }],
+            prefix_sei: sei
+                .map(|data| {
+                    SeiPayload::FilmGrainCharacteristics(
+                        FilmGrainCharacteristics::parse(data).unwrap(),
+                    )
+                })
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse() -> Result<()> {
+        let fgc = FilmGrainCharacteristics::parse(PERSISTENT)?;
+
+        assert_eq!(fgc.model_name(), "frequency filtering");
+        assert_eq!(fgc.blending_mode_name(), "additive");
+        assert_eq!(fgc.log2_scale_factor, 5);
+        assert!(fgc.colour_description.is_none());
+        assert!(fgc.film_grain_characteristics_persistence_flag);
+        assert_eq!(
+            fgc.components,
+            [
+                Some(FilmGrainComponent {
+                    num_model_values_minus1: 1,
+                    intensity_intervals: vec![FilmGrainIntensityInterval {
+                        intensity_interval_lower_bound: 0,
+                        intensity_interval_upper_bound: 255,
+                        comp_model_value: vec![40, -2],
+                    }],
+                }),
+                None,
+                None,
+            ]
+        );
+
+        let cancel = FilmGrainCharacteristics::parse(CANCEL)?;
+        assert!(cancel.film_grain_characteristics_cancel_flag);
+        assert!(cancel.components.iter().all(Option::is_none));
+
+        assert!(FilmGrainCharacteristics::parse(&PERSISTENT[..5]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn persistence() -> Result<()> {
+        let frames = [
+            frame(NAL_IDR_W_RADL, Some(PERSISTENT)),
+            frame(NAL_TRAIL_R, None),
+            frame(NAL_TRAIL_R, Some(CANCEL)),
+            frame(NAL_TRAIL_R, None),
+            frame(NAL_TRAIL_R, Some(PERSISTENT)),
+            // New CLVS
+            frame(NAL_IDR_W_RADL, None),
+            frame(NAL_TRAIL_R, Some(CURRENT_PICTURE)),
+            frame(NAL_TRAIL_R, None),
+        ];
+
+        let persistent = FilmGrainCharacteristics::parse(PERSISTENT)?;
+        let current_picture = FilmGrainCharacteristics::parse(CURRENT_PICTURE)?;
+
+        assert_eq!(
+            frames_film_grain(&frames),
+            [
+                Some(&persistent),
+                Some(&persistent),
+                None,
+                None,
+                Some(&persistent),
+                None,
+                Some(&current_picture),
+                None,
+            ]
+        );
+
+        Ok(())
+    }
+}
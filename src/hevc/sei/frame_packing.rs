@@ -0,0 +1,270 @@
+use anyhow::Result;
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+use super::super::Frame;
+use super::SeiPayload;
+
+/// frame_packing_arrangement, D.2.16
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct FramePackingArrangement {
+    pub frame_packing_arrangement_id: u32,
+    /// Cancels the persistence of the previous arrangement, nothing else is present
+    pub frame_packing_arrangement_cancel_flag: bool,
+
+    /// 3: side-by-side, 4: top-bottom, 5: temporal interleaving
+    pub frame_packing_arrangement_type: u8,
+    pub quincunx_sampling_flag: bool,
+    /// 1: frame 0 is the left view, 2: frame 0 is the right view
+    pub content_interpretation_type: u8,
+    pub spatial_flipping_flag: bool,
+    pub frame0_flipped_flag: bool,
+    pub field_views_flag: bool,
+    pub current_frame_is_frame0_flag: bool,
+    pub frame0_self_contained_flag: bool,
+    pub frame1_self_contained_flag: bool,
+
+    /// Present without quincunx sampling, for the spatial arrangements
+    pub frame0_grid_position_x: u8,
+    pub frame0_grid_position_y: u8,
+    pub frame1_grid_position_x: u8,
+    pub frame1_grid_position_y: u8,
+
+    pub frame_packing_arrangement_persistence_flag: bool,
+    pub upsampled_aspect_ratio_flag: bool,
+}
+
+/// Arrangement of the two views in the decoded pictures
+#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+pub enum StereoMode {
+    Checkerboard,
+    ColumnInterleaved,
+    RowInterleaved,
+    SideBySide,
+    TopBottom,
+    /// Frame sequential, the views alternate between pictures
+    FrameAlternate,
+}
+
+/// Effective stereo 3D signalling of a stream
+#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+pub struct Stereo3d {
+    pub mode: StereoMode,
+    /// The left view is in frame 0, the left or top half, or the first picture.
+    /// `true` when `content_interpretation_type` is unspecified.
+    pub left_view_first: bool,
+    pub quincunx_sampling: bool,
+}
+
+impl FramePackingArrangement {
+    pub fn parse(data: &[u8]) -> Result<FramePackingArrangement> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        let mut fpa = FramePackingArrangement {
+            frame_packing_arrangement_id: reader.read_ue()? as u32,
+            frame_packing_arrangement_cancel_flag: reader.read_bit()?,
+            ..Default::default()
+        };
+
+        if !fpa.frame_packing_arrangement_cancel_flag {
+            fpa.frame_packing_arrangement_type = reader.read::<7, u8>()?;
+            fpa.quincunx_sampling_flag = reader.read_bit()?;
+            fpa.content_interpretation_type = reader.read::<6, u8>()?;
+            fpa.spatial_flipping_flag = reader.read_bit()?;
+            fpa.frame0_flipped_flag = reader.read_bit()?;
+            fpa.field_views_flag = reader.read_bit()?;
+            fpa.current_frame_is_frame0_flag = reader.read_bit()?;
+            fpa.frame0_self_contained_flag = reader.read_bit()?;
+            fpa.frame1_self_contained_flag = reader.read_bit()?;
+
+            if !fpa.quincunx_sampling_flag && fpa.frame_packing_arrangement_type != 5 {
+                fpa.frame0_grid_position_x = reader.read::<4, u8>()?;
+                fpa.frame0_grid_position_y = reader.read::<4, u8>()?;
+                fpa.frame1_grid_position_x = reader.read::<4, u8>()?;
+                fpa.frame1_grid_position_y = reader.read::<4, u8>()?;
+            }
+
+            // frame_packing_arrangement_reserved_byte
+            reader.skip_n(8)?;
+
+            fpa.frame_packing_arrangement_persistence_flag = reader.read_bit()?;
+        }
+
+        fpa.upsampled_aspect_ratio_flag = reader.read_bit()?;
+
+        Ok(fpa)
+    }
+
+    /// `None` for the reserved types and 2D content (type 6)
+    pub fn stereo_mode(&self) -> Option<StereoMode> {
+        match self.frame_packing_arrangement_type {
+            0 => Some(StereoMode::Checkerboard),
+            1 => Some(StereoMode::ColumnInterleaved),
+            2 => Some(StereoMode::RowInterleaved),
+            3 => Some(StereoMode::SideBySide),
+            4 => Some(StereoMode::TopBottom),
+            5 => Some(StereoMode::FrameAlternate),
+            _ => None,
+        }
+    }
+
+    pub fn stereo_3d(&self) -> Option<Stereo3d> {
+        if self.frame_packing_arrangement_cancel_flag {
+            return None;
+        }
+
+        self.stereo_mode().map(|mode| Stereo3d {
+            mode,
+            left_view_first: self.content_interpretation_type != 2,
+            quincunx_sampling: self.quincunx_sampling_flag,
+        })
+    }
+}
+
+impl Stereo3d {
+    /// Matroska `StereoMode` element value
+    pub fn matroska_stereo_mode(&self) -> u8 {
+        match (self.mode, self.left_view_first) {
+            (StereoMode::SideBySide, true) => 1,
+            (StereoMode::SideBySide, false) => 11,
+            (StereoMode::TopBottom, true) => 3,
+            (StereoMode::TopBottom, false) => 2,
+            (StereoMode::Checkerboard, true) => 5,
+            (StereoMode::Checkerboard, false) => 4,
+            (StereoMode::RowInterleaved, true) => 7,
+            (StereoMode::RowInterleaved, false) => 6,
+            (StereoMode::ColumnInterleaved, true) => 9,
+            (StereoMode::ColumnInterleaved, false) => 8,
+            (StereoMode::FrameAlternate, true) => 13,
+            (StereoMode::FrameAlternate, false) => 14,
+        }
+    }
+}
+
+/// Frame packing arrangement applying to each frame, in the order of the frames.
+/// Persistent arrangements apply to the following frames until cancelled or replaced,
+/// so the frames should be in presentation order, usually `HevcParser::ordered_frames`.
+pub fn frames_frame_packing(frames: &[Frame]) -> Vec<Option<&FramePackingArrangement>> {
+    let mut persistent: Option<&FramePackingArrangement> = None;
+
+    frames
+        .iter()
+        .map(|f| {
+            if f.starts_clvs() {
+                persistent = None;
+            }
+
+            let fpa = f.prefix_sei.iter().find_map(|sei| match sei {
+                SeiPayload::FramePackingArrangement(fpa) => Some(fpa),
+                _ => None,
+            });
+
+            match fpa {
+                Some(fpa) if fpa.frame_packing_arrangement_cancel_flag => {
+                    persistent = None;
+                    None
+                }
+                Some(fpa) => {
+                    persistent = fpa
+                        .frame_packing_arrangement_persistence_flag
+                        .then_some(fpa);
+                    Some(fpa)
+                }
+                None => persistent,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hevc::{NAL_BLA_W_LP, NAL_TRAIL_R, NALUnit};
+
+    /// Persistent side-by-side, left view first
+    const SIDE_BY_SIDE: &[u8] = &[0x81, 0x81, 0x00, 0x00, 0x00, 0x02];
+    /// Frame alternate for the current picture, right view first
+    const FRAME_ALTERNATE: &[u8] = &[0x82, 0x82, 0x00, 0x00];
+    const CANCEL: &[u8] = &[0xC0];
+
+    fn frame(nal_type: u8, sei: Option<&[u8]>) -> Frame {
+        Frame {
+            nals: vec![NALUnit {
+                nal_type,
+                ..Default::default()
+            }],
+            prefix_sei: sei
+                .map(|data| {
+                    SeiPayload::FramePackingArrangement(
+                        FramePackingArrangement::parse(data).unwrap(),
+                    )
+                })
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse() -> Result<()> {
+        let fpa = FramePackingArrangement::parse(SIDE_BY_SIDE)?;
+        assert_eq!(fpa.frame_packing_arrangement_type, 3);
+        assert_eq!(fpa.content_interpretation_type, 1);
+        assert!(fpa.frame_packing_arrangement_persistence_flag);
+        assert_eq!(
+            fpa.stereo_3d(),
+            Some(Stereo3d {
+                mode: StereoMode::SideBySide,
+                left_view_first: true,
+                quincunx_sampling: false,
+            })
+        );
+        assert_eq!(fpa.stereo_3d().unwrap().matroska_stereo_mode(), 1);
+
+        // No grid positions for frame alternate
+        let fpa = FramePackingArrangement::parse(FRAME_ALTERNATE)?;
+        assert!(!fpa.frame_packing_arrangement_persistence_flag);
+        assert_eq!(fpa.stereo_3d().unwrap().matroska_stereo_mode(), 14);
+
+        let cancel = FramePackingArrangement::parse(CANCEL)?;
+        assert!(cancel.frame_packing_arrangement_cancel_flag);
+        assert_eq!(cancel.stereo_3d(), None);
+
+        assert!(FramePackingArrangement::parse(&SIDE_BY_SIDE[..4]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn persistence() -> Result<()> {
+        let frames = [
+            frame(NAL_TRAIL_R, Some(SIDE_BY_SIDE)),
+            frame(NAL_TRAIL_R, None),
+            frame(NAL_TRAIL_R, Some(FRAME_ALTERNATE)),
+            frame(NAL_TRAIL_R, None),
+            frame(NAL_TRAIL_R, Some(SIDE_BY_SIDE)),
+            frame(NAL_TRAIL_R, Some(CANCEL)),
+            frame(NAL_TRAIL_R, Some(SIDE_BY_SIDE)),
+            // New CLVS
+            frame(NAL_BLA_W_LP, None),
+        ];
+
+        let side_by_side = FramePackingArrangement::parse(SIDE_BY_SIDE)?;
+        let frame_alternate = FramePackingArrangement::parse(FRAME_ALTERNATE)?;
+
+        assert_eq!(
+            frames_frame_packing(&frames),
+            [
+                Some(&side_by_side),
+                Some(&side_by_side),
+                Some(&frame_alternate),
+                None,
+                Some(&side_by_side),
+                None,
+                Some(&side_by_side),
+                None,
+            ]
+        );
+
+        Ok(())
+    }
+}
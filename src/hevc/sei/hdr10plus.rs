@@ -0,0 +1,428 @@
+use anyhow::{Result, ensure};
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+use super::super::Frame;
+use super::SeiPayload;
+
+#[cfg(feature = "serde")]
+use {
+    anyhow::bail,
+    serde_json::{Value, json},
+    std::io::Write,
+};
+
+pub const HDR10PLUS_COUNTRY_CODE: u8 = 0xB5;
+pub const HDR10PLUS_PROVIDER_CODE: u16 = 0x003C;
+pub const HDR10PLUS_PROVIDER_ORIENTED_CODE: u16 = 0x0001;
+pub const HDR10PLUS_APPLICATION_IDENTIFIER: u8 = 4;
+
+/// SMPTE ST 2094-40 application 4 dynamic metadata
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct Hdr10PlusMetadata {
+    pub application_version: u8,
+    pub num_windows: u8,
+
+    /// Windows after the implicit full picture window
+    pub processing_windows: Vec<ProcessingWindow>,
+
+    pub targeted_system_display_maximum_luminance: u32,
+    pub targeted_system_display_actual_peak_luminance_flag: bool,
+    pub targeted_system_display_actual_peak_luminance: Vec<Vec<u8>>,
+
+    /// One per window, including the full picture
+    pub windows: Vec<Hdr10PlusWindow>,
+
+    pub mastering_display_actual_peak_luminance_flag: bool,
+    pub mastering_display_actual_peak_luminance: Vec<Vec<u8>>,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct ProcessingWindow {
+    pub window_upper_left_corner_x: u16,
+    pub window_upper_left_corner_y: u16,
+    pub window_lower_right_corner_x: u16,
+    pub window_lower_right_corner_y: u16,
+
+    pub center_of_ellipse_x: u16,
+    pub center_of_ellipse_y: u16,
+    pub rotation_angle: u8,
+
+    pub semimajor_axis_internal_ellipse: u16,
+    pub semimajor_axis_external_ellipse: u16,
+    pub semiminor_axis_external_ellipse: u16,
+    pub overlap_process_option: bool,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct Hdr10PlusWindow {
+    pub maxscl: [u32; 3],
+    pub average_maxrgb: u32,
+    pub distribution_maxrgb: Vec<DistributionMaxRgb>,
+    pub fraction_bright_pixels: u16,
+
+    pub tone_mapping_flag: bool,
+    pub knee_point_x: u16,
+    pub knee_point_y: u16,
+    pub bezier_curve_anchors: Vec<u16>,
+
+    pub color_saturation_mapping_flag: bool,
+    pub color_saturation_weight: u8,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct DistributionMaxRgb {
+    pub percentage: u8,
+    pub percentile: u32,
+}
+
+impl Hdr10PlusMetadata {
+    /// Whether the T.35 country and provider codes are the ones of HDR10+
+    pub fn is_hdr10plus(country_code: u8, data: &[u8]) -> bool {
+        country_code == HDR10PLUS_COUNTRY_CODE
+            && matches!(
+                data,
+                [0x00, 0x3C, 0x00, 0x01, HDR10PLUS_APPLICATION_IDENTIFIER, ..]
+            )
+    }
+
+    /// Parses the T.35 data following the country code
+    pub fn parse(data: &[u8]) -> Result<Hdr10PlusMetadata> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        let provider_code = reader.read::<16, u16>()?;
+        let provider_oriented_code = reader.read::<16, u16>()?;
+        let application_identifier = reader.read::<8, u8>()?;
+
+        ensure!(
+            provider_code == HDR10PLUS_PROVIDER_CODE
+                && provider_oriented_code == HDR10PLUS_PROVIDER_ORIENTED_CODE
+                && application_identifier == HDR10PLUS_APPLICATION_IDENTIFIER,
+            "Not HDR10+ metadata"
+        );
+
+        let mut meta = Hdr10PlusMetadata {
+            application_version: reader.read::<8, u8>()?,
+            num_windows: reader.read::<2, u8>()?,
+            ..Default::default()
+        };
+
+        ensure!(
+            meta.num_windows > 0,
+            "HDR10+ num_windows must be at least 1"
+        );
+
+        for _ in 1..meta.num_windows {
+            meta.processing_windows.push(ProcessingWindow {
+                window_upper_left_corner_x: reader.read::<16, u16>()?,
+                window_upper_left_corner_y: reader.read::<16, u16>()?,
+                window_lower_right_corner_x: reader.read::<16, u16>()?,
+                window_lower_right_corner_y: reader.read::<16, u16>()?,
+                center_of_ellipse_x: reader.read::<16, u16>()?,
+                center_of_ellipse_y: reader.read::<16, u16>()?,
+                rotation_angle: reader.read::<8, u8>()?,
+                semimajor_axis_internal_ellipse: reader.read::<16, u16>()?,
+                semimajor_axis_external_ellipse: reader.read::<16, u16>()?,
+                semiminor_axis_external_ellipse: reader.read::<16, u16>()?,
+                overlap_process_option: reader.read_bit()?,
+            });
+        }
+
+        meta.targeted_system_display_maximum_luminance = reader.read::<27, u32>()?;
+        meta.targeted_system_display_actual_peak_luminance_flag = reader.read_bit()?;
+
+        if meta.targeted_system_display_actual_peak_luminance_flag {
+            meta.targeted_system_display_actual_peak_luminance =
+                Self::parse_peak_luminance(&mut reader)?;
+        }
+
+        for _ in 0..meta.num_windows {
+            let mut window = Hdr10PlusWindow::default();
+
+            for v in window.maxscl.iter_mut() {
+                *v = reader.read::<17, u32>()?;
+            }
+
+            window.average_maxrgb = reader.read::<17, u32>()?;
+
+            let num_distribution_maxrgb_percentiles = reader.read::<4, u8>()?;
+            for _ in 0..num_distribution_maxrgb_percentiles {
+                window.distribution_maxrgb.push(DistributionMaxRgb {
+                    percentage: reader.read::<7, u8>()?,
+                    percentile: reader.read::<17, u32>()?,
+                });
+            }
+
+            window.fraction_bright_pixels = reader.read::<10, u16>()?;
+
+            meta.windows.push(window);
+        }
+
+        meta.mastering_display_actual_peak_luminance_flag = reader.read_bit()?;
+
+        if meta.mastering_display_actual_peak_luminance_flag {
+            meta.mastering_display_actual_peak_luminance = Self::parse_peak_luminance(&mut reader)?;
+        }
+
+        for window in meta.windows.iter_mut() {
+            window.tone_mapping_flag = reader.read_bit()?;
+
+            if window.tone_mapping_flag {
+                window.knee_point_x = reader.read::<12, u16>()?;
+                window.knee_point_y = reader.read::<12, u16>()?;
+
+                let num_bezier_curve_anchors = reader.read::<4, u8>()?;
+                for _ in 0..num_bezier_curve_anchors {
+                    window.bezier_curve_anchors.push(reader.read::<10, u16>()?);
+                }
+            }
+
+            window.color_saturation_mapping_flag = reader.read_bit()?;
+
+            if window.color_saturation_mapping_flag {
+                window.color_saturation_weight = reader.read::<6, u8>()?;
+            }
+        }
+
+        Ok(meta)
+    }
+
+    fn parse_peak_luminance(reader: &mut BsIoSliceReader) -> Result<Vec<Vec<u8>>> {
+        let num_rows = reader.read::<5, u8>()?;
+        let num_cols = reader.read::<5, u8>()?;
+
+        (0..num_rows)
+            .map(|_| (0..num_cols).map(|_| Ok(reader.read::<4, u8>()?)).collect())
+            .collect()
+    }
+
+    /// Profile B when a bezier curve is present, otherwise A
+    pub fn profile(&self) -> &'static str {
+        if self.windows.iter().any(|w| w.tone_mapping_flag) {
+            "B"
+        } else {
+            "A"
+        }
+    }
+}
+
+/// HDR10+ metadata of each frame, in the order of the frames
+pub fn frames_hdr10plus(frames: &[Frame]) -> Vec<Option<&Hdr10PlusMetadata>> {
+    frames
+        .iter()
+        .map(|f| {
+            f.prefix_sei.iter().find_map(|sei| match sei {
+                SeiPayload::Hdr10Plus(meta) => Some(meta),
+                _ => None,
+            })
+        })
+        .collect()
+}
+
+/// Writes the metadata of the frames in the JSON format of hdr10plus_tool.
+/// The frames must be in presentation order, usually `HevcParser::ordered_frames`.
+///
+/// Scenes are delimited by changes in the metadata.
+#[cfg(feature = "serde")]
+pub fn write_hdr10plus_json(frames: &[Frame], writer: &mut dyn Write) -> Result<()> {
+    let metadata: Vec<&Hdr10PlusMetadata> =
+        frames_hdr10plus(frames).into_iter().flatten().collect();
+
+    let Some(first) = metadata.first() else {
+        bail!("No HDR10+ metadata found");
+    };
+
+    let profile = first.profile();
+
+    let mut scene_info = Vec::with_capacity(metadata.len());
+    let mut scene_first_frame_index = Vec::new();
+    let mut scene_frame_numbers: Vec<usize> = Vec::new();
+
+    for (i, meta) in metadata.iter().enumerate() {
+        if i == 0 || metadata[i - 1] != *meta {
+            scene_first_frame_index.push(i);
+            scene_frame_numbers.push(0);
+        }
+
+        let scene_frame_index = scene_frame_numbers.last().copied().unwrap_or(0);
+        if let Some(count) = scene_frame_numbers.last_mut() {
+            *count += 1;
+        }
+
+        let mut info = json!({
+            "LuminanceParameters": luminance_parameters_json(&meta.windows[0]),
+            "NumberOfWindows": meta.num_windows,
+            "TargetedSystemDisplayMaximumLuminance": meta.targeted_system_display_maximum_luminance,
+            "SceneFrameIndex": scene_frame_index,
+            "SceneId": scene_first_frame_index.len() - 1,
+            "SequenceFrameIndex": i,
+        });
+
+        if profile == "B" {
+            let window = &meta.windows[0];
+
+            info["BezierCurveData"] = json!({
+                "Anchors": window.bezier_curve_anchors,
+                "KneePointX": window.knee_point_x,
+                "KneePointY": window.knee_point_y,
+            });
+        }
+
+        scene_info.push(info);
+    }
+
+    let root = json!({
+        "JSONInfo": {
+            "HDR10plusProfile": profile,
+            "Version": "1.0",
+        },
+        "SceneInfo": scene_info,
+        "SceneInfoSummary": {
+            "SceneFirstFrameIndex": scene_first_frame_index,
+            "SceneFrameNumbers": scene_frame_numbers,
+        },
+        "ToolInfo": {
+            "Tool": env!("CARGO_PKG_NAME"),
+            "Version": env!("CARGO_PKG_VERSION"),
+        }
+    });
+
+    serde_json::to_writer_pretty(writer, &root)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+fn luminance_parameters_json(window: &Hdr10PlusWindow) -> Value {
+    let (indices, values): (Vec<u8>, Vec<u32>) = window
+        .distribution_maxrgb
+        .iter()
+        .map(|d| (d.percentage, d.percentile))
+        .unzip();
+
+    json!({
+        "AverageRGB": window.average_maxrgb,
+        "LuminanceDistributions": {
+            "DistributionIndex": indices,
+            "DistributionValues": values,
+        },
+        "MaxScl": window.maxscl,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// user_data_registered_itu_t_t35 payload of a profile B frame, 400 nits target
+    const PAYLOAD: &[u8] = &[
+        0xB5, 0x00, 0x3C, 0x00, 0x01, 0x04, 0x01, 0x40, 0x00, 0x0C, 0x80, 0x2A, 0x42, 0x12, 0xA1,
+        0x08, 0x6E, 0x00, 0x6A, 0x24, 0x08, 0x00, 0x20, 0x28, 0x02, 0x10, 0x50, 0x03, 0x60, 0xC8,
+        0x05, 0x89, 0x90, 0x07, 0x2E, 0x58, 0x09, 0xB6, 0xD0, 0x13, 0x0E, 0xF8, 0x1F, 0x63, 0x18,
+        0x3E, 0x8C, 0x00, 0x40, 0x44, 0x0E, 0xA4, 0x66, 0x33, 0x53, 0x36, 0x6A, 0x00, 0x99, 0xAC,
+        0xDC, 0xCF, 0x9A, 0x00,
+    ];
+
+    #[test]
+    fn parse_profile_b() -> Result<()> {
+        assert!(Hdr10PlusMetadata::is_hdr10plus(PAYLOAD[0], &PAYLOAD[1..]));
+
+        let meta = Hdr10PlusMetadata::parse(&PAYLOAD[1..])?;
+        assert_eq!(meta.application_version, 1);
+        assert_eq!(meta.num_windows, 1);
+        assert_eq!(meta.targeted_system_display_maximum_luminance, 400);
+        assert!(!meta.targeted_system_display_actual_peak_luminance_flag);
+        assert!(!meta.mastering_display_actual_peak_luminance_flag);
+        assert_eq!(meta.profile(), "B");
+
+        let window = &meta.windows[0];
+        assert_eq!(window.maxscl, [5409, 4769, 4316]);
+        assert_eq!(window.average_maxrgb, 424);
+        assert_eq!(window.distribution_maxrgb.len(), 9);
+        assert_eq!(
+            window.distribution_maxrgb[8],
+            DistributionMaxRgb {
+                percentage: 99,
+                percentile: 4003,
+            }
+        );
+        assert_eq!((window.knee_point_x, window.knee_point_y), (17, 58));
+        assert_eq!(
+            window.bezier_curve_anchors,
+            [102, 205, 307, 410, 512, 614, 717, 819, 922]
+        );
+        assert!(!window.color_saturation_mapping_flag);
+
+        assert!(Hdr10PlusMetadata::parse(&PAYLOAD[1..20]).is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn hdr10plus_tool_json() -> Result<()> {
+        let meta = Hdr10PlusMetadata::parse(&PAYLOAD[1..])?;
+
+        let mut second_scene = meta.clone();
+        second_scene.windows[0].average_maxrgb = 500;
+
+        let frames: Vec<Frame> = [&meta, &meta, &second_scene]
+            .into_iter()
+            .map(|meta| Frame {
+                prefix_sei: vec![SeiPayload::Hdr10Plus(meta.clone())],
+                ..Default::default()
+            })
+            .collect();
+
+        let mut out = Vec::new();
+        write_hdr10plus_json(&frames, &mut out)?;
+        let value: Value = serde_json::from_slice(&out)?;
+
+        let scene_info = |average_rgb: u32, scene_id: usize, scene_frame: usize, frame: usize| {
+            json!({
+                "BezierCurveData": {
+                    "Anchors": [102, 205, 307, 410, 512, 614, 717, 819, 922],
+                    "KneePointX": 17,
+                    "KneePointY": 58
+                },
+                "LuminanceParameters": {
+                    "AverageRGB": average_rgb,
+                    "LuminanceDistributions": {
+                        "DistributionIndex": [1, 5, 10, 25, 50, 75, 90, 95, 99],
+                        "DistributionValues": [8, 132, 216, 354, 459, 621, 1219, 2008, 4003]
+                    },
+                    "MaxScl": [5409, 4769, 4316]
+                },
+                "NumberOfWindows": 1,
+                "TargetedSystemDisplayMaximumLuminance": 400,
+                "SceneFrameIndex": scene_frame,
+                "SceneId": scene_id,
+                "SequenceFrameIndex": frame
+            })
+        };
+
+        assert_eq!(
+            value,
+            json!({
+                "JSONInfo": {
+                    "HDR10plusProfile": "B",
+                    "Version": "1.0"
+                },
+                "SceneInfo": [
+                    scene_info(424, 0, 0, 0),
+                    scene_info(424, 0, 1, 1),
+                    scene_info(500, 1, 0, 2)
+                ],
+                "SceneInfoSummary": {
+                    "SceneFirstFrameIndex": [0, 2],
+                    "SceneFrameNumbers": [2, 1]
+                },
+                "ToolInfo": {
+                    "Tool": env!("CARGO_PKG_NAME"),
+                    "Version": env!("CARGO_PKG_VERSION")
+                }
+            })
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use anyhow::{Result, bail};
+
+/// user_data_registered_itu_t_t35 envelope, D.2.6
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct ItuTT35 {
+    pub country_code: u8,
+    /// Only present when `country_code` is 0xFF
+    pub country_code_extension: Option<u8>,
+
+    /// Remaining bytes, starting with the provider code
+    pub data: Vec<u8>,
+}
+
+impl ItuTT35 {
+    pub fn parse(data: &[u8]) -> Result<ItuTT35> {
+        let Some((&country_code, mut rest)) = data.split_first() else {
+            bail!("Empty ITU-T T.35 payload");
+        };
+
+        let mut country_code_extension = None;
+
+        if country_code == 0xFF {
+            let Some((&ext, remaining)) = rest.split_first() else {
+                bail!("Missing ITU-T T.35 country code extension");
+            };
+
+            country_code_extension = Some(ext);
+            rest = remaining;
+        }
+
+        Ok(ItuTT35 {
+            country_code,
+            country_code_extension,
+            data: rest.to_vec(),
+        })
+    }
+
+    /// Terminal provider code, the first two bytes after the country code
+    pub fn provider_code(&self) -> Option<u16> {
+        match self.data.as_slice() {
+            [a, b, ..] => Some(u16::from_be_bytes([*a, *b])),
+            _ => None,
+        }
+    }
+}
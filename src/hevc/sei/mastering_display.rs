@@ -0,0 +1,141 @@
+use anyhow::Result;
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+/// mastering_display_colour_volume, D.2.28
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct MasteringDisplayColourVolume {
+    /// In increments of 0.00002
+    pub display_primaries_x: [u16; 3],
+    pub display_primaries_y: [u16; 3],
+    pub white_point_x: u16,
+    pub white_point_y: u16,
+
+    /// In units of 0.0001 cd/m²
+    pub max_display_mastering_luminance: u32,
+    pub min_display_mastering_luminance: u32,
+}
+
+/// Mastering metadata in the units of the Matroska `MasteringMetadata` element
+#[derive(Default, Debug, PartialEq, Clone, Copy)]
+pub struct MatroskaMasteringMetadata {
+    pub primary_r_chromaticity_x: f64,
+    pub primary_r_chromaticity_y: f64,
+    pub primary_g_chromaticity_x: f64,
+    pub primary_g_chromaticity_y: f64,
+    pub primary_b_chromaticity_x: f64,
+    pub primary_b_chromaticity_y: f64,
+    pub white_point_chromaticity_x: f64,
+    pub white_point_chromaticity_y: f64,
+
+    /// In cd/m²
+    pub luminance_max: f64,
+    pub luminance_min: f64,
+}
+
+impl MasteringDisplayColourVolume {
+    pub fn parse(data: &[u8]) -> Result<MasteringDisplayColourVolume> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+        let mut mdcv = MasteringDisplayColourVolume::default();
+
+        for c in 0..3 {
+            mdcv.display_primaries_x[c] = reader.read::<16, u16>()?;
+            mdcv.display_primaries_y[c] = reader.read::<16, u16>()?;
+        }
+
+        mdcv.white_point_x = reader.read::<16, u16>()?;
+        mdcv.white_point_y = reader.read::<16, u16>()?;
+        mdcv.max_display_mastering_luminance = reader.read::<32, u32>()?;
+        mdcv.min_display_mastering_luminance = reader.read::<32, u32>()?;
+
+        Ok(mdcv)
+    }
+
+    /// Indices of the red, green and blue primaries.
+    /// The order is not mandated, so red has the highest x and green the highest y.
+    pub fn rgb_indices(&self) -> [usize; 3] {
+        let x = &self.display_primaries_x;
+        let y = &self.display_primaries_y;
+
+        let r = (0..3).max_by_key(|&c| x[c]).unwrap_or(2);
+        let g = (0..3)
+            .filter(|&c| c != r)
+            .max_by_key(|&c| y[c])
+            .unwrap_or(0);
+        let b = 3 - r - g;
+
+        [r, g, b]
+    }
+
+    /// x265 `--master-display` value, e.g. `G(13250,34500)B(7500,3000)R(34000,16000)WP(15635,16450)L(10000000,1)`
+    pub fn to_x265_string(&self) -> String {
+        let [r, g, b] = self.rgb_indices();
+        let x = &self.display_primaries_x;
+        let y = &self.display_primaries_y;
+
+        format!(
+            "G({},{})B({},{})R({},{})WP({},{})L({},{})",
+            x[g],
+            y[g],
+            x[b],
+            y[b],
+            x[r],
+            y[r],
+            self.white_point_x,
+            self.white_point_y,
+            self.max_display_mastering_luminance,
+            self.min_display_mastering_luminance
+        )
+    }
+
+    pub fn matroska(&self) -> MatroskaMasteringMetadata {
+        let [r, g, b] = self.rgb_indices();
+        let chroma = |v: u16| v as f64 / 50_000.0;
+
+        MatroskaMasteringMetadata {
+            primary_r_chromaticity_x: chroma(self.display_primaries_x[r]),
+            primary_r_chromaticity_y: chroma(self.display_primaries_y[r]),
+            primary_g_chromaticity_x: chroma(self.display_primaries_x[g]),
+            primary_g_chromaticity_y: chroma(self.display_primaries_y[g]),
+            primary_b_chromaticity_x: chroma(self.display_primaries_x[b]),
+            primary_b_chromaticity_y: chroma(self.display_primaries_y[b]),
+            white_point_chromaticity_x: chroma(self.white_point_x),
+            white_point_chromaticity_y: chroma(self.white_point_y),
+            luminance_max: self.max_display_mastering_luminance as f64 / 10_000.0,
+            luminance_min: self.min_display_mastering_luminance as f64 / 10_000.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Display P3 D65 in the usual G, B, R order, 1000 and 0.0001 cd/m²
+    const P3_D65: &[u8] = &[
+        0x33, 0xC2, 0x86, 0xC4, 0x1D, 0x4C, 0x0B, 0xB8, 0x84, 0xD0, 0x3E, 0x80, 0x3D, 0x13, 0x40,
+        0x42, 0x00, 0x98, 0x96, 0x80, 0x00, 0x00, 0x00, 0x01,
+    ];
+
+    #[test]
+    fn parse_p3_d65() -> Result<()> {
+        let mdcv = MasteringDisplayColourVolume::parse(P3_D65)?;
+
+        assert_eq!(mdcv.display_primaries_x, [13250, 7500, 34000]);
+        assert_eq!(mdcv.display_primaries_y, [34500, 3000, 16000]);
+        assert_eq!(mdcv.rgb_indices(), [2, 0, 1]);
+        assert_eq!(
+            mdcv.to_x265_string(),
+            "G(13250,34500)B(7500,3000)R(34000,16000)WP(15635,16450)L(10000000,1)"
+        );
+
+        let matroska = mdcv.matroska();
+        assert_eq!(matroska.primary_r_chromaticity_x, 0.68);
+        assert_eq!(matroska.white_point_chromaticity_y, 0.329);
+        assert_eq!(matroska.luminance_max, 1000.0);
+        assert_eq!(matroska.luminance_min, 0.0001);
+
+        assert!(MasteringDisplayColourVolume::parse(&P3_D65[..20]).is_err());
+
+        Ok(())
+    }
+}
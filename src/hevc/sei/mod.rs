@@ -0,0 +1,563 @@
+use super::sps::SPSNAL;
+use super::{
+    ALPHA_CHANNEL_INFO, ALTERNATIVE_TRANSFER_CHARACTERISTICS, AMBIENT_VIEWING_ENVIRONMENT,
+    BUFFERING_PERIOD, CONTENT_COLOUR_VOLUME, CONTENT_LIGHT_LEVEL_INFO, CUBEMAP_PROJECTION,
+    DECODED_PICTURE_HASH, DECODING_UNIT_INFO, EQUIRECTANGULAR_PROJECTION,
+    FILM_GRAIN_CHARACTERISTICS, FRAME_PACKING_ARRANGEMENT, MASTERING_DISPLAY_COLOUR_VOLUME,
+    NAL_EOB_NUT, NAL_EOS_NUT, NAL_SEI_PREFIX, NAL_SEI_SUFFIX, PIC_TIMING, RECOVERY_POINT,
+    REGION_WISE_PACKING, SCALABLE_NESTING, SOP_DESCRIPTION, SPHERE_ROTATION, TIME_CODE,
+    USER_DATA_REGISTERED_ITU_T_35, USER_DATA_UNREGISTERED,
+};
+use anyhow::{Result, bail};
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+pub mod a53;
+pub mod alpha_channel;
+pub mod alternative_transfer;
+pub mod ambient_viewing;
+pub mod buffering_period;
+pub mod content_colour_volume;
+pub mod content_light_level;
+pub mod decoding_unit_info;
+pub mod film_grain;
+pub mod frame_packing;
+pub mod hdr10plus;
+pub mod itu_t_t35;
+pub mod mastering_display;
+pub mod omnidirectional;
+pub mod pic_timing;
+pub mod picture_hash;
+pub mod recovery_point;
+pub mod registry;
+pub mod scalable_nesting;
+pub mod sop_description;
+pub mod time_code;
+pub mod user_data_unregistered;
+
+pub use a53::{AfdData, BarData, CcData};
+pub use alpha_channel::AlphaChannelInfo;
+pub use alternative_transfer::AlternativeTransferCharacteristics;
+pub use ambient_viewing::AmbientViewingEnvironment;
+pub use buffering_period::BufferingPeriod;
+pub use content_colour_volume::ContentColourVolume;
+pub use content_light_level::ContentLightLevelInfo;
+pub use decoding_unit_info::DecodingUnitInfo;
+pub use film_grain::FilmGrainCharacteristics;
+pub use frame_packing::FramePackingArrangement;
+pub use hdr10plus::Hdr10PlusMetadata;
+pub use itu_t_t35::ItuTT35;
+pub use mastering_display::MasteringDisplayColourVolume;
+pub use omnidirectional::{
+    CubemapProjection, EquirectangularProjection, RegionWisePacking, SphereRotation,
+};
+pub use pic_timing::PicTiming;
+pub use picture_hash::DecodedPictureHash;
+pub use recovery_point::RecoveryPoint;
+pub use registry::{CustomSei, CustomSeiPayload, SeiRegistry};
+pub use scalable_nesting::ScalableNesting;
+pub use sop_description::SopDescription;
+pub use time_code::TimeCode;
+pub use user_data_unregistered::UserDataUnregistered;
+
+#[derive(Default, Debug, Clone)]
+pub struct SeiMessage {
+    num_payload_type_ff_bytes: usize,
+    last_payload_type_byte: u8,
+
+    num_payload_size_ff_bytes: usize,
+    last_payload_size_byte: u8,
+
+    // Offset of the messame in the input slice
+    pub msg_offset: usize,
+
+    pub payload_type: u32,
+    pub payload_offset: usize,
+    pub payload_size: usize,
+}
+
+/// Decoded SEI payload.
+/// Payloads without a decoder, or that failed to decode, are kept as raw bytes.
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub enum SeiPayload {
+    BufferingPeriod(BufferingPeriod),
+    PicTiming(PicTiming),
+    UserDataRegisteredItuTT35(ItuTT35),
+    /// ST 2094-40, in user_data_registered_itu_t_t35
+    Hdr10Plus(Hdr10PlusMetadata),
+    /// ATSC A/53 closed captions, in user_data_registered_itu_t_t35
+    CcData(CcData),
+    /// Active format description, in user_data_registered_itu_t_t35
+    AfdData(AfdData),
+    /// ATSC A/53 bar data, in user_data_registered_itu_t_t35
+    BarData(BarData),
+    UserDataUnregistered(UserDataUnregistered),
+    RecoveryPoint(RecoveryPoint),
+    FilmGrainCharacteristics(FilmGrainCharacteristics),
+    FramePackingArrangement(FramePackingArrangement),
+    EquirectangularProjection(EquirectangularProjection),
+    CubemapProjection(CubemapProjection),
+    SphereRotation(SphereRotation),
+    RegionWisePacking(RegionWisePacking),
+    AlphaChannelInfo(AlphaChannelInfo),
+    MasteringDisplayColourVolume(MasteringDisplayColourVolume),
+    ContentLightLevelInfo(ContentLightLevelInfo),
+    AlternativeTransferCharacteristics(AlternativeTransferCharacteristics),
+    AmbientViewingEnvironment(AmbientViewingEnvironment),
+    ContentColourVolume(ContentColourVolume),
+    DecodingUnitInfo(DecodingUnitInfo),
+    SopDescription(SopDescription),
+    ScalableNesting(ScalableNesting),
+    DecodedPictureHash(DecodedPictureHash),
+    TimeCode(TimeCode),
+    /// Decoded by the `SeiRegistry`
+    Custom(CustomSeiPayload),
+    Unknown {
+        payload_type: u32,
+        data: Vec<u8>,
+    },
+}
+
+/// State required to decode the payloads
+#[derive(Default, Debug, Clone, Copy)]
+pub struct SeiContext<'a> {
+    /// Active SPS, for the payloads depending on it such as pic_timing
+    pub(crate) sps: Option<&'a SPSNAL>,
+    /// Parsed SPSs, for the buffering_period which references its own SPS
+    pub(crate) sps_list: &'a [SPSNAL],
+    pub registry: Option<&'a SeiRegistry>,
+}
+
+impl SeiMessage {
+    /// Assumes the data does not contain any `emulation_prevention_three_byte`s
+    pub fn parse_sei_rbsp(data: &[u8]) -> Result<Vec<SeiMessage>> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        // forbidden_zero_bit
+        reader.skip_n(1)?;
+
+        let nal_type = reader.read::<6, u8>()?;
+
+        if nal_type != NAL_SEI_PREFIX && nal_type != NAL_SEI_SUFFIX {
+            bail!("NAL type {} is not SEI", nal_type);
+        }
+
+        if reader.available()? < 9 && matches!(nal_type, NAL_EOS_NUT | NAL_EOB_NUT) {
+        } else {
+            reader.skip_n(6)?; // nuh_layer_id
+            reader.skip_n(3)?; // temporal_id
+        }
+
+        Self::parse_messages(&mut reader)
+    }
+
+    /// Parses the `sei_message`s of RBSP data without the NAL header.
+    /// Assumes the data does not contain any `emulation_prevention_three_byte`s
+    pub fn parse_sei_messages(data: &[u8]) -> Result<Vec<SeiMessage>> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        Self::parse_messages(&mut reader)
+    }
+
+    /// Payload bytes, `data` being the slice the message was parsed from
+    pub fn payload_data<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        &data[self.payload_offset..self.payload_offset + self.payload_size]
+    }
+
+    fn parse_messages(reader: &mut BsIoSliceReader) -> Result<Vec<SeiMessage>> {
+        let mut messages = Vec::new();
+
+        loop {
+            messages.push(Self::parse_sei_message(reader)?);
+
+            if reader.available()? <= 8 {
+                break;
+            }
+        }
+
+        Ok(messages)
+    }
+
+    fn parse_sei_message(reader: &mut BsIoSliceReader) -> Result<SeiMessage> {
+        let mut msg = SeiMessage {
+            msg_offset: (reader.position_in_bits()? / 8) as usize,
+            last_payload_type_byte: reader.read::<8, u8>()?,
+            ..Default::default()
+        };
+
+        while msg.last_payload_type_byte == 0xFF {
+            msg.num_payload_type_ff_bytes += 1;
+            msg.last_payload_type_byte = reader.read::<8, u8>()?;
+
+            msg.payload_type += 255;
+        }
+
+        msg.payload_type += msg.last_payload_type_byte as u32;
+
+        msg.last_payload_size_byte = reader.read::<8, u8>()?;
+        while msg.last_payload_size_byte == 0xFF {
+            msg.num_payload_size_ff_bytes += 1;
+            msg.last_payload_size_byte = reader.read::<8, u8>()?;
+
+            msg.payload_size += 255;
+        }
+
+        msg.payload_size += msg.last_payload_size_byte as usize;
+        msg.payload_offset = (reader.position_in_bits()? / 8) as usize;
+
+        if msg.payload_size > reader.available()? as usize {
+            bail!("Payload size is larger than NALU size");
+        }
+
+        reader.skip_n(msg.payload_size as u32 * 8)?;
+
+        Ok(msg)
+    }
+}
+
+impl SeiPayload {
+    /// Decodes every message of SEI RBSP data.
+    /// `with_header` indicates that the data starts with the 2 bytes NAL header.
+    pub fn parse_rbsp(data: &[u8], with_header: bool, ctx: &SeiContext) -> Result<Vec<SeiPayload>> {
+        let messages = if with_header {
+            SeiMessage::parse_sei_rbsp(data)?
+        } else {
+            SeiMessage::parse_sei_messages(data)?
+        };
+
+        Ok(messages
+            .iter()
+            .map(|msg| Self::parse(msg.payload_type, msg.payload_data(data), ctx))
+            .collect())
+    }
+
+    /// Decodes a single payload
+    pub fn parse(payload_type: u32, data: &[u8], ctx: &SeiContext) -> SeiPayload {
+        if let Some(registry) = ctx.registry.filter(|registry| !registry.is_empty()) {
+            let t35 = if payload_type == USER_DATA_REGISTERED_ITU_T_35 {
+                ItuTT35::parse(data).ok()
+            } else {
+                None
+            };
+
+            if let Some(Ok(custom)) = registry.decode(payload_type, data, t35.as_ref()) {
+                return SeiPayload::Custom(custom);
+            }
+        }
+
+        Self::parse_typed(payload_type, data, ctx)
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| SeiPayload::Unknown {
+                payload_type,
+                data: data.to_vec(),
+            })
+    }
+
+    fn parse_typed(payload_type: u32, data: &[u8], ctx: &SeiContext) -> Result<Option<Self>> {
+        Ok(match payload_type {
+            BUFFERING_PERIOD => Some(SeiPayload::BufferingPeriod(BufferingPeriod::parse(
+                data,
+                ctx.sps_list,
+            )?)),
+            PIC_TIMING => match ctx.sps {
+                Some(sps) => PicTiming::parse(data, sps)?.map(SeiPayload::PicTiming),
+                None => None,
+            },
+            USER_DATA_REGISTERED_ITU_T_35 => Some(Self::parse_itu_t_t35(ItuTT35::parse(data)?)?),
+            USER_DATA_UNREGISTERED => Some(SeiPayload::UserDataUnregistered(
+                UserDataUnregistered::parse(data)?,
+            )),
+            RECOVERY_POINT => Some(SeiPayload::RecoveryPoint(RecoveryPoint::parse(data)?)),
+            FILM_GRAIN_CHARACTERISTICS => Some(SeiPayload::FilmGrainCharacteristics(
+                FilmGrainCharacteristics::parse(data)?,
+            )),
+            FRAME_PACKING_ARRANGEMENT => Some(SeiPayload::FramePackingArrangement(
+                FramePackingArrangement::parse(data)?,
+            )),
+            EQUIRECTANGULAR_PROJECTION => Some(SeiPayload::EquirectangularProjection(
+                EquirectangularProjection::parse(data)?,
+            )),
+            CUBEMAP_PROJECTION => Some(SeiPayload::CubemapProjection(CubemapProjection::parse(
+                data,
+            )?)),
+            SPHERE_ROTATION => Some(SeiPayload::SphereRotation(SphereRotation::parse(data)?)),
+            REGION_WISE_PACKING => Some(SeiPayload::RegionWisePacking(RegionWisePacking::parse(
+                data,
+            )?)),
+            ALPHA_CHANNEL_INFO => {
+                Some(SeiPayload::AlphaChannelInfo(AlphaChannelInfo::parse(data)?))
+            }
+            MASTERING_DISPLAY_COLOUR_VOLUME => Some(SeiPayload::MasteringDisplayColourVolume(
+                MasteringDisplayColourVolume::parse(data)?,
+            )),
+            CONTENT_LIGHT_LEVEL_INFO => Some(SeiPayload::ContentLightLevelInfo(
+                ContentLightLevelInfo::parse(data)?,
+            )),
+            ALTERNATIVE_TRANSFER_CHARACTERISTICS => {
+                Some(SeiPayload::AlternativeTransferCharacteristics(
+                    AlternativeTransferCharacteristics::parse(data)?,
+                ))
+            }
+            AMBIENT_VIEWING_ENVIRONMENT => Some(SeiPayload::AmbientViewingEnvironment(
+                AmbientViewingEnvironment::parse(data)?,
+            )),
+            CONTENT_COLOUR_VOLUME => Some(SeiPayload::ContentColourVolume(
+                ContentColourVolume::parse(data)?,
+            )),
+            DECODING_UNIT_INFO => match ctx.sps {
+                Some(sps) => Some(SeiPayload::DecodingUnitInfo(DecodingUnitInfo::parse(
+                    data, sps,
+                )?)),
+                None => None,
+            },
+            SOP_DESCRIPTION => Some(SeiPayload::SopDescription(SopDescription::parse(data)?)),
+            SCALABLE_NESTING => Some(SeiPayload::ScalableNesting(ScalableNesting::parse(
+                data, ctx,
+            )?)),
+            DECODED_PICTURE_HASH => Some(SeiPayload::DecodedPictureHash(
+                DecodedPictureHash::parse(data, ctx.sps)?,
+            )),
+            TIME_CODE => Some(SeiPayload::TimeCode(TimeCode::parse(data)?)),
+            _ => None,
+        })
+    }
+
+    /// Dispatch on the country and provider codes
+    fn parse_itu_t_t35(t35: ItuTT35) -> Result<Self> {
+        Ok(
+            if Hdr10PlusMetadata::is_hdr10plus(t35.country_code, &t35.data) {
+                SeiPayload::Hdr10Plus(Hdr10PlusMetadata::parse(&t35.data)?)
+            } else if CcData::is_cc_data(t35.country_code, &t35.data) {
+                SeiPayload::CcData(CcData::parse(&t35.data)?)
+            } else if BarData::is_bar_data(t35.country_code, &t35.data) {
+                SeiPayload::BarData(BarData::parse(&t35.data)?)
+            } else if AfdData::is_afd_data(t35.country_code, &t35.data) {
+                SeiPayload::AfdData(AfdData::parse(&t35.data)?)
+            } else {
+                SeiPayload::UserDataRegisteredItuTT35(t35)
+            },
+        )
+    }
+
+    pub fn payload_type(&self) -> u32 {
+        match self {
+            SeiPayload::BufferingPeriod(_) => BUFFERING_PERIOD,
+            SeiPayload::PicTiming(_) => PIC_TIMING,
+            SeiPayload::UserDataRegisteredItuTT35(_)
+            | SeiPayload::Hdr10Plus(_)
+            | SeiPayload::CcData(_)
+            | SeiPayload::AfdData(_)
+            | SeiPayload::BarData(_) => USER_DATA_REGISTERED_ITU_T_35,
+            SeiPayload::UserDataUnregistered(_) => USER_DATA_UNREGISTERED,
+            SeiPayload::RecoveryPoint(_) => RECOVERY_POINT,
+            SeiPayload::FilmGrainCharacteristics(_) => FILM_GRAIN_CHARACTERISTICS,
+            SeiPayload::FramePackingArrangement(_) => FRAME_PACKING_ARRANGEMENT,
+            SeiPayload::EquirectangularProjection(_) => EQUIRECTANGULAR_PROJECTION,
+            SeiPayload::CubemapProjection(_) => CUBEMAP_PROJECTION,
+            SeiPayload::SphereRotation(_) => SPHERE_ROTATION,
+            SeiPayload::RegionWisePacking(_) => REGION_WISE_PACKING,
+            SeiPayload::AlphaChannelInfo(_) => ALPHA_CHANNEL_INFO,
+            SeiPayload::MasteringDisplayColourVolume(_) => MASTERING_DISPLAY_COLOUR_VOLUME,
+            SeiPayload::ContentLightLevelInfo(_) => CONTENT_LIGHT_LEVEL_INFO,
+            SeiPayload::AlternativeTransferCharacteristics(_) => {
+                ALTERNATIVE_TRANSFER_CHARACTERISTICS
+            }
+            SeiPayload::AmbientViewingEnvironment(_) => AMBIENT_VIEWING_ENVIRONMENT,
+            SeiPayload::ContentColourVolume(_) => CONTENT_COLOUR_VOLUME,
+            SeiPayload::DecodingUnitInfo(_) => DECODING_UNIT_INFO,
+            SeiPayload::SopDescription(_) => SOP_DESCRIPTION,
+            SeiPayload::ScalableNesting(_) => SCALABLE_NESTING,
+            SeiPayload::DecodedPictureHash(_) => DECODED_PICTURE_HASH,
+            SeiPayload::TimeCode(_) => TIME_CODE,
+            SeiPayload::Custom(custom) => custom.payload_type,
+            SeiPayload::Unknown { payload_type, .. } => *payload_type,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ATSC cc_data with a single field 1 pair
+    const CC_DATA: &[u8] = &[
+        0xB5, 0x00, 0x31, 0x47, 0x41, 0x39, 0x34, 0x03, 0x41, 0xFF, 0xFC, 0x94, 0x2C,
+    ];
+
+    /// Prefix SEI NAL with a recovery point, cc_data,
+    /// a truncated mastering display colour volume and a reserved payload type
+    fn sei_nal() -> Vec<u8> {
+        [
+            &[0x4E, 0x01],
+            [0x06, 0x02, 0x11, 0x00].as_slice(),
+            &[0x04, CC_DATA.len() as u8],
+            CC_DATA,
+            &[0x89, 0x02, 0x00, 0x00],
+            &[0xC8, 0x01, 0xAB],
+            &[0x80],
+        ]
+        .concat()
+    }
+
+    fn parse_t35(data: &[u8], ctx: &SeiContext) -> SeiPayload {
+        SeiPayload::parse(USER_DATA_REGISTERED_ITU_T_35, data, ctx)
+    }
+
+    #[test]
+    fn parse_rbsp() -> Result<()> {
+        let data = sei_nal();
+        let payloads = SeiPayload::parse_rbsp(&data, true, &SeiContext::default())?;
+
+        assert_eq!(
+            payloads[0],
+            SeiPayload::RecoveryPoint(RecoveryPoint {
+                recovery_poc_cnt: 4,
+                exact_match_flag: true,
+                broken_link_flag: false,
+            })
+        );
+
+        let SeiPayload::CcData(cc_data) = &payloads[1] else {
+            panic!("Expected cc_data, got {:?}", payloads[1]);
+        };
+        assert!(cc_data.process_cc_data_flag);
+        assert_eq!(cc_data.constructs.len(), 1);
+        assert_eq!(
+            (
+                cc_data.constructs[0].cc_data_1,
+                cc_data.constructs[0].cc_data_2
+            ),
+            (0x94, 0x2C)
+        );
+
+        // Failed to decode
+        assert_eq!(
+            payloads[2],
+            SeiPayload::Unknown {
+                payload_type: MASTERING_DISPLAY_COLOUR_VOLUME,
+                data: vec![0, 0],
+            }
+        );
+        // No decoder
+        assert_eq!(
+            payloads[3],
+            SeiPayload::Unknown {
+                payload_type: 200,
+                data: vec![0xAB],
+            }
+        );
+
+        // Same messages without the NAL header
+        assert_eq!(
+            SeiPayload::parse_rbsp(&data[2..], false, &SeiContext::default())?,
+            payloads
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn itu_t_t35_dispatch() {
+        let ctx = SeiContext::default();
+
+        // HDR10+ is checked first, an invalid payload isn't decoded as generic T.35
+        let hdr10plus = [0xB5, 0x00, 0x3C, 0x00, 0x01, 0x04];
+        assert!(matches!(
+            parse_t35(&hdr10plus, &ctx),
+            SeiPayload::Unknown { .. }
+        ));
+
+        assert!(matches!(parse_t35(CC_DATA, &ctx), SeiPayload::CcData(_)));
+
+        let bar_data = [
+            0xB5, 0x00, 0x31, 0x47, 0x41, 0x39, 0x34, 0x06, 0xC0, 0xC0, 0x8C, 0xC3, 0xAC,
+        ];
+        assert_eq!(
+            parse_t35(&bar_data, &ctx),
+            SeiPayload::BarData(BarData {
+                line_number_end_of_top_bar: Some(140),
+                line_number_start_of_bottom_bar: Some(940),
+                ..Default::default()
+            })
+        );
+
+        let afd_data = [0xB5, 0x00, 0x31, 0x44, 0x54, 0x47, 0x31, 0x41, 0xF8];
+        assert_eq!(
+            parse_t35(&afd_data, &ctx),
+            SeiPayload::AfdData(AfdData {
+                active_format: Some(8)
+            })
+        );
+
+        // GA94 with another user_data_type_code
+        let mut other_ga94 = CC_DATA.to_vec();
+        other_ga94[7] = 0x05;
+        assert_eq!(
+            parse_t35(&other_ga94, &ctx),
+            SeiPayload::UserDataRegisteredItuTT35(ItuTT35 {
+                country_code: 0xB5,
+                country_code_extension: None,
+                data: other_ga94[1..].to_vec(),
+            })
+        );
+
+        assert_eq!(
+            parse_t35(&[0xFF, 0x01, 0x12, 0x34], &ctx),
+            SeiPayload::UserDataRegisteredItuTT35(ItuTT35 {
+                country_code: 0xFF,
+                country_code_extension: Some(0x01),
+                data: vec![0x12, 0x34],
+            })
+        );
+    }
+
+    #[test]
+    fn registry_precedence() -> Result<()> {
+        let mut registry = SeiRegistry::default();
+        registry.register_payload_type(RECOVERY_POINT, |data| Ok(Box::new(data.to_vec())));
+        registry.register_itu_t_t35(0xB5, 0x0031, |data| Ok(Box::new(data.len())));
+        // Falls back to the crate's decoder
+        registry.register_payload_type(MASTERING_DISPLAY_COLOUR_VOLUME, |_| {
+            anyhow::bail!("Custom decoder error")
+        });
+
+        let ctx = SeiContext {
+            registry: Some(&registry),
+            ..Default::default()
+        };
+        let payloads = SeiPayload::parse_rbsp(&sei_nal(), true, &ctx)?;
+
+        let SeiPayload::Custom(recovery_point) = &payloads[0] else {
+            panic!("Expected a custom payload, got {:?}", payloads[0]);
+        };
+        assert_eq!(recovery_point.payload_type, RECOVERY_POINT);
+        assert_eq!(
+            recovery_point.downcast_ref::<Vec<u8>>(),
+            Some(&vec![0x11, 0x00])
+        );
+
+        // The T.35 decoder receives the data after the country code
+        let SeiPayload::Custom(cc_data) = &payloads[1] else {
+            panic!("Expected a custom payload, got {:?}", payloads[1]);
+        };
+        assert_eq!(cc_data.payload_type, USER_DATA_REGISTERED_ITU_T_35);
+        assert_eq!(cc_data.downcast_ref::<usize>(), Some(&(CC_DATA.len() - 1)));
+
+        assert!(matches!(payloads[2], SeiPayload::Unknown { .. }));
+        assert!(matches!(payloads[3], SeiPayload::Unknown { .. }));
+
+        // Payload type decoders take precedence over the T.35 ones
+        registry.register_payload_type(USER_DATA_REGISTERED_ITU_T_35, |_| Ok(Box::new("t35")));
+        let ctx = SeiContext {
+            registry: Some(&registry),
+            ..Default::default()
+        };
+
+        let SeiPayload::Custom(custom) = parse_t35(CC_DATA, &ctx) else {
+            panic!("Expected a custom payload");
+        };
+        assert_eq!(custom.downcast_ref::<&str>(), Some(&"t35"));
+
+        Ok(())
+    }
+}
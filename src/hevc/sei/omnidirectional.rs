@@ -0,0 +1,475 @@
+use anyhow::Result;
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+use super::super::Frame;
+use super::SeiPayload;
+
+/// equirectangular_projection, D.2.41.2
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct EquirectangularProjection {
+    /// Cancels the persistence of the previous projection, nothing else is present
+    pub erp_cancel_flag: bool,
+    pub erp_persistence_flag: bool,
+
+    pub erp_guard_band_flag: bool,
+    pub erp_guard_band_type: u8,
+    pub erp_left_guard_band_width: u8,
+    pub erp_right_guard_band_width: u8,
+}
+
+/// cubemap_projection, D.2.41.3
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct CubemapProjection {
+    pub cmp_cancel_flag: bool,
+    pub cmp_persistence_flag: bool,
+}
+
+/// sphere_rotation, D.2.41.4.
+/// The angles are in units of 2^-16 degrees.
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct SphereRotation {
+    pub sphere_rotation_cancel_flag: bool,
+    pub sphere_rotation_persistence_flag: bool,
+
+    pub yaw_rotation: i32,
+    pub pitch_rotation: i32,
+    pub roll_rotation: i32,
+}
+
+/// regionwise_packing, D.2.41.6
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct RegionWisePacking {
+    pub rwp_cancel_flag: bool,
+    pub rwp_persistence_flag: bool,
+    pub constituent_picture_matching_flag: bool,
+
+    pub proj_picture_width: u32,
+    pub proj_picture_height: u32,
+    pub packed_picture_width: u16,
+    pub packed_picture_height: u16,
+
+    pub regions: Vec<PackedRegion>,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct PackedRegion {
+    /// 0: none, 1: horizontal mirroring, 2 to 7: rotations with or without mirroring
+    pub rwp_transform_type: u8,
+
+    pub proj_region_width: u32,
+    pub proj_region_height: u32,
+    pub proj_region_top: u32,
+    pub proj_region_left: u32,
+
+    pub packed_region_width: u16,
+    pub packed_region_height: u16,
+    pub packed_region_top: u16,
+    pub packed_region_left: u16,
+
+    pub guard_band: Option<RegionGuardBand>,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct RegionGuardBand {
+    pub rwp_left_guard_band_width: u8,
+    pub rwp_right_guard_band_width: u8,
+    pub rwp_top_guard_band_height: u8,
+    pub rwp_bottom_guard_band_height: u8,
+    pub rwp_guard_band_not_used_for_pred_flag: bool,
+    pub rwp_guard_band_type: [u8; 4],
+}
+
+/// Projection of 360° video
+#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+pub enum Projection {
+    Equirectangular(EquirectangularProjection),
+    Cubemap,
+}
+
+/// Omnidirectional video signalling applying to a frame
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub struct SphericalVideo {
+    pub projection: Projection,
+    pub sphere_rotation: Option<SphereRotation>,
+    pub region_wise_packing: Option<RegionWisePacking>,
+}
+
+impl EquirectangularProjection {
+    pub fn parse(data: &[u8]) -> Result<EquirectangularProjection> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        let mut erp = EquirectangularProjection {
+            erp_cancel_flag: reader.read_bit()?,
+            ..Default::default()
+        };
+
+        if !erp.erp_cancel_flag {
+            erp.erp_persistence_flag = reader.read_bit()?;
+            erp.erp_guard_band_flag = reader.read_bit()?;
+
+            // erp_reserved_zero_2bits
+            reader.skip_n(2)?;
+
+            if erp.erp_guard_band_flag {
+                erp.erp_guard_band_type = reader.read::<3, u8>()?;
+                erp.erp_left_guard_band_width = reader.read::<8, u8>()?;
+                erp.erp_right_guard_band_width = reader.read::<8, u8>()?;
+            }
+        }
+
+        Ok(erp)
+    }
+}
+
+impl CubemapProjection {
+    pub fn parse(data: &[u8]) -> Result<CubemapProjection> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        let cmp_cancel_flag = reader.read_bit()?;
+
+        Ok(CubemapProjection {
+            cmp_cancel_flag,
+            cmp_persistence_flag: !cmp_cancel_flag && reader.read_bit()?,
+        })
+    }
+}
+
+impl SphereRotation {
+    pub fn parse(data: &[u8]) -> Result<SphereRotation> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        let mut rotation = SphereRotation {
+            sphere_rotation_cancel_flag: reader.read_bit()?,
+            ..Default::default()
+        };
+
+        if !rotation.sphere_rotation_cancel_flag {
+            rotation.sphere_rotation_persistence_flag = reader.read_bit()?;
+
+            // sphere_rotation_reserved_zero_6bits
+            reader.skip_n(6)?;
+
+            rotation.yaw_rotation = reader.read::<32, u32>()? as i32;
+            rotation.pitch_rotation = reader.read::<32, u32>()? as i32;
+            rotation.roll_rotation = reader.read::<32, u32>()? as i32;
+        }
+
+        Ok(rotation)
+    }
+
+    pub fn yaw_degrees(&self) -> f64 {
+        self.yaw_rotation as f64 / 65536.0
+    }
+
+    pub fn pitch_degrees(&self) -> f64 {
+        self.pitch_rotation as f64 / 65536.0
+    }
+
+    pub fn roll_degrees(&self) -> f64 {
+        self.roll_rotation as f64 / 65536.0
+    }
+}
+
+impl RegionWisePacking {
+    pub fn parse(data: &[u8]) -> Result<RegionWisePacking> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        let mut rwp = RegionWisePacking {
+            rwp_cancel_flag: reader.read_bit()?,
+            ..Default::default()
+        };
+
+        if rwp.rwp_cancel_flag {
+            return Ok(rwp);
+        }
+
+        rwp.rwp_persistence_flag = reader.read_bit()?;
+        rwp.constituent_picture_matching_flag = reader.read_bit()?;
+
+        // rwp_reserved_zero_5bits
+        reader.skip_n(5)?;
+
+        let num_packed_regions = reader.read::<8, u8>()?;
+
+        rwp.proj_picture_width = reader.read::<32, u32>()?;
+        rwp.proj_picture_height = reader.read::<32, u32>()?;
+        rwp.packed_picture_width = reader.read::<16, u16>()?;
+        rwp.packed_picture_height = reader.read::<16, u16>()?;
+
+        for _ in 0..num_packed_regions {
+            // rwp_reserved_zero_4bits
+            reader.skip_n(4)?;
+
+            let mut region = PackedRegion {
+                rwp_transform_type: reader.read::<3, u8>()?,
+                ..Default::default()
+            };
+
+            let rwp_guard_band_flag = reader.read_bit()?;
+
+            region.proj_region_width = reader.read::<32, u32>()?;
+            region.proj_region_height = reader.read::<32, u32>()?;
+            region.proj_region_top = reader.read::<32, u32>()?;
+            region.proj_region_left = reader.read::<32, u32>()?;
+            region.packed_region_width = reader.read::<16, u16>()?;
+            region.packed_region_height = reader.read::<16, u16>()?;
+            region.packed_region_top = reader.read::<16, u16>()?;
+            region.packed_region_left = reader.read::<16, u16>()?;
+
+            if rwp_guard_band_flag {
+                let mut guard_band = RegionGuardBand {
+                    rwp_left_guard_band_width: reader.read::<8, u8>()?,
+                    rwp_right_guard_band_width: reader.read::<8, u8>()?,
+                    rwp_top_guard_band_height: reader.read::<8, u8>()?,
+                    rwp_bottom_guard_band_height: reader.read::<8, u8>()?,
+                    rwp_guard_band_not_used_for_pred_flag: reader.read_bit()?,
+                    ..Default::default()
+                };
+
+                for guard_band_type in guard_band.rwp_guard_band_type.iter_mut() {
+                    *guard_band_type = reader.read::<3, u8>()?;
+                }
+
+                // rwp_guard_band_reserved_zero_3bits
+                reader.skip_n(3)?;
+
+                region.guard_band = Some(guard_band);
+            }
+
+            rwp.regions.push(region);
+        }
+
+        Ok(rwp)
+    }
+}
+
+impl SphericalVideo {
+    /// Matroska `ProjectionType` element value
+    pub fn matroska_projection_type(&self) -> u8 {
+        match self.projection {
+            Projection::Equirectangular(_) => 1,
+            Projection::Cubemap => 2,
+        }
+    }
+}
+
+/// Omnidirectional signalling applying to each frame, in the order of the frames.
+/// `None` for the frames without projection.
+///
+/// Persistent messages apply to the following frames until cancelled or replaced,
+/// so the frames should be in presentation order, usually `HevcParser::ordered_frames`.
+pub fn frames_spherical_video(frames: &[Frame]) -> Vec<Option<SphericalVideo>> {
+    let mut erp: Option<&EquirectangularProjection> = None;
+    let mut cmp: Option<&CubemapProjection> = None;
+    let mut rotation: Option<&SphereRotation> = None;
+    let mut rwp: Option<&RegionWisePacking> = None;
+
+    frames
+        .iter()
+        .map(|f| {
+            if f.starts_clvs() {
+                (erp, cmp, rotation, rwp) = (None, None, None, None);
+            }
+
+            let mut current_erp = erp;
+            let mut current_cmp = cmp;
+            let mut current_rotation = rotation;
+            let mut current_rwp = rwp;
+
+            for sei in &f.prefix_sei {
+                match sei {
+                    SeiPayload::EquirectangularProjection(msg) => {
+                        current_erp = (!msg.erp_cancel_flag).then_some(msg);
+                        erp = current_erp.filter(|msg| msg.erp_persistence_flag);
+                    }
+                    SeiPayload::CubemapProjection(msg) => {
+                        current_cmp = (!msg.cmp_cancel_flag).then_some(msg);
+                        cmp = current_cmp.filter(|msg| msg.cmp_persistence_flag);
+                    }
+                    SeiPayload::SphereRotation(msg) => {
+                        current_rotation = (!msg.sphere_rotation_cancel_flag).then_some(msg);
+                        rotation =
+                            current_rotation.filter(|msg| msg.sphere_rotation_persistence_flag);
+                    }
+                    SeiPayload::RegionWisePacking(msg) => {
+                        current_rwp = (!msg.rwp_cancel_flag).then_some(msg);
+                        rwp = current_rwp.filter(|msg| msg.rwp_persistence_flag);
+                    }
+                    _ => (),
+                }
+            }
+
+            let projection = match (current_erp, current_cmp) {
+                (Some(erp), _) => Projection::Equirectangular(*erp),
+                (None, Some(_)) => Projection::Cubemap,
+                (None, None) => return None,
+            };
+
+            Some(SphericalVideo {
+                projection,
+                sphere_rotation: current_rotation.copied(),
+                region_wise_packing: current_rwp.cloned(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hevc::{NAL_IDR_N_LP, NAL_TRAIL_R, NALUnit};
+
+    /// Persistent, with 8 samples guard bands
+    const ERP: &[u8] = &[0x61, 0x08, 0x08];
+    /// Yaw 90°, pitch -45°, for the current picture
+    const ROTATION: &[u8] = &[
+        0x00, 0x00, 0x5A, 0x00, 0x00, 0xFF, 0xD3, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    /// Persistent, a single region with guard bands
+    const RWP: &[u8] = &[
+        0x40, 0x01, 0x00, 0x00, 0x0F, 0x00, 0x00, 0x00, 0x07, 0x80, 0x07, 0x80, 0x04, 0x38, 0x01,
+        0x00, 0x00, 0x0F, 0x00, 0x00, 0x00, 0x07, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x07, 0x80, 0x04, 0x38, 0x00, 0x00, 0x00, 0x00, 0x02, 0x02, 0x00, 0x00, 0x92, 0x00,
+    ];
+    const CANCEL: &[u8] = &[0x80];
+    /// Persistent
+    const CMP: &[u8] = &[0x40];
+
+    fn frame(nal_type: u8, prefix_sei: Vec<SeiPayload>) -> Frame {
+        Frame {
+            nals: vec![NALUnit {
+                nal_type,
+                ..Default::default()
+            }],
+            prefix_sei,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse() -> Result<()> {
+        let erp = EquirectangularProjection::parse(ERP)?;
+        assert!(erp.erp_persistence_flag && erp.erp_guard_band_flag);
+        assert_eq!(erp.erp_guard_band_type, 1);
+        assert_eq!(
+            (
+                erp.erp_left_guard_band_width,
+                erp.erp_right_guard_band_width
+            ),
+            (8, 8)
+        );
+        assert!(EquirectangularProjection::parse(CANCEL)?.erp_cancel_flag);
+
+        assert!(CubemapProjection::parse(CMP)?.cmp_persistence_flag);
+        assert_eq!(
+            CubemapProjection::parse(CANCEL)?,
+            CubemapProjection {
+                cmp_cancel_flag: true,
+                cmp_persistence_flag: false,
+            }
+        );
+
+        let rotation = SphereRotation::parse(ROTATION)?;
+        assert!(!rotation.sphere_rotation_persistence_flag);
+        assert_eq!(rotation.yaw_degrees(), 90.0);
+        assert_eq!(rotation.pitch_degrees(), -45.0);
+        assert_eq!(rotation.roll_degrees(), 0.0);
+
+        let rwp = RegionWisePacking::parse(RWP)?;
+        assert_eq!(
+            (rwp.proj_picture_width, rwp.proj_picture_height),
+            (3840, 1920)
+        );
+        assert_eq!(
+            (rwp.packed_picture_width, rwp.packed_picture_height),
+            (1920, 1080)
+        );
+        assert_eq!(
+            rwp.regions,
+            [PackedRegion {
+                rwp_transform_type: 0,
+                proj_region_width: 3840,
+                proj_region_height: 1920,
+                packed_region_width: 1920,
+                packed_region_height: 1080,
+                guard_band: Some(RegionGuardBand {
+                    rwp_left_guard_band_width: 2,
+                    rwp_right_guard_band_width: 2,
+                    rwp_guard_band_not_used_for_pred_flag: true,
+                    rwp_guard_band_type: [1, 1, 0, 0],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }]
+        );
+
+        assert!(RegionWisePacking::parse(&RWP[..30]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn persistence() -> Result<()> {
+        let erp = EquirectangularProjection::parse(ERP)?;
+        let rotation = SphereRotation::parse(ROTATION)?;
+        let rwp = RegionWisePacking::parse(RWP)?;
+
+        let frames = [
+            frame(
+                NAL_IDR_N_LP,
+                vec![
+                    SeiPayload::EquirectangularProjection(erp),
+                    SeiPayload::SphereRotation(rotation),
+                ],
+            ),
+            frame(NAL_TRAIL_R, vec![]),
+            frame(
+                NAL_TRAIL_R,
+                vec![SeiPayload::RegionWisePacking(rwp.clone())],
+            ),
+            frame(
+                NAL_TRAIL_R,
+                vec![SeiPayload::EquirectangularProjection(
+                    EquirectangularProjection::parse(CANCEL)?,
+                )],
+            ),
+            // The region-wise packing still applies
+            frame(
+                NAL_TRAIL_R,
+                vec![SeiPayload::CubemapProjection(CubemapProjection::parse(
+                    CMP,
+                )?)],
+            ),
+            // New CLVS
+            frame(NAL_IDR_N_LP, vec![]),
+        ];
+
+        let spherical = |projection, sphere_rotation, region_wise_packing| {
+            Some(SphericalVideo {
+                projection,
+                sphere_rotation,
+                region_wise_packing,
+            })
+        };
+
+        assert_eq!(
+            frames_spherical_video(&frames),
+            [
+                spherical(Projection::Equirectangular(erp), Some(rotation), None),
+                spherical(Projection::Equirectangular(erp), None, None),
+                spherical(Projection::Equirectangular(erp), None, Some(rwp.clone())),
+                None,
+                spherical(Projection::Cubemap, None, Some(rwp)),
+                None,
+            ]
+        );
+        assert_eq!(
+            frames_spherical_video(&frames[4..5])[0]
+                .as_ref()
+                .map(SphericalVideo::matroska_projection_type),
+            Some(2)
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+use anyhow::Result;
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+use super::super::sps::SPSNAL;
+use super::super::stream_info::FieldOrder;
+
+/// pic_timing SEI, D.2.3
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct PicTiming {
+    /// Whether `pic_struct`, `source_scan_type` and `duplicate_flag` are present
+    pub frame_field_info_present_flag: bool,
+    pub pic_struct: u8,
+    pub source_scan_type: u8,
+    pub duplicate_flag: bool,
+
+    /// Present when the HRD parameters signal CpbDpbDelaysPresentFlag
+    pub hrd: Option<PicTimingHrd>,
+}
+
+/// CPB and DPB delays of the picture, in clock ticks
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct PicTimingHrd {
+    pub au_cpb_removal_delay_minus1: u32,
+    pub pic_dpb_output_delay: u32,
+    /// In sub-picture clock ticks, with sub-picture HRD parameters
+    pub pic_dpb_output_du_delay: Option<u32>,
+
+    /// Present when `sub_pic_cpb_params_in_pic_timing_sei_flag` is set
+    pub decoding_units: Option<PicTimingDecodingUnits>,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct PicTimingDecodingUnits {
+    pub num_decoding_units_minus1: u64,
+    /// Present when every decoding unit uses the same increment
+    pub du_common_cpb_removal_delay_increment_minus1: Option<u32>,
+
+    /// One per decoding unit
+    pub num_nalus_in_du_minus1: Vec<u64>,
+    /// One per decoding unit but the last, without common increment
+    pub du_cpb_removal_delay_increment_minus1: Vec<u32>,
+}
+
+impl PicTiming {
+    /// Parses the payload of a pic_timing SEI message.
+    /// Returns `None` when the SPS signals neither `frame_field_info_present_flag` nor CPB/DPB delays.
+    pub fn parse(data: &[u8], sps: &SPSNAL) -> Result<Option<PicTiming>> {
+        let frame_field_info_present_flag =
+            sps.vui_present && sps.vui_parameters.frame_field_info_present_flag;
+        let hrd = sps
+            .hrd_parameters()
+            .filter(|hrd| hrd.cpb_dpb_delays_present());
+
+        if !frame_field_info_present_flag && hrd.is_none() {
+            return Ok(None);
+        }
+
+        let mut reader = BsIoSliceReader::from_slice(data);
+        let mut pic_timing = PicTiming {
+            frame_field_info_present_flag,
+            ..Default::default()
+        };
+
+        if frame_field_info_present_flag {
+            pic_timing.pic_struct = reader.read::<4, u8>()?;
+            pic_timing.source_scan_type = reader.read::<2, u8>()?;
+            pic_timing.duplicate_flag = reader.read_bit()?;
+        }
+
+        if let Some(hrd) = hrd {
+            let mut delays = PicTimingHrd {
+                au_cpb_removal_delay_minus1: reader
+                    .read_var(hrd.au_cpb_removal_delay_length_minus1 as u32 + 1)?,
+                pic_dpb_output_delay: reader
+                    .read_var(hrd.dpb_output_delay_length_minus1 as u32 + 1)?,
+                ..Default::default()
+            };
+
+            if hrd.sub_pic_hrd_params_present_flag {
+                delays.pic_dpb_output_du_delay =
+                    Some(reader.read_var(hrd.dpb_output_delay_du_length_minus1 as u32 + 1)?);
+
+                if hrd.sub_pic_cpb_params_in_pic_timing_sei_flag {
+                    let increment_length =
+                        hrd.du_cpb_removal_delay_increment_length_minus1 as u32 + 1;
+
+                    let mut units = PicTimingDecodingUnits {
+                        num_decoding_units_minus1: reader.read_ue()?,
+                        ..Default::default()
+                    };
+
+                    let du_common_cpb_removal_delay_flag = reader.read_bit()?;
+                    if du_common_cpb_removal_delay_flag {
+                        units.du_common_cpb_removal_delay_increment_minus1 =
+                            Some(reader.read_var(increment_length)?);
+                    }
+
+                    for i in 0..=units.num_decoding_units_minus1 {
+                        units.num_nalus_in_du_minus1.push(reader.read_ue()?);
+
+                        if !du_common_cpb_removal_delay_flag && i < units.num_decoding_units_minus1
+                        {
+                            units
+                                .du_cpb_removal_delay_increment_minus1
+                                .push(reader.read_var(increment_length)?);
+                        }
+                    }
+
+                    delays.decoding_units = Some(units);
+                }
+            }
+
+            pic_timing.hrd = Some(delays);
+        }
+
+        Ok(Some(pic_timing))
+    }
+
+    /// The picture is a single field, pic_struct 1, 2 or 9 to 12
+    pub fn is_field(&self) -> bool {
+        matches!(self.pic_struct, 1 | 2 | 9..=12)
+    }
+
+    pub fn is_top_field(&self) -> bool {
+        matches!(self.pic_struct, 1 | 9 | 11)
+    }
+
+    pub fn is_bottom_field(&self) -> bool {
+        matches!(self.pic_struct, 2 | 10 | 12)
+    }
+
+    /// Field paired with the previous field in output order, pic_struct 9 or 10
+    pub fn is_paired_with_previous(&self) -> bool {
+        matches!(self.pic_struct, 9 | 10)
+    }
+
+    /// Field order of the picture, `None` if it is not interlaced
+    pub fn field_order(&self) -> Option<FieldOrder> {
+        match self.pic_struct {
+            1 | 3 | 5 | 9 | 11 => Some(FieldOrder::TopFieldFirst),
+            2 | 4 | 6 | 10 | 12 => Some(FieldOrder::BottomFieldFirst),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::io::Write;
+
+use anyhow::{Result, bail};
+
+use super::super::Frame;
+use super::super::sps::SPSNAL;
+use super::SeiPayload;
+
+/// decoded_picture_hash, D.3.19
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct DecodedPictureHash {
+    pub hash_type: u8,
+    /// Hash of each colour plane, big endian
+    pub planes: Vec<Vec<u8>>,
+}
+
+impl DecodedPictureHash {
+    /// Without SPS, the number of planes is deduced from the payload size
+    pub fn parse(data: &[u8], sps: Option<&SPSNAL>) -> Result<DecodedPictureHash> {
+        let Some((&hash_type, hashes)) = data.split_first() else {
+            bail!("Empty decoded_picture_hash payload");
+        };
+
+        let hash_size = match hash_type {
+            0 => 16,
+            1 => 2,
+            2 => 4,
+            _ => bail!("Invalid decoded picture hash_type {}", hash_type),
+        };
+
+        let num_planes = match sps {
+            // `chroma_format_idc` is also 0 with separate colour planes
+            Some(sps) if sps.chroma_format_idc == 0 && !sps.separate_colour_plane_flag => 1,
+            Some(_) => 3,
+            None => (hashes.len() / hash_size).clamp(1, 3),
+        };
+
+        if hashes.len() < num_planes * hash_size {
+            bail!("decoded_picture_hash payload is too short");
+        }
+
+        Ok(DecodedPictureHash {
+            hash_type,
+            planes: hashes
+                .chunks_exact(hash_size)
+                .take(num_planes)
+                .map(|hash| hash.to_vec())
+                .collect(),
+        })
+    }
+
+    pub fn hash_type_name(&self) -> &'static str {
+        match self.hash_type {
+            0 => "MD5",
+            1 => "CRC",
+            _ => "Checksum",
+        }
+    }
+
+    /// Lowercase hex of each plane, separated by commas, as printed by HM
+    pub fn to_hex_string(&self) -> String {
+        self.planes
+            .iter()
+            .map(|hash| hash.iter().map(|b| format!("{b:02x}")).collect::<String>())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Decoded picture hash of each frame, in the order of the frames
+pub fn frames_picture_hash(frames: &[Frame]) -> Vec<Option<&DecodedPictureHash>> {
+    frames
+        .iter()
+        .map(|f| {
+            f.suffix_sei
+                .iter()
+                .chain(&f.prefix_sei)
+                .find_map(|sei| match sei {
+                    SeiPayload::DecodedPictureHash(hash) => Some(hash),
+                    _ => None,
+                })
+        })
+        .collect()
+}
+
+/// Writes a line per frame with a hash, in the format of the HM decoder's
+/// `--SEIDecodedPictureHash` log, e.g. `POC    0 [MD5:<Y>,<Cb>,<Cr>]`.
+/// The frames should be in presentation order, usually `HevcParser::ordered_frames`.
+pub fn write_picture_hashes(frames: &[Frame], writer: &mut dyn Write) -> Result<()> {
+    for (frame, hash) in frames.iter().zip(frames_picture_hash(frames)) {
+        if let Some(hash) = hash {
+            writeln!(
+                writer,
+                "POC {:4} [{}:{}]",
+                frame.first_slice.pic_order_cnt_val,
+                hash.hash_type_name(),
+                hash.to_hex_string()
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn planes_from_sps() -> Result<()> {
+        let data = [[1].as_slice(), &[0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc]].concat();
+
+        let mut sps = SPSNAL::default();
+        let hash = DecodedPictureHash::parse(&data, Some(&sps))?;
+        assert_eq!(hash.hash_type_name(), "CRC");
+        assert_eq!(hash.to_hex_string(), "1234");
+
+        sps.chroma_format_idc = 1;
+        let hash = DecodedPictureHash::parse(&data, Some(&sps))?;
+        assert_eq!(hash.to_hex_string(), "1234,5678,9abc");
+
+        // 4:4:4 coded as separate planes
+        sps.chroma_format_idc = 0;
+        sps.separate_colour_plane_flag = true;
+        let hash = DecodedPictureHash::parse(&data, Some(&sps))?;
+        assert_eq!(hash.planes.len(), 3);
+
+        assert!(DecodedPictureHash::parse(&data[..5], Some(&sps)).is_err());
+
+        Ok(())
+    }
+}
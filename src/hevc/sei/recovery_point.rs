@@ -0,0 +1,66 @@
+use anyhow::Result;
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+/// recovery_point, D.2.8.
+/// Decoding can start at the associated picture, the pictures are correct from
+/// the POC `recovery_poc_cnt` after it in output order.
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct RecoveryPoint {
+    pub recovery_poc_cnt: i64,
+    pub exact_match_flag: bool,
+    pub broken_link_flag: bool,
+}
+
+impl RecoveryPoint {
+    pub fn parse(data: &[u8]) -> Result<RecoveryPoint> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        Ok(RecoveryPoint {
+            recovery_poc_cnt: reader.read_se()?,
+            exact_match_flag: reader.read_bit()?,
+            broken_link_flag: reader.read_bit()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hevc::{Frame, SeiPayload};
+
+    #[test]
+    fn parse() -> Result<()> {
+        // recovery_poc_cnt 4 and exact_match_flag
+        assert_eq!(
+            RecoveryPoint::parse(&[0x11, 0x00])?,
+            RecoveryPoint {
+                recovery_poc_cnt: 4,
+                exact_match_flag: true,
+                broken_link_flag: false,
+            }
+        );
+
+        // recovery_poc_cnt -2 and broken_link_flag
+        let recovery_point = RecoveryPoint::parse(&[0x2A])?;
+        assert_eq!(recovery_point.recovery_poc_cnt, -2);
+        assert!(!recovery_point.exact_match_flag && recovery_point.broken_link_flag);
+
+        assert!(RecoveryPoint::parse(&[]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn random_access_point() -> Result<()> {
+        let mut frame = Frame::default();
+        assert!(!frame.is_random_access_point());
+
+        frame
+            .prefix_sei
+            .push(SeiPayload::RecoveryPoint(RecoveryPoint::parse(&[0x2A])?));
+        assert!(frame.is_random_access_point());
+        assert_eq!(frame.recovery_point().unwrap().recovery_poc_cnt, -2);
+
+        Ok(())
+    }
+}
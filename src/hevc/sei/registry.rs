@@ -0,0 +1,145 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use super::ItuTT35;
+
+/// Value produced by a registered SEI decoder
+pub trait CustomSei: Any + fmt::Debug + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any + fmt::Debug + Send + Sync> CustomSei for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Decoder for a payload, returning the application's own type
+pub type SeiDecoderFn = Arc<dyn Fn(&[u8]) -> Result<Box<dyn CustomSei>> + Send + Sync>;
+
+/// Payload decoded by a decoder from the `SeiRegistry`
+#[derive(Debug, Clone)]
+pub struct CustomSeiPayload {
+    pub payload_type: u32,
+    pub value: Arc<dyn CustomSei>,
+}
+
+/// Application provided SEI decoders.
+/// They take precedence over the decoders of the crate.
+#[derive(Default, Clone)]
+pub struct SeiRegistry {
+    payload_types: HashMap<u32, SeiDecoderFn>,
+    itu_t_t35: HashMap<(u8, u16), SeiDecoderFn>,
+}
+
+impl CustomSeiPayload {
+    pub fn downcast_ref<T: CustomSei>(&self) -> Option<&T> {
+        // Deref first, `Arc<dyn CustomSei>` is itself a `CustomSei`
+        (*self.value).as_any().downcast_ref()
+    }
+}
+
+impl PartialEq for CustomSeiPayload {
+    fn eq(&self, other: &Self) -> bool {
+        self.payload_type == other.payload_type && Arc::ptr_eq(&self.value, &other.value)
+    }
+}
+
+impl Eq for CustomSeiPayload {}
+
+impl SeiRegistry {
+    /// Registers a decoder for every payload of `payload_type`.
+    /// The decoder receives the payload bytes.
+    pub fn register_payload_type<F>(&mut self, payload_type: u32, decoder: F)
+    where
+        F: Fn(&[u8]) -> Result<Box<dyn CustomSei>> + Send + Sync + 'static,
+    {
+        self.payload_types.insert(payload_type, Arc::new(decoder));
+    }
+
+    /// Registers a decoder for the user_data_registered_itu_t_t35 payloads of a provider.
+    /// The decoder receives the bytes following the country code, starting with the provider code.
+    pub fn register_itu_t_t35<F>(&mut self, country_code: u8, provider_code: u16, decoder: F)
+    where
+        F: Fn(&[u8]) -> Result<Box<dyn CustomSei>> + Send + Sync + 'static,
+    {
+        self.itu_t_t35
+            .insert((country_code, provider_code), Arc::new(decoder));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.payload_types.is_empty() && self.itu_t_t35.is_empty()
+    }
+
+    /// `None` when no decoder is registered for the payload
+    pub(crate) fn decode(
+        &self,
+        payload_type: u32,
+        data: &[u8],
+        t35: Option<&ItuTT35>,
+    ) -> Option<Result<CustomSeiPayload>> {
+        let (decoder, data) = if let Some(decoder) = self.payload_types.get(&payload_type) {
+            (decoder, data)
+        } else {
+            let t35 = t35?;
+            let decoder = self
+                .itu_t_t35
+                .get(&(t35.country_code, t35.provider_code()?))?;
+
+            (decoder, t35.data.as_slice())
+        };
+
+        Some(decoder(data).map(|value| CustomSeiPayload {
+            payload_type,
+            value: Arc::from(value),
+        }))
+    }
+}
+
+impl fmt::Debug for SeiRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SeiRegistry")
+            .field("payload_types", &self.payload_types.keys())
+            .field("itu_t_t35", &self.itu_t_t35.keys())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decoder_lookup() -> Result<()> {
+        let mut registry = SeiRegistry::default();
+        assert!(registry.is_empty());
+
+        registry.register_itu_t_t35(0xB5, 0x003C, |data| Ok(Box::new(data.to_vec())));
+        assert!(!registry.is_empty());
+
+        let t35 = ItuTT35::parse(&[0xB5, 0x00, 0x3C, 0x01])?;
+        let custom = registry.decode(4, &[0xB5, 0x00, 0x3C, 0x01], Some(&t35));
+        assert_eq!(
+            custom.transpose()?.unwrap().downcast_ref::<Vec<u8>>(),
+            Some(&vec![0x00, 0x3C, 0x01])
+        );
+
+        // Other provider, or too short for a provider code
+        let t35 = ItuTT35::parse(&[0xB5, 0x00, 0x31])?;
+        assert!(
+            registry
+                .decode(4, &[0xB5, 0x00, 0x31], Some(&t35))
+                .is_none()
+        );
+        let t35 = ItuTT35::parse(&[0xB5, 0x00])?;
+        assert!(registry.decode(4, &[0xB5, 0x00], Some(&t35)).is_none());
+
+        assert!(registry.decode(5, &[0x00], None).is_none());
+
+        Ok(())
+    }
+}
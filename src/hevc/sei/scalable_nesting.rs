@@ -0,0 +1,162 @@
+use anyhow::Result;
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+use super::{SeiContext, SeiMessage, SeiPayload};
+
+/// scalable_nesting, D.2.13.
+/// The nested messages apply to the signalled operation points, or layers and sub-layers.
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct ScalableNesting {
+    pub bitstream_subset_flag: bool,
+    pub nesting_op_flag: bool,
+
+    /// With `nesting_op_flag`, the first operation point is the default one when set.
+    /// Its `nesting_max_temporal_id_plus1` is then 7, including every sub-layer.
+    pub default_op_flag: bool,
+    pub operation_points: Vec<NestingOperationPoint>,
+
+    /// Without `nesting_op_flag`
+    pub all_layers_flag: bool,
+    pub nesting_no_op_max_temporal_id_plus1: u8,
+    pub nesting_layer_id: Vec<u8>,
+
+    /// Decoded with the same decoders as the non nested messages
+    pub messages: Vec<SeiPayload>,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct NestingOperationPoint {
+    pub nesting_max_temporal_id_plus1: u8,
+    pub nesting_op_idx: u64,
+}
+
+impl ScalableNesting {
+    pub fn parse(data: &[u8], ctx: &SeiContext) -> Result<ScalableNesting> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        let mut nesting = ScalableNesting {
+            bitstream_subset_flag: reader.read_bit()?,
+            nesting_op_flag: reader.read_bit()?,
+            ..Default::default()
+        };
+
+        if nesting.nesting_op_flag {
+            nesting.default_op_flag = reader.read_bit()?;
+
+            let nesting_num_ops_minus1 = reader.read_ue()?;
+
+            if nesting.default_op_flag {
+                nesting.operation_points.push(NestingOperationPoint {
+                    nesting_max_temporal_id_plus1: 7,
+                    ..Default::default()
+                });
+            }
+
+            for _ in nesting.default_op_flag as u64..=nesting_num_ops_minus1 {
+                nesting.operation_points.push(NestingOperationPoint {
+                    nesting_max_temporal_id_plus1: reader.read::<3, u8>()?,
+                    nesting_op_idx: reader.read_ue()?,
+                });
+            }
+        } else {
+            nesting.all_layers_flag = reader.read_bit()?;
+
+            if !nesting.all_layers_flag {
+                nesting.nesting_no_op_max_temporal_id_plus1 = reader.read::<3, u8>()?;
+
+                let nesting_num_layers_minus1 = reader.read_ue()?;
+                for _ in 0..=nesting_num_layers_minus1 {
+                    nesting.nesting_layer_id.push(reader.read::<6, u8>()?);
+                }
+            }
+        }
+
+        // nesting_zero_bit
+        let position = reader.position_in_bits()?;
+        reader.skip_n(((8 - position % 8) % 8) as u32)?;
+
+        nesting.messages = SeiMessage::parse_messages(&mut reader)?
+            .iter()
+            .map(|msg| SeiPayload::parse(msg.payload_type, msg.payload_data(data), ctx))
+            .collect();
+
+        Ok(nesting)
+    }
+
+    /// Highest temporal ID the nested messages apply to, `None` for all of them
+    pub fn max_temporal_id(&self) -> Option<u8> {
+        if self.nesting_op_flag {
+            self.operation_points
+                .iter()
+                .map(|op| op.nesting_max_temporal_id_plus1)
+                .max()
+                .and_then(|tid| tid.checked_sub(1))
+        } else if self.all_layers_flag {
+            None
+        } else {
+            self.nesting_no_op_max_temporal_id_plus1.checked_sub(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hevc::sei::{AlternativeTransferCharacteristics, ContentLightLevelInfo};
+
+    #[test]
+    fn operation_points() -> Result<()> {
+        // Default operation point and one up to temporal ID 2, nesting a content light level
+        let data = [0x69, 0xB0, 0x90, 0x04, 0x03, 0xE8, 0x01, 0x90, 0x80];
+        let nesting = ScalableNesting::parse(&data, &SeiContext::default())?;
+
+        assert!(nesting.nesting_op_flag && nesting.default_op_flag);
+        assert_eq!(
+            nesting.operation_points,
+            [
+                NestingOperationPoint {
+                    nesting_max_temporal_id_plus1: 7,
+                    nesting_op_idx: 0,
+                },
+                NestingOperationPoint {
+                    nesting_max_temporal_id_plus1: 3,
+                    nesting_op_idx: 2,
+                },
+            ]
+        );
+        assert_eq!(nesting.max_temporal_id(), Some(6));
+        assert_eq!(
+            nesting.messages,
+            [SeiPayload::ContentLightLevelInfo(ContentLightLevelInfo {
+                max_content_light_level: 1000,
+                max_pic_average_light_level: 400,
+            })]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn layers() -> Result<()> {
+        // Layer 1, temporal ID 0, nesting an alternative transfer characteristics
+        let data = [0x06, 0x08, 0x93, 0x01, 0x12, 0x80];
+        let nesting = ScalableNesting::parse(&data, &SeiContext::default())?;
+
+        assert!(!nesting.nesting_op_flag && !nesting.all_layers_flag);
+        assert_eq!(nesting.nesting_layer_id, [1]);
+        assert_eq!(nesting.max_temporal_id(), Some(0));
+        assert_eq!(
+            nesting.messages,
+            [SeiPayload::AlternativeTransferCharacteristics(
+                AlternativeTransferCharacteristics {
+                    preferred_transfer_characteristics: 18,
+                }
+            )]
+        );
+
+        // Nested payload larger than the remaining data
+        assert!(ScalableNesting::parse(&data[..4], &SeiContext::default()).is_err());
+
+        Ok(())
+    }
+}
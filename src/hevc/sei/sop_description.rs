@@ -0,0 +1,288 @@
+use anyhow::Result;
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+use super::super::{Frame, NAL_IDR_N_LP, NAL_IDR_W_RADL};
+use super::SeiPayload;
+
+/// structure_of_pictures_info, D.2.20.
+/// Describes the pictures following in decoding order, starting with the current one.
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct SopDescription {
+    pub sop_seq_parameter_set_id: u64,
+    pub entries: Vec<SopEntry>,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct SopEntry {
+    pub sop_vcl_nut: u8,
+    pub sop_temporal_id: u8,
+    /// Not present for IDR pictures
+    pub sop_short_term_rps_idx: Option<u64>,
+    /// POC relative to the first picture of the SOP
+    pub sop_poc_delta: i64,
+}
+
+/// Difference between a SOP description and the pictures
+#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+pub struct SopMismatch {
+    /// Decoded number of the frame with the SOP description
+    pub sop_start: u64,
+    pub entry: usize,
+    pub kind: SopMismatchKind,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+pub enum SopMismatchKind {
+    /// The stream ends before the SOP
+    MissingPicture,
+    NalType {
+        expected: u8,
+        found: u8,
+    },
+    TemporalId {
+        expected: u8,
+        found: u8,
+    },
+    PocDelta {
+        expected: i64,
+        found: i64,
+    },
+    ShortTermRpsIdx {
+        expected: u64,
+        found: u64,
+    },
+}
+
+impl SopDescription {
+    pub fn parse(data: &[u8]) -> Result<SopDescription> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+
+        let mut sop = SopDescription {
+            sop_seq_parameter_set_id: reader.read_ue()?,
+            ..Default::default()
+        };
+
+        let num_entries_in_sop_minus1 = reader.read_ue()?;
+
+        for i in 0..=num_entries_in_sop_minus1 {
+            let mut entry = SopEntry {
+                sop_vcl_nut: reader.read::<6, u8>()?,
+                sop_temporal_id: reader.read::<3, u8>()?,
+                ..Default::default()
+            };
+
+            if entry.sop_vcl_nut != NAL_IDR_W_RADL && entry.sop_vcl_nut != NAL_IDR_N_LP {
+                entry.sop_short_term_rps_idx = Some(reader.read_ue()?);
+            }
+
+            if i > 0 {
+                entry.sop_poc_delta = reader.read_se()?;
+            }
+
+            sop.entries.push(entry);
+        }
+
+        Ok(sop)
+    }
+}
+
+/// Compares the SOP descriptions to the pictures following them in decoding order.
+/// Pictures with an RPS coded in the slice header must use `num_short_term_ref_pic_sets` as index.
+pub fn check_sop_descriptions(frames: &[Frame]) -> Vec<SopMismatch> {
+    let mut decode_order: Vec<&Frame> = frames.iter().collect();
+    decode_order.sort_by_key(|f| f.decoded_number);
+
+    let mut mismatches = Vec::new();
+
+    for (start, frame) in decode_order.iter().enumerate() {
+        let Some(sop) = frame.prefix_sei.iter().find_map(|sei| match sei {
+            SeiPayload::SopDescription(sop) => Some(sop),
+            _ => None,
+        }) else {
+            continue;
+        };
+
+        let first_poc = frame.first_slice.pic_order_cnt_val as i64;
+
+        for (entry_idx, entry) in sop.entries.iter().enumerate() {
+            let mut mismatch = |kind| {
+                mismatches.push(SopMismatch {
+                    sop_start: frame.decoded_number,
+                    entry: entry_idx,
+                    kind,
+                })
+            };
+
+            let Some(picture) = decode_order.get(start + entry_idx) else {
+                mismatch(SopMismatchKind::MissingPicture);
+                continue;
+            };
+
+            if let Some(nal) = picture.nals.iter().find(|nal| nal.is_slice()) {
+                if nal.nal_type != entry.sop_vcl_nut {
+                    mismatch(SopMismatchKind::NalType {
+                        expected: entry.sop_vcl_nut,
+                        found: nal.nal_type,
+                    });
+                }
+
+                if nal.temporal_id != entry.sop_temporal_id {
+                    mismatch(SopMismatchKind::TemporalId {
+                        expected: entry.sop_temporal_id,
+                        found: nal.temporal_id,
+                    });
+                }
+            }
+
+            let poc_delta = picture.first_slice.pic_order_cnt_val as i64 - first_poc;
+            if poc_delta != entry.sop_poc_delta {
+                mismatch(SopMismatchKind::PocDelta {
+                    expected: entry.sop_poc_delta,
+                    found: poc_delta,
+                });
+            }
+
+            let slice = &picture.first_slice;
+            if let Some(expected) = entry.sop_short_term_rps_idx
+                && slice.curr_rps_idx != expected
+            {
+                mismatch(SopMismatchKind::ShortTermRpsIdx {
+                    expected,
+                    found: slice.curr_rps_idx,
+                });
+            }
+        }
+    }
+
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hevc::{NAL_TRAIL_N, NAL_TRAIL_R, NALUnit, SliceNAL};
+
+    /// IDR, P with POC delta 2 and RPS 0, then B at temporal ID 1 with POC delta 1 and RPS 1
+    const SOP: &[u8] = &[0xB4, 0xC0, 0x22, 0x40, 0x0A, 0x40];
+
+    fn frame(decoded_number: u64, nal_type: u8, temporal_id: u8, poc: i32, rps_idx: u64) -> Frame {
+        let mut first_slice = SliceNAL::default();
+        first_slice.pic_order_cnt_val = poc;
+        first_slice.curr_rps_idx = rps_idx;
+
+        Frame {
+            decoded_number,
+            nals: vec![NALUnit {
+                nal_type,
+                temporal_id,
+                ..Default::default()
+            }],
+            first_slice,
+            ..Default::default()
+        }
+    }
+
+    fn sop_frames() -> Vec<Frame> {
+        let mut frames = vec![
+            frame(0, NAL_IDR_W_RADL, 0, 0, 0),
+            frame(1, NAL_TRAIL_R, 0, 2, 0),
+            frame(2, NAL_TRAIL_N, 1, 1, 1),
+        ];
+        frames[0].prefix_sei.push(SeiPayload::SopDescription(
+            SopDescription::parse(SOP).unwrap(),
+        ));
+
+        frames
+    }
+
+    #[test]
+    fn parse() -> Result<()> {
+        let sop = SopDescription::parse(SOP)?;
+
+        assert_eq!(
+            sop.entries,
+            [
+                SopEntry {
+                    sop_vcl_nut: NAL_IDR_W_RADL,
+                    sop_temporal_id: 0,
+                    sop_short_term_rps_idx: None,
+                    sop_poc_delta: 0,
+                },
+                SopEntry {
+                    sop_vcl_nut: NAL_TRAIL_R,
+                    sop_temporal_id: 0,
+                    sop_short_term_rps_idx: Some(0),
+                    sop_poc_delta: 2,
+                },
+                SopEntry {
+                    sop_vcl_nut: NAL_TRAIL_N,
+                    sop_temporal_id: 1,
+                    sop_short_term_rps_idx: Some(1),
+                    sop_poc_delta: 1,
+                },
+            ]
+        );
+
+        assert!(SopDescription::parse(&SOP[..3]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn check() {
+        let mut frames = sop_frames();
+        assert!(check_sop_descriptions(&frames).is_empty());
+
+        // Presentation order doesn't matter
+        frames.swap(1, 2);
+        assert!(check_sop_descriptions(&frames).is_empty());
+
+        let mut frames = sop_frames();
+        frames[2] = frame(2, NAL_TRAIL_R, 0, 3, 2);
+
+        let mismatch = |entry, kind| SopMismatch {
+            sop_start: 0,
+            entry,
+            kind,
+        };
+        assert_eq!(
+            check_sop_descriptions(&frames),
+            [
+                mismatch(
+                    2,
+                    SopMismatchKind::NalType {
+                        expected: NAL_TRAIL_N,
+                        found: NAL_TRAIL_R,
+                    }
+                ),
+                mismatch(
+                    2,
+                    SopMismatchKind::TemporalId {
+                        expected: 1,
+                        found: 0,
+                    }
+                ),
+                mismatch(
+                    2,
+                    SopMismatchKind::PocDelta {
+                        expected: 1,
+                        found: 3,
+                    }
+                ),
+                mismatch(
+                    2,
+                    SopMismatchKind::ShortTermRpsIdx {
+                        expected: 1,
+                        found: 2,
+                    }
+                ),
+            ]
+        );
+
+        frames.truncate(2);
+        assert_eq!(
+            check_sop_descriptions(&frames),
+            [mismatch(2, SopMismatchKind::MissingPicture)]
+        );
+    }
+}
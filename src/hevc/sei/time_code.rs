@@ -0,0 +1,249 @@
+use std::io::Write;
+
+use anyhow::Result;
+use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
+
+use super::super::Frame;
+use super::SeiPayload;
+
+/// time_code, D.2.27
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct TimeCode {
+    /// One per `num_clock_ts`, `None` when `clock_timestamp_flag` is not set
+    pub clock_timestamps: Vec<Option<ClockTimestamp>>,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct ClockTimestamp {
+    pub units_field_based_flag: bool,
+    pub counting_type: u8,
+    pub full_timestamp_flag: bool,
+    pub discontinuity_flag: bool,
+    pub cnt_dropped_flag: bool,
+    pub n_frames: u16,
+
+    /// `None` when not present, the value is then the one of the previous timestamp
+    pub seconds_value: Option<u8>,
+    pub minutes_value: Option<u8>,
+    pub hours_value: Option<u8>,
+
+    pub time_offset_length: u8,
+    pub time_offset_value: i32,
+}
+
+impl TimeCode {
+    pub fn parse(data: &[u8]) -> Result<TimeCode> {
+        let mut reader = BsIoSliceReader::from_slice(data);
+        let mut time_code = TimeCode::default();
+
+        let num_clock_ts = reader.read::<2, u8>()?;
+
+        for _ in 0..num_clock_ts {
+            let clock_timestamp_flag = reader.read_bit()?;
+
+            let clock_ts = if clock_timestamp_flag {
+                Some(ClockTimestamp::parse(&mut reader)?)
+            } else {
+                None
+            };
+
+            time_code.clock_timestamps.push(clock_ts);
+        }
+
+        Ok(time_code)
+    }
+
+    /// First clock timestamp present
+    pub fn clock_timestamp(&self) -> Option<&ClockTimestamp> {
+        self.clock_timestamps.iter().flatten().next()
+    }
+}
+
+impl ClockTimestamp {
+    fn parse(reader: &mut BsIoSliceReader) -> Result<ClockTimestamp> {
+        let mut ts = ClockTimestamp {
+            units_field_based_flag: reader.read_bit()?,
+            counting_type: reader.read::<5, u8>()?,
+            full_timestamp_flag: reader.read_bit()?,
+            discontinuity_flag: reader.read_bit()?,
+            cnt_dropped_flag: reader.read_bit()?,
+            n_frames: reader.read::<9, u16>()?,
+            ..Default::default()
+        };
+
+        if ts.full_timestamp_flag {
+            ts.seconds_value = Some(reader.read::<6, u8>()?);
+            ts.minutes_value = Some(reader.read::<6, u8>()?);
+            ts.hours_value = Some(reader.read::<5, u8>()?);
+        } else if reader.read_bit()? {
+            ts.seconds_value = Some(reader.read::<6, u8>()?);
+
+            if reader.read_bit()? {
+                ts.minutes_value = Some(reader.read::<6, u8>()?);
+
+                if reader.read_bit()? {
+                    ts.hours_value = Some(reader.read::<5, u8>()?);
+                }
+            }
+        }
+
+        ts.time_offset_length = reader.read::<5, u8>()?;
+
+        if ts.time_offset_length > 0 {
+            let len = ts.time_offset_length as u32;
+            let value: u32 = reader.read_var(len)?;
+
+            // i(v), two's complement
+            ts.time_offset_value = ((value << (32 - len)) as i32) >> (32 - len);
+        }
+
+        Ok(ts)
+    }
+
+    /// Drop frame counting, as with NTSC 30000/1001
+    pub fn is_drop_frame(&self) -> bool {
+        self.counting_type == 4
+    }
+
+    /// Fills the values that are not present from the previous timestamp
+    pub fn inherit(&mut self, previous: &ClockTimestamp) {
+        self.seconds_value = self.seconds_value.or(previous.seconds_value);
+        self.minutes_value = self.minutes_value.or(previous.minutes_value);
+        self.hours_value = self.hours_value.or(previous.hours_value);
+    }
+
+    /// `HH:MM:SS:FF`, or `HH:MM:SS;FF` when drop frame.
+    /// Missing values are written as zero.
+    pub fn to_smpte_string(&self) -> String {
+        format!(
+            "{:02}:{:02}:{:02}{}{:02}",
+            self.hours_value.unwrap_or(0),
+            self.minutes_value.unwrap_or(0),
+            self.seconds_value.unwrap_or(0),
+            if self.is_drop_frame() { ';' } else { ':' },
+            self.n_frames
+        )
+    }
+}
+
+/// Clock timestamp of each frame, in the order of the frames.
+/// The values that are not present are inherited from the previous timestamp.
+pub fn frames_timecodes(frames: &[Frame]) -> Vec<Option<ClockTimestamp>> {
+    let mut previous: Option<ClockTimestamp> = None;
+
+    frames
+        .iter()
+        .map(|f| {
+            let mut ts = f.prefix_sei.iter().find_map(|sei| match sei {
+                SeiPayload::TimeCode(time_code) => time_code.clock_timestamp().copied(),
+                _ => None,
+            })?;
+
+            if let Some(prev) = &previous {
+                ts.inherit(prev);
+            }
+
+            previous = Some(ts);
+
+            Some(ts)
+        })
+        .collect()
+}
+
+/// Timecode of the first frame with a time_code SEI.
+/// The frames should be in presentation order, usually `HevcParser::ordered_frames`.
+pub fn start_timecode(frames: &[Frame]) -> Option<String> {
+    frames_timecodes(frames)
+        .into_iter()
+        .flatten()
+        .next()
+        .map(|ts| ts.to_smpte_string())
+}
+
+/// Writes a line per frame with a time code, as `presentation_number timecode`.
+/// The frames should be in presentation order, usually `HevcParser::ordered_frames`.
+pub fn write_timecode_track(frames: &[Frame], writer: &mut dyn Write) -> Result<()> {
+    for (frame, ts) in frames.iter().zip(frames_timecodes(frames)) {
+        if let Some(ts) = ts {
+            writeln!(
+                writer,
+                "{} {}",
+                frame.presentation_number,
+                ts.to_smpte_string()
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Drop frame 01:10:03;02, with a time offset of -3
+    const FULL: &[u8] = &[0x62, 0x50, 0x10, 0x65, 0x04, 0x9A];
+    /// Second timestamp only, with the seconds and frames
+    const SECONDS_ONLY: &[u8] = &[0x91, 0x00, 0x16, 0x20, 0x00];
+
+    fn frame(data: &[u8]) -> Frame {
+        Frame {
+            prefix_sei: vec![SeiPayload::TimeCode(TimeCode::parse(data).unwrap())],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse() -> Result<()> {
+        let ts = *TimeCode::parse(FULL)?.clock_timestamp().unwrap();
+        assert!(ts.is_drop_frame() && ts.full_timestamp_flag && ts.cnt_dropped_flag);
+        assert_eq!(
+            (ts.hours_value, ts.minutes_value, ts.seconds_value),
+            (Some(1), Some(10), Some(3))
+        );
+        assert_eq!(ts.time_offset_length, 4);
+        assert_eq!(ts.time_offset_value, -3);
+        assert_eq!(ts.to_smpte_string(), "01:10:03;02");
+
+        let time_code = TimeCode::parse(SECONDS_ONLY)?;
+        assert_eq!(time_code.clock_timestamps.len(), 2);
+        assert!(time_code.clock_timestamps[0].is_none());
+
+        let ts = time_code.clock_timestamp().unwrap();
+        assert_eq!(ts.n_frames, 5);
+        assert_eq!(
+            (ts.hours_value, ts.minutes_value, ts.seconds_value),
+            (None, None, Some(4))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn inherited_values() -> Result<()> {
+        let mut frames = [frame(FULL), Frame::default(), frame(SECONDS_ONLY)];
+        for (i, f) in frames.iter_mut().enumerate() {
+            f.presentation_number = i as u64;
+        }
+
+        let timecodes: Vec<_> = frames_timecodes(&frames)
+            .iter()
+            .map(|ts| ts.map(|ts| ts.to_smpte_string()))
+            .collect();
+        assert_eq!(
+            timecodes,
+            [
+                Some("01:10:03;02".to_owned()),
+                None,
+                Some("01:10:04;05".to_owned())
+            ]
+        );
+        assert_eq!(start_timecode(&frames[1..]), Some("00:00:04;05".to_owned()));
+
+        let mut out = Vec::new();
+        write_timecode_track(&frames, &mut out)?;
+        assert_eq!(out, b"0 01:10:03;02\n2 01:10:04;05\n");
+
+        Ok(())
+    }
+}
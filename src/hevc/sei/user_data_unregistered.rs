@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+
+use super::super::Frame;
+use super::SeiPayload;
+
+pub const X265_UUID: [u8; 16] = [
+    0x2C, 0xA2, 0xDE, 0x09, 0xB5, 0x17, 0x47, 0xDB, 0xBB, 0x55, 0xA4, 0xFE, 0x7F, 0xC2, 0xFC, 0x4E,
+];
+pub const X264_UUID: [u8; 16] = [
+    0xDC, 0x45, 0xE9, 0xBD, 0xE6, 0xD9, 0x48, 0xB7, 0x96, 0x2C, 0xD8, 0x20, 0xD9, 0x23, 0xEE, 0xEF,
+];
+
+/// user_data_unregistered, D.2.7
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct UserDataUnregistered {
+    pub uuid_iso_iec_11578: [u8; 16],
+    pub data: Vec<u8>,
+}
+
+/// Version and options written by an encoder, such as x265's info SEI
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub struct EncoderSettings {
+    /// e.g. `x265`
+    pub encoder: String,
+    /// Text preceding the options, e.g. `x265 (build 199) - 3.5+1:[Linux][GCC 9.3.0][64 bit] 10bit - H.265/HEVC codec - ...`
+    pub version: String,
+
+    /// Options given as `key=value`.
+    /// Flags are `1`, or `0` when given as `no-key`.
+    pub options: BTreeMap<String, String>,
+}
+
+impl UserDataUnregistered {
+    pub fn parse(data: &[u8]) -> Result<UserDataUnregistered> {
+        if data.len() < 16 {
+            bail!("user_data_unregistered payload is shorter than the UUID");
+        }
+
+        let (uuid, data) = data.split_at(16);
+
+        Ok(UserDataUnregistered {
+            uuid_iso_iec_11578: uuid.try_into()?,
+            data: data.to_vec(),
+        })
+    }
+
+    /// UUID in the usual hyphenated form
+    pub fn uuid_string(&self) -> String {
+        let hex: String = self
+            .uuid_iso_iec_11578
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+
+        format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        )
+    }
+
+    /// Payload as text, without the trailing NUL bytes
+    pub fn text(&self) -> Option<&str> {
+        let end = self
+            .data
+            .iter()
+            .rposition(|&b| b != 0)
+            .map_or(0, |pos| pos + 1);
+
+        std::str::from_utf8(&self.data[..end]).ok()
+    }
+
+    /// Settings of the encoders with a known UUID
+    pub fn encoder_settings(&self) -> Option<EncoderSettings> {
+        let encoder = match self.uuid_iso_iec_11578 {
+            X265_UUID => "x265",
+            X264_UUID => "x264",
+            _ => return None,
+        };
+
+        let text = self.text()?;
+        let (version, options) = text.split_once(" - options:").unwrap_or((text, ""));
+
+        let options = options
+            .split_whitespace()
+            .map(|option| match option.split_once('=') {
+                Some((key, value)) => (key.to_owned(), value.to_owned()),
+                None => match option.strip_prefix("no-") {
+                    Some(key) => (key.to_owned(), "0".to_owned()),
+                    None => (option.to_owned(), "1".to_owned()),
+                },
+            })
+            .collect();
+
+        Some(EncoderSettings {
+            encoder: encoder.to_owned(),
+            version: version.to_owned(),
+            options,
+        })
+    }
+}
+
+/// Settings of the first known encoder SEI found in the frames
+pub fn encoder_settings(frames: &[Frame]) -> Option<EncoderSettings> {
+    frames
+        .iter()
+        .flat_map(|f| f.prefix_sei.iter().chain(&f.suffix_sei))
+        .find_map(|sei| match sei {
+            SeiPayload::UserDataUnregistered(user_data) => user_data.encoder_settings(),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn x265_settings() -> Result<()> {
+        let text = b"x265 (build 199) - 3.5+1:[Linux][GCC 9.3.0][64 bit] 10bit - H.265/HEVC codec - Copyright 2013-2018 (c) Multicoreware, Inc - http://x265.org - options: crf=18.0 no-open-gop bframes=4 hdr10\0";
+        let data = [X265_UUID.as_slice(), text].concat();
+
+        let user_data = UserDataUnregistered::parse(&data)?;
+        assert_eq!(
+            user_data.uuid_string(),
+            "2ca2de09-b517-47db-bb55-a4fe7fc2fc4e"
+        );
+        assert!(user_data.text().unwrap().ends_with("hdr10"));
+
+        let settings = user_data.encoder_settings().unwrap();
+        assert_eq!(settings.encoder, "x265");
+        assert!(settings.version.starts_with("x265 (build 199) - 3.5+1"));
+        assert!(settings.version.ends_with("http://x265.org"));
+        assert_eq!(
+            settings.options,
+            BTreeMap::from(
+                [
+                    ("bframes", "4"),
+                    ("crf", "18.0"),
+                    ("hdr10", "1"),
+                    ("open-gop", "0"),
+                ]
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            )
+        );
+
+        let frames = [Frame {
+            suffix_sei: vec![SeiPayload::UserDataUnregistered(user_data)],
+            ..Default::default()
+        }];
+        assert_eq!(encoder_settings(&frames), Some(settings));
+
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_uuid() -> Result<()> {
+        let user_data = UserDataUnregistered::parse(&[0xAB; 20])?;
+        assert_eq!(user_data.data, [0xAB; 4]);
+        assert!(user_data.text().is_none());
+        assert!(user_data.encoder_settings().is_none());
+
+        assert!(UserDataUnregistered::parse(&[0; 15]).is_err());
+
+        Ok(())
+    }
+}
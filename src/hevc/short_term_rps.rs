@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{Result, format_err};
 
 use super::BsIoVecReader;
 use super::sps::SPSNAL;
@@ -11,14 +11,15 @@ pub struct ShortTermRPS {
     abs_delta_rps: u64,
     used_by_curr_pic_flags: Vec<bool>,
     use_delta_flags: Vec<bool>,
-    num_delta_pocs: u64,
-    num_negative_pics: u64,
-    num_positive_pics: u64,
-
-    delta_poc_s0: Vec<u64>,
-    used_by_curr_pic_s0_flags: Vec<bool>,
-    delta_poc_s1: Vec<u64>,
-    used_by_curr_pic_s1_flags: Vec<bool>,
+    pub(crate) num_delta_pocs: u64,
+    pub(crate) num_negative_pics: u64,
+    pub(crate) num_positive_pics: u64,
+
+    // Derived values, DeltaPocS0 and DeltaPocS1
+    pub(crate) delta_poc_s0: Vec<i64>,
+    pub(crate) used_by_curr_pic_s0_flags: Vec<bool>,
+    pub(crate) delta_poc_s1: Vec<i64>,
+    pub(crate) used_by_curr_pic_s1_flags: Vec<bool>,
 }
 
 impl ShortTermRPS {
@@ -45,19 +46,13 @@ impl ShortTermRPS {
             rps.delta_rps_sign = bs.read_bit()?;
             rps.abs_delta_rps = bs.read_ue()? + 1;
 
-            let ref_rps_idx = st_rps_idx - (rps.delta_idx as usize + 1);
-            let mut num_delta_pocs: usize = 0;
-            let ref_rps = &ref_pic_sets[ref_rps_idx];
-
-            if ref_rps.inter_ref_pic_set_prediction_flag {
-                for i in 0..ref_rps.used_by_curr_pic_flags.len() {
-                    if ref_rps.used_by_curr_pic_flags[i] || ref_rps.use_delta_flags[i] {
-                        num_delta_pocs += 1;
-                    }
-                }
-            } else {
-                num_delta_pocs = (ref_rps.num_negative_pics + ref_rps.num_positive_pics) as usize;
-            }
+            let ref_rps_idx = st_rps_idx
+                .checked_sub(rps.delta_idx as usize + 1)
+                .ok_or_else(|| format_err!("Invalid short term RPS delta_idx"))?;
+            let ref_rps = ref_pic_sets
+                .get(ref_rps_idx)
+                .ok_or_else(|| format_err!("Invalid short term RPS reference index"))?;
+            let num_delta_pocs = ref_rps.num_delta_pocs as usize;
 
             rps.used_by_curr_pic_flags.resize(num_delta_pocs + 1, false);
             rps.use_delta_flags.resize(num_delta_pocs + 1, true);
@@ -69,21 +64,197 @@ impl ShortTermRPS {
                     rps.use_delta_flags[i] = bs.read_bit()?;
                 }
             }
+
+            rps.derive_from_ref_rps(ref_rps);
         } else {
             rps.num_negative_pics = bs.read_ue()?;
             rps.num_positive_pics = bs.read_ue()?;
 
+            let mut delta_poc = 0;
             for _ in 0..rps.num_negative_pics {
-                rps.delta_poc_s0.push(bs.read_ue()? + 1);
+                delta_poc -= bs.read_ue()? as i64 + 1;
+
+                rps.delta_poc_s0.push(delta_poc);
                 rps.used_by_curr_pic_s0_flags.push(bs.read_bit()?);
             }
 
+            delta_poc = 0;
             for _ in 0..rps.num_positive_pics {
-                rps.delta_poc_s1.push(bs.read_ue()? + 1);
+                delta_poc += bs.read_ue()? as i64 + 1;
+
+                rps.delta_poc_s1.push(delta_poc);
                 rps.used_by_curr_pic_s1_flags.push(bs.read_bit()?);
             }
         }
 
+        rps.num_delta_pocs = rps.num_negative_pics + rps.num_positive_pics;
+
         Ok(rps)
     }
+
+    /// Derivation of the delta POCs for inter RPS prediction, 7.4.8
+    fn derive_from_ref_rps(&mut self, ref_rps: &ShortTermRPS) {
+        let delta_rps = if self.delta_rps_sign {
+            -(self.abs_delta_rps as i64)
+        } else {
+            self.abs_delta_rps as i64
+        };
+
+        let ref_num_negative = ref_rps.num_negative_pics as usize;
+        let ref_num_delta_pocs = ref_rps.num_delta_pocs as usize;
+
+        for (j, ref_delta_poc) in ref_rps.delta_poc_s1.iter().enumerate().rev() {
+            let delta_poc = ref_delta_poc + delta_rps;
+
+            if delta_poc < 0 && self.use_delta_flags[ref_num_negative + j] {
+                self.delta_poc_s0.push(delta_poc);
+                self.used_by_curr_pic_s0_flags
+                    .push(self.used_by_curr_pic_flags[ref_num_negative + j]);
+            }
+        }
+
+        if delta_rps < 0 && self.use_delta_flags[ref_num_delta_pocs] {
+            self.delta_poc_s0.push(delta_rps);
+            self.used_by_curr_pic_s0_flags
+                .push(self.used_by_curr_pic_flags[ref_num_delta_pocs]);
+        }
+
+        for (j, ref_delta_poc) in ref_rps.delta_poc_s0.iter().enumerate() {
+            let delta_poc = ref_delta_poc + delta_rps;
+
+            if delta_poc < 0 && self.use_delta_flags[j] {
+                self.delta_poc_s0.push(delta_poc);
+                self.used_by_curr_pic_s0_flags
+                    .push(self.used_by_curr_pic_flags[j]);
+            }
+        }
+
+        for (j, ref_delta_poc) in ref_rps.delta_poc_s0.iter().enumerate().rev() {
+            let delta_poc = ref_delta_poc + delta_rps;
+
+            if delta_poc > 0 && self.use_delta_flags[j] {
+                self.delta_poc_s1.push(delta_poc);
+                self.used_by_curr_pic_s1_flags
+                    .push(self.used_by_curr_pic_flags[j]);
+            }
+        }
+
+        if delta_rps > 0 && self.use_delta_flags[ref_num_delta_pocs] {
+            self.delta_poc_s1.push(delta_rps);
+            self.used_by_curr_pic_s1_flags
+                .push(self.used_by_curr_pic_flags[ref_num_delta_pocs]);
+        }
+
+        for (j, ref_delta_poc) in ref_rps.delta_poc_s1.iter().enumerate() {
+            let delta_poc = ref_delta_poc + delta_rps;
+
+            if delta_poc > 0 && self.use_delta_flags[ref_num_negative + j] {
+                self.delta_poc_s1.push(delta_poc);
+                self.used_by_curr_pic_s1_flags
+                    .push(self.used_by_curr_pic_flags[ref_num_negative + j]);
+            }
+        }
+
+        self.num_negative_pics = self.delta_poc_s0.len() as u64;
+        self.num_positive_pics = self.delta_poc_s1.len() as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitvec_helpers::bitstream_io_writer::BitstreamIoWriter;
+
+    use super::*;
+
+    fn reader(writer: BitstreamIoWriter) -> BsIoVecReader {
+        BsIoVecReader::from_vec(writer.into_inner())
+    }
+
+    /// DeltaPocS0 [-1, -3] and DeltaPocS1 [2], all used by the current picture
+    fn explicit_rps() -> Result<ShortTermRPS> {
+        let mut w = BitstreamIoWriter::with_capacity(8);
+        w.write_ue(2)?; // num_negative_pics
+        w.write_ue(1)?; // num_positive_pics
+        w.write_ue(0)?;
+        w.write_bit(true)?;
+        w.write_ue(1)?;
+        w.write_bit(true)?;
+        w.write_ue(1)?;
+        w.write_bit(true)?;
+        w.byte_align()?;
+
+        ShortTermRPS::parse(&mut reader(w), &SPSNAL::default(), 0, 2, false)
+    }
+
+    /// Predicted with deltaRps -1, the reference DeltaPocS0 -3 is dropped
+    fn write_predicted_rps(w: &mut BitstreamIoWriter, delta_idx: Option<u64>) -> Result<()> {
+        w.write_bit(true)?; // inter_ref_pic_set_prediction_flag
+        if let Some(delta_idx) = delta_idx {
+            w.write_ue(delta_idx)?;
+        }
+        w.write_bit(true)?; // delta_rps_sign
+        w.write_ue(0)?; // abs_delta_rps_minus1
+
+        // j = 0..=NumDeltaPocs, the last one being the reference picture itself
+        w.write_bit(true)?;
+        w.write_bit(false)?;
+        w.write_bit(false)?; // use_delta_flag
+        w.write_bit(true)?;
+        w.write_bit(true)?;
+        w.byte_align()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn explicit() -> Result<()> {
+        let rps = explicit_rps()?;
+
+        assert_eq!(rps.delta_poc_s0, [-1, -3]);
+        assert_eq!(rps.delta_poc_s1, [2]);
+        assert_eq!(rps.num_delta_pocs, 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn inter_rps_prediction() -> Result<()> {
+        let mut sps = SPSNAL::default();
+        sps.short_term_ref_pic_sets = vec![explicit_rps()?];
+
+        let mut w = BitstreamIoWriter::with_capacity(8);
+        write_predicted_rps(&mut w, None)?;
+
+        let rps = ShortTermRPS::parse(&mut reader(w), &sps, 1, 2, false)?;
+
+        assert_eq!(rps.delta_poc_s0, [-1, -2]);
+        assert_eq!(rps.used_by_curr_pic_s0_flags, [true, true]);
+        assert_eq!(rps.delta_poc_s1, [1]);
+        assert_eq!(rps.used_by_curr_pic_s1_flags, [true]);
+        assert_eq!((rps.num_negative_pics, rps.num_positive_pics), (2, 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn inter_rps_prediction_in_slice_header() -> Result<()> {
+        let mut sps = SPSNAL::default();
+        sps.short_term_ref_pic_sets = vec![explicit_rps()?, ShortTermRPS::default()];
+
+        // delta_idx_minus1 = 1 refers to the first RPS
+        let mut w = BitstreamIoWriter::with_capacity(8);
+        write_predicted_rps(&mut w, Some(1))?;
+
+        let rps = ShortTermRPS::parse(&mut reader(w), &sps, 2, 2, true)?;
+        assert_eq!(rps.delta_poc_s0, [-1, -2]);
+        assert_eq!(rps.delta_poc_s1, [1]);
+
+        // Out of range reference
+        let mut w = BitstreamIoWriter::with_capacity(8);
+        write_predicted_rps(&mut w, Some(2))?;
+
+        assert!(ShortTermRPS::parse(&mut reader(w), &sps, 2, 2, true).is_err());
+
+        Ok(())
+    }
 }
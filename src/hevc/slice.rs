@@ -1,21 +1,111 @@
-use anyhow::{Result, format_err};
+use anyhow::{Result, ensure, format_err};
 
 use super::BsIoVecReader;
+use super::dpb::{LongTermRef, RefPicSet};
+use super::pred_weight_table::PredWeightTable;
+use super::short_term_rps::ShortTermRPS;
 use super::*;
 use super::{NALUnit, pps::PPSNAL, sps::SPSNAL};
 
+pub const SLICE_TYPE_B: u64 = 0;
+pub const SLICE_TYPE_P: u64 = 1;
+pub const SLICE_TYPE_I: u64 = 2;
+
 #[derive(Default, Debug, PartialEq, Clone, Eq)]
 pub struct SliceNAL {
     pub first_slice_in_pic_flag: bool,
     pub key_frame: bool,
-    pps_id: u64,
+    pub(crate) no_output_of_prior_pics_flag: bool,
+    pub(crate) pps_id: u64,
+    pub(crate) sps_id: u64,
     pub slice_type: u64,
 
-    dependent_slice_segment_flag: bool,
-    slice_segment_addr: u64,
+    pub(crate) dependent_slice_segment_flag: bool,
+    pub(crate) slice_segment_addr: u64,
+
+    pub(crate) pic_output_flag: bool,
+    pub(crate) colour_plane_id: u8,
 
     pic_order_cnt_lsb: u64,
     pub output_picture_number: u64,
+
+    pub(crate) short_term_ref_pic_set_sps_flag: bool,
+    pub(crate) short_term_ref_pic_set_idx: u64,
+    pub(crate) short_term_ref_pic_set: ShortTermRPS,
+    pub(crate) st_rps_bits: u64,
+    /// CurrRpsIdx, `num_short_term_ref_pic_sets` for an RPS coded in the slice header
+    pub(crate) curr_rps_idx: u64,
+
+    pub(crate) num_long_term_sps: u64,
+    pub(crate) num_long_term_pics: u64,
+    lt_idx_sps: Vec<u64>,
+    poc_lsb_lt: Vec<u64>,
+    used_by_curr_pic_lt_flag: Vec<bool>,
+    delta_poc_msb_present_flag: Vec<bool>,
+    delta_poc_msb_cycle_lt: Vec<u64>,
+
+    pub(crate) slice_temporal_mvp_enabled_flag: bool,
+    pub(crate) slice_sao_luma_flag: bool,
+    pub(crate) slice_sao_chroma_flag: bool,
+
+    pub(crate) num_ref_idx_active_override_flag: bool,
+    pub(crate) num_ref_idx_l0_active: u64,
+    pub(crate) num_ref_idx_l1_active: u64,
+
+    pub(crate) ref_pic_list_modification_flag_l0: bool,
+    pub(crate) list_entry_l0: Vec<u64>,
+    pub(crate) ref_pic_list_modification_flag_l1: bool,
+    pub(crate) list_entry_l1: Vec<u64>,
+
+    pub(crate) mvd_l1_zero_flag: bool,
+    pub(crate) cabac_init_flag: bool,
+    pub(crate) collocated_from_l0_flag: bool,
+    pub(crate) collocated_ref_idx: u64,
+
+    pub(crate) pred_weight_table: Option<Box<PredWeightTable>>,
+
+    pub(crate) max_num_merge_cand: u64,
+
+    pub(crate) slice_qp_delta: i64,
+    pub(crate) slice_qp_y: i64,
+    pub(crate) slice_cb_qp_offset: i64,
+    pub(crate) slice_cr_qp_offset: i64,
+    pub(crate) cu_chroma_qp_offset_enabled_flag: bool,
+
+    pub(crate) deblocking_filter_override_flag: bool,
+    pub(crate) slice_deblocking_filter_disabled_flag: bool,
+    pub(crate) slice_beta_offset: i64,
+    pub(crate) slice_tc_offset: i64,
+    pub(crate) slice_loop_filter_across_slices_enabled_flag: bool,
+
+    pub(crate) num_entry_point_offsets: u64,
+    pub(crate) offset_len: u64,
+
+    pub(crate) slice_segment_header_extension_length: u64,
+
+    // Computed values
+    pub(crate) pic_order_cnt_val: i32,
+    pub(crate) rps: RefPicSet,
+    /// Size of the slice segment header in bytes, including the NAL header.
+    /// Does not account for `emulation_prevention_three_byte`s
+    pub(crate) header_size: u64,
+    /// Error in the header syntax following the POC, the values after it are not parsed
+    pub(crate) header_error: Option<String>,
+}
+
+/// Encoder decisions of a slice segment, kept for every slice of a frame
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SliceInfo {
+    pub slice_type: u64,
+    pub dependent: bool,
+    /// SliceQpY, before any CU level delta
+    pub qp: i64,
+    pub weighted_prediction: bool,
+    pub deblocking_filter_disabled: bool,
+    pub sao_luma: bool,
+    pub sao_chroma: bool,
+    pub max_num_merge_cand: u64,
 }
 
 impl SliceNAL {
@@ -32,9 +122,9 @@ impl SliceNAL {
             ..Default::default()
         };
 
-        if is_irap_nal(nal) {
+        if nal.is_irap() {
             slice.key_frame = true;
-            bs.skip_n(1)?; // no_output_of_prior_pics_flag
+            slice.no_output_of_prior_pics_flag = bs.read_bit()?;
         }
 
         slice.pps_id = bs.read_ue()?;
@@ -45,6 +135,8 @@ impl SliceNAL {
             .get(pps.sps_id as usize)
             .ok_or_else(|| format_err!("Invalid SPS index"))?;
 
+        slice.sps_id = pps.sps_id;
+
         if !slice.first_slice_in_pic_flag {
             if pps.dependent_slice_segments_enabled_flag {
                 slice.dependent_slice_segment_flag = bs.read_bit()?;
@@ -60,32 +152,55 @@ impl SliceNAL {
             slice.dependent_slice_segment_flag = false;
         }
 
-        if slice.dependent_slice_segment_flag {
-            return Ok(slice);
+        if !slice.dependent_slice_segment_flag {
+            slice.parse_poc(bs, sps, pps, nal, poc_tid0, poc)?;
         }
 
+        // The rest of the header is only used for the hwaccel parameters and statistics.
+        // A malformed value doesn't prevent finding the frame boundaries.
+        if let Err(e) = slice.parse_remaining_header(bs, sps, pps, nal) {
+            slice.header_error = Some(e.to_string());
+        }
+
+        Ok(slice)
+    }
+
+    /// Header syntax up to the POC, required to split the frames
+    fn parse_poc(
+        &mut self,
+        bs: &mut BsIoVecReader,
+        sps: &SPSNAL,
+        pps: &PPSNAL,
+        nal: &NALUnit,
+        poc_tid0: &mut u64,
+        poc: &mut u64,
+    ) -> Result<()> {
         for _ in 0..pps.num_extra_slice_header_bits {
             bs.skip_n(1)?; // slice_reserved_undetermined_flag
         }
 
-        slice.slice_type = bs.read_ue()?;
+        self.slice_type = bs.read_ue()?;
 
+        self.pic_output_flag = true;
         if pps.output_flag_present_flag {
-            bs.skip_n(1)?;
+            self.pic_output_flag = bs.read_bit()?;
         }
 
         if sps.separate_colour_plane_flag {
-            bs.skip_n(2)?;
+            self.colour_plane_id = bs.read::<2, u8>()?;
         }
 
-        if !is_idr_nal(nal) {
-            slice.pic_order_cnt_lsb = bs.read_var(sps.log2_max_poc_lsb as u32)?;
-            slice.output_picture_number = compute_poc(sps, *poc_tid0, slice.pic_order_cnt_lsb, nal);
+        if !nal.is_idr() {
+            self.pic_order_cnt_lsb = bs.read_var(sps.log2_max_poc_lsb as u32)?;
+            self.output_picture_number = compute_poc(sps, *poc_tid0, self.pic_order_cnt_lsb, nal);
         } else {
-            slice.output_picture_number = 0;
+            self.output_picture_number = 0;
         }
 
-        *poc = slice.output_picture_number;
+        // Two's complement of the wrapped value
+        self.pic_order_cnt_val = self.output_picture_number as i64 as i32;
+
+        *poc = self.output_picture_number;
 
         if nal.temporal_id == 0
             && nal.nal_type != NAL_TRAIL_N
@@ -99,37 +214,398 @@ impl SliceNAL {
             *poc_tid0 = *poc;
         }
 
-        Ok(slice)
+        Ok(())
     }
-}
 
-fn is_irap_nal(nal: &NALUnit) -> bool {
-    nal.nal_type >= 16 && nal.nal_type <= 23
+    fn parse_remaining_header(
+        &mut self,
+        bs: &mut BsIoVecReader,
+        sps: &SPSNAL,
+        pps: &PPSNAL,
+        nal: &NALUnit,
+    ) -> Result<()> {
+        if !self.dependent_slice_segment_flag {
+            self.parse_independent_header(bs, sps, pps, nal)?;
+        }
+
+        if pps.tiles_enabled_flag || pps.entropy_coding_sync_enabled_flag {
+            self.num_entry_point_offsets = bs.read_ue()?;
+
+            if self.num_entry_point_offsets > 0 {
+                self.offset_len = bs.read_ue()? + 1;
+
+                for _ in 0..self.num_entry_point_offsets {
+                    bs.skip_n(self.offset_len as u32)?; // entry_point_offset_minus1
+                }
+            }
+        }
+
+        if pps.slice_header_extension_present_flag {
+            self.slice_segment_header_extension_length = bs.read_ue()?;
+
+            for _ in 0..self.slice_segment_header_extension_length {
+                bs.skip_n(8)?; // slice_segment_header_extension_data_byte
+            }
+        }
+
+        // byte_alignment()
+        bs.skip_n(1)?; // alignment_bit_equal_to_one
+        while !bs.byte_aligned() {
+            bs.skip_n(1)?; // alignment_bit_equal_to_zero
+        }
+
+        self.header_size = bs.position_in_bits()? / 8;
+
+        Ok(())
+    }
+
+    /// Independent slice segment header after the POC
+    fn parse_independent_header(
+        &mut self,
+        bs: &mut BsIoVecReader,
+        sps: &SPSNAL,
+        pps: &PPSNAL,
+        nal: &NALUnit,
+    ) -> Result<()> {
+        if !nal.is_idr() {
+            self.parse_ref_pic_sets(bs, sps)?;
+
+            if sps.sps_temporal_mvp_enabled_flag {
+                self.slice_temporal_mvp_enabled_flag = bs.read_bit()?;
+            }
+        }
+
+        self.derive_rps(sps)?;
+
+        if sps.sao_enabled_flag {
+            self.slice_sao_luma_flag = bs.read_bit()?;
+
+            if sps.chroma_array_type() != 0 {
+                self.slice_sao_chroma_flag = bs.read_bit()?;
+            }
+        }
+
+        self.collocated_from_l0_flag = true;
+
+        if self.slice_type == SLICE_TYPE_P || self.slice_type == SLICE_TYPE_B {
+            let is_b_slice = self.slice_type == SLICE_TYPE_B;
+
+            self.num_ref_idx_l0_active = pps.num_ref_idx_l0_default_active;
+            if is_b_slice {
+                self.num_ref_idx_l1_active = pps.num_ref_idx_l1_default_active;
+            }
+
+            self.num_ref_idx_active_override_flag = bs.read_bit()?;
+            if self.num_ref_idx_active_override_flag {
+                self.num_ref_idx_l0_active = bs.read_ue()? + 1;
+
+                if is_b_slice {
+                    self.num_ref_idx_l1_active = bs.read_ue()? + 1;
+                }
+            }
+
+            let num_pic_total_curr = self.rps.num_pic_total_curr();
+            if pps.lists_modification_present_flag && num_pic_total_curr > 1 {
+                let list_entry_length = (num_pic_total_curr as f64).log2().ceil() as u32;
+
+                self.ref_pic_list_modification_flag_l0 = bs.read_bit()?;
+                if self.ref_pic_list_modification_flag_l0 {
+                    for _ in 0..self.num_ref_idx_l0_active {
+                        self.list_entry_l0.push(bs.read_var(list_entry_length)?);
+                    }
+                }
+
+                if is_b_slice {
+                    self.ref_pic_list_modification_flag_l1 = bs.read_bit()?;
+                    if self.ref_pic_list_modification_flag_l1 {
+                        for _ in 0..self.num_ref_idx_l1_active {
+                            self.list_entry_l1.push(bs.read_var(list_entry_length)?);
+                        }
+                    }
+                }
+            }
+
+            if is_b_slice {
+                self.mvd_l1_zero_flag = bs.read_bit()?;
+            }
+
+            if pps.cabac_init_present_flag {
+                self.cabac_init_flag = bs.read_bit()?;
+            }
+
+            if self.slice_temporal_mvp_enabled_flag {
+                if is_b_slice {
+                    self.collocated_from_l0_flag = bs.read_bit()?;
+                }
+
+                if (self.collocated_from_l0_flag && self.num_ref_idx_l0_active > 1)
+                    || (!self.collocated_from_l0_flag && self.num_ref_idx_l1_active > 1)
+                {
+                    self.collocated_ref_idx = bs.read_ue()?;
+                }
+            }
+
+            if (pps.weighted_pred_flag && self.slice_type == SLICE_TYPE_P)
+                || (pps.weighted_bipred_flag && is_b_slice)
+            {
+                self.pred_weight_table = Some(Box::new(PredWeightTable::parse(
+                    bs,
+                    sps,
+                    self.num_ref_idx_l0_active,
+                    self.num_ref_idx_l1_active,
+                    is_b_slice,
+                )?));
+            }
+
+            let five_minus_max_num_merge_cand = bs.read_ue()?;
+            ensure!(
+                five_minus_max_num_merge_cand <= 4,
+                "Invalid five_minus_max_num_merge_cand {five_minus_max_num_merge_cand}"
+            );
+
+            self.max_num_merge_cand = 5 - five_minus_max_num_merge_cand;
+        }
+
+        self.slice_qp_delta = bs.read_se()?;
+        self.slice_qp_y = 26 + pps.pic_init_qp_minus26 + self.slice_qp_delta;
+
+        if pps.pic_slice_level_chroma_qp_offsets_present_flag {
+            self.slice_cb_qp_offset = bs.read_se()?;
+            self.slice_cr_qp_offset = bs.read_se()?;
+        }
+
+        if pps.range_extension.chroma_qp_offset_list_enabled_flag {
+            self.cu_chroma_qp_offset_enabled_flag = bs.read_bit()?;
+        }
+
+        if pps.deblocking_filter_override_enabled_flag {
+            self.deblocking_filter_override_flag = bs.read_bit()?;
+        }
+
+        self.slice_deblocking_filter_disabled_flag = pps.disable_dbf;
+        self.slice_beta_offset = pps.beta_offset;
+        self.slice_tc_offset = pps.tc_offset;
+
+        if self.deblocking_filter_override_flag {
+            self.slice_deblocking_filter_disabled_flag = bs.read_bit()?;
+
+            if !self.slice_deblocking_filter_disabled_flag {
+                self.slice_beta_offset = 2 * bs.read_se()?;
+                self.slice_tc_offset = 2 * bs.read_se()?;
+            }
+        }
+
+        self.slice_loop_filter_across_slices_enabled_flag =
+            pps.seq_loop_filter_across_slices_enabled_flag;
+
+        if pps.seq_loop_filter_across_slices_enabled_flag
+            && (self.slice_sao_luma_flag
+                || self.slice_sao_chroma_flag
+                || !self.slice_deblocking_filter_disabled_flag)
+        {
+            self.slice_loop_filter_across_slices_enabled_flag = bs.read_bit()?;
+        }
+
+        Ok(())
+    }
+
+    fn parse_ref_pic_sets(&mut self, bs: &mut BsIoVecReader, sps: &SPSNAL) -> Result<()> {
+        self.short_term_ref_pic_set_sps_flag = bs.read_bit()?;
+
+        if !self.short_term_ref_pic_set_sps_flag {
+            let start = bs.position_in_bits()?;
+
+            self.short_term_ref_pic_set =
+                ShortTermRPS::parse(bs, sps, sps.nb_st_rps as usize, sps.nb_st_rps, true)?;
+
+            self.st_rps_bits = bs.position_in_bits()? - start;
+            self.curr_rps_idx = sps.nb_st_rps;
+        } else {
+            if sps.nb_st_rps > 1 {
+                let idx_length = (sps.nb_st_rps as f64).log2().ceil() as u32;
+                self.short_term_ref_pic_set_idx = bs.read_var(idx_length)?;
+            }
+
+            self.short_term_ref_pic_set = sps
+                .short_term_ref_pic_sets
+                .get(self.short_term_ref_pic_set_idx as usize)
+                .cloned()
+                .ok_or_else(|| format_err!("Invalid short term RPS index"))?;
+            self.curr_rps_idx = self.short_term_ref_pic_set_idx;
+        }
+
+        if sps.long_term_ref_pics_present_flag {
+            if sps.num_long_term_ref_pics_sps > 0 {
+                self.num_long_term_sps = bs.read_ue()?;
+            }
+
+            self.num_long_term_pics = bs.read_ue()?;
+
+            for i in 0..self.num_long_term_sps + self.num_long_term_pics {
+                if i < self.num_long_term_sps {
+                    let mut lt_idx_sps = 0;
+
+                    if sps.num_long_term_ref_pics_sps > 1 {
+                        let idx_length = (sps.num_long_term_ref_pics_sps as f64).log2().ceil();
+                        lt_idx_sps = bs.read_var(idx_length as u32)?;
+                    }
+
+                    let poc_lsb_lt = sps
+                        .lt_ref_pic_poc_lsb_sps
+                        .get(lt_idx_sps as usize)
+                        .ok_or_else(|| format_err!("Invalid lt_idx_sps"))?;
+
+                    self.lt_idx_sps.push(lt_idx_sps);
+                    self.poc_lsb_lt.push(*poc_lsb_lt);
+                    self.used_by_curr_pic_lt_flag
+                        .push(sps.used_by_curr_pic_lt_sps_flag[lt_idx_sps as usize]);
+                } else {
+                    self.poc_lsb_lt
+                        .push(bs.read_var(sps.log2_max_poc_lsb as u32)?);
+                    self.used_by_curr_pic_lt_flag.push(bs.read_bit()?);
+                }
+
+                let delta_poc_msb_present_flag = bs.read_bit()?;
+                self.delta_poc_msb_present_flag
+                    .push(delta_poc_msb_present_flag);
+
+                if delta_poc_msb_present_flag {
+                    self.delta_poc_msb_cycle_lt.push(bs.read_ue()?);
+                } else {
+                    self.delta_poc_msb_cycle_lt.push(0);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Derives the POC values of the RPS, 8.3.2
+    fn derive_rps(&mut self, sps: &SPSNAL) -> Result<()> {
+        let poc = self.pic_order_cnt_val;
+        let st_rps = &self.short_term_ref_pic_set;
+
+        for (delta_poc, used) in st_rps
+            .delta_poc_s0
+            .iter()
+            .zip(st_rps.used_by_curr_pic_s0_flags.iter())
+        {
+            let ref_poc = ref_poc(poc, *delta_poc)?;
+
+            if *used {
+                self.rps.st_curr_before.push(ref_poc);
+            } else {
+                self.rps.st_foll.push(ref_poc);
+            }
+        }
+
+        for (delta_poc, used) in st_rps
+            .delta_poc_s1
+            .iter()
+            .zip(st_rps.used_by_curr_pic_s1_flags.iter())
+        {
+            let ref_poc = ref_poc(poc, *delta_poc)?;
+
+            if *used {
+                self.rps.st_curr_after.push(ref_poc);
+            } else {
+                self.rps.st_foll.push(ref_poc);
+            }
+        }
+
+        let max_poc_lsb = 1_i64 << sps.log2_max_poc_lsb;
+        let mut delta_poc_msb_cycle_lt = 0;
+
+        for i in 0..self.poc_lsb_lt.len() {
+            if i == 0 || i as u64 == self.num_long_term_sps {
+                delta_poc_msb_cycle_lt = self.delta_poc_msb_cycle_lt[i] as i64;
+            } else {
+                delta_poc_msb_cycle_lt += self.delta_poc_msb_cycle_lt[i] as i64;
+            }
+
+            let msb_present = self.delta_poc_msb_present_flag[i];
+            let poc_lsb_lt = self.poc_lsb_lt[i] as i64;
+
+            let lt_poc = if msb_present {
+                poc as i64
+                    - delta_poc_msb_cycle_lt * max_poc_lsb
+                    - (self.pic_order_cnt_lsb as i64 - poc_lsb_lt)
+            } else {
+                poc_lsb_lt
+            };
+
+            let lt = LongTermRef {
+                poc: lt_poc as i32,
+                msb_present,
+            };
+
+            if self.used_by_curr_pic_lt_flag[i] {
+                self.rps.lt_curr.push(lt);
+            } else {
+                self.rps.lt_foll.push(lt);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copies the slice header values of the independent slice segment
+    pub(crate) fn inherit_from(&mut self, independent: &SliceNAL) {
+        *self = SliceNAL {
+            first_slice_in_pic_flag: self.first_slice_in_pic_flag,
+            dependent_slice_segment_flag: self.dependent_slice_segment_flag,
+            slice_segment_addr: self.slice_segment_addr,
+            num_entry_point_offsets: self.num_entry_point_offsets,
+            offset_len: self.offset_len,
+            slice_segment_header_extension_length: self.slice_segment_header_extension_length,
+            header_size: self.header_size,
+            header_error: self
+                .header_error
+                .clone()
+                .or(independent.header_error.clone()),
+            ..independent.clone()
+        };
+    }
 }
 
-fn is_idr_nal(nal: &NALUnit) -> bool {
-    nal.nal_type == NAL_IDR_W_RADL || nal.nal_type == NAL_IDR_N_LP
+/// POC of a short-term reference picture, which must stay in the range of `PicOrderCntVal`
+fn ref_poc(poc: i32, delta_poc: i64) -> Result<i32> {
+    i32::try_from(poc as i64 + delta_poc)
+        .map_err(|_| format_err!("Reference POC out of range: {poc} + {delta_poc}"))
 }
 
 fn compute_poc(sps: &SPSNAL, poc_tid0: u64, poc_lsb: u64, nal: &NALUnit) -> u64 {
     let max_poc_lsb = 1 << sps.log2_max_poc_lsb;
     let prev_poc_lsb = poc_tid0 % max_poc_lsb;
-    let prev_poc_msb = poc_tid0 - prev_poc_lsb;
+    let prev_poc_msb = poc_tid0.wrapping_sub(prev_poc_lsb);
 
+    // Negative POC values wrap around
     let mut poc_msb = if poc_lsb < prev_poc_lsb && prev_poc_lsb - poc_lsb >= max_poc_lsb / 2 {
-        prev_poc_msb + max_poc_lsb
+        prev_poc_msb.wrapping_add(max_poc_lsb)
     } else if poc_lsb > prev_poc_lsb && poc_lsb - prev_poc_lsb > max_poc_lsb / 2 {
-        prev_poc_msb - max_poc_lsb
+        prev_poc_msb.wrapping_sub(max_poc_lsb)
     } else {
         prev_poc_msb
     };
 
-    if nal.nal_type == NAL_BLA_W_LP
-        || nal.nal_type == NAL_BLA_W_RADL
-        || nal.nal_type == NAL_BLA_N_LP
-    {
+    if nal.is_bla() {
         poc_msb = 0;
     }
 
-    poc_msb + poc_lsb
+    poc_msb.wrapping_add(poc_lsb)
+}
+
+impl From<&SliceNAL> for SliceInfo {
+    fn from(slice: &SliceNAL) -> Self {
+        Self {
+            slice_type: slice.slice_type,
+            dependent: slice.dependent_slice_segment_flag,
+            qp: slice.slice_qp_y,
+            weighted_prediction: slice.pred_weight_table.is_some(),
+            deblocking_filter_disabled: slice.slice_deblocking_filter_disabled_flag,
+            sao_luma: slice.slice_sao_luma_flag,
+            sao_chroma: slice.slice_sao_chroma_flag,
+            max_num_merge_cand: slice.max_num_merge_cand,
+        }
+    }
 }
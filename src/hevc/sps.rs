@@ -1,6 +1,7 @@
 use anyhow::Result;
 
 use super::BsIoVecReader;
+use super::hrd_parameters::HrdParameters;
 use super::profile_tier_level::ProfileTierLevel;
 use super::scaling_list_data::ScalingListData;
 use super::short_term_rps::ShortTermRPS;
@@ -10,65 +11,72 @@ use super::vui_parameters::VuiParameters;
 #[derive(Default, Debug, PartialEq, Clone, Eq)]
 pub struct SPSNAL {
     pub(crate) vps_id: u8,
-    max_sub_layers: u8,
+    pub(crate) max_sub_layers: u8,
     temporal_id_nesting_flag: bool,
 
     ptl: ProfileTierLevel,
     pub(crate) sps_id: u64,
-    chroma_format_idc: u64,
+    pub(crate) chroma_format_idc: u64,
     pub(crate) separate_colour_plane_flag: bool,
-    width: u64,
-    height: u64,
+    pub(crate) width: u64,
+    pub(crate) height: u64,
 
-    pic_conformance_flag: bool,
-    conf_win_left_offset: u64,
-    conf_win_right_offset: u64,
-    conf_win_top_offset: u64,
-    conf_win_bottom_offset: u64,
+    pub(crate) pic_conformance_flag: bool,
+    pub(crate) conf_win_left_offset: u64,
+    pub(crate) conf_win_right_offset: u64,
+    pub(crate) conf_win_top_offset: u64,
+    pub(crate) conf_win_bottom_offset: u64,
 
-    bit_depth: u64,
-    bit_depth_chroma: u64,
+    pub(crate) bit_depth: u64,
+    pub(crate) bit_depth_chroma: u64,
     pub(crate) log2_max_poc_lsb: u64,
     sublayer_ordering_info: bool,
-    max_dec_pic_buffering: Vec<u64>,
-    num_reorder_pics: Vec<u64>,
+    pub(crate) max_dec_pic_buffering: Vec<u64>,
+    pub(crate) num_reorder_pics: Vec<u64>,
     max_latency_increase: Vec<u64>,
 
-    log2_min_cb_size: u64,
-    log2_diff_max_min_coding_block_size: u64,
-    log2_min_tb_size: u64,
-    log2_diff_max_min_transform_block_size: u64,
-    max_transform_hierarchy_depth_inter: u64,
-    max_transform_hierarchy_depth_intra: u64,
-
-    scaling_list_enabled_flag: bool,
-    scaling_list_data_present_flag: bool,
-    scaling_list_data: ScalingListData,
-
-    amp_enabled_flag: bool,
-    sao_enabled_flag: bool,
-    pcm_enabled_flag: bool,
-    pcm_bit_depth: u8,
-    pcm_bit_depth_chroma: u8,
-    pcm_log2_min_pcm_cb_size: u64,
-    pcm_log2_max_pcm_cb_size: u64,
-    pcm_loop_filter_disable_flag: bool,
-
-    nb_st_rps: u64,
+    pub(crate) log2_min_cb_size: u64,
+    pub(crate) log2_diff_max_min_coding_block_size: u64,
+    pub(crate) log2_min_tb_size: u64,
+    pub(crate) log2_diff_max_min_transform_block_size: u64,
+    pub(crate) max_transform_hierarchy_depth_inter: u64,
+    pub(crate) max_transform_hierarchy_depth_intra: u64,
+
+    pub(crate) scaling_list_enabled_flag: bool,
+    pub(crate) scaling_list_data_present_flag: bool,
+    pub(crate) scaling_list_data: ScalingListData,
+
+    pub(crate) amp_enabled_flag: bool,
+    pub(crate) sao_enabled_flag: bool,
+    pub(crate) pcm_enabled_flag: bool,
+    pub(crate) pcm_bit_depth: u8,
+    pub(crate) pcm_bit_depth_chroma: u8,
+    pub(crate) pcm_log2_min_pcm_cb_size: u64,
+    pub(crate) pcm_log2_max_pcm_cb_size: u64,
+    pub(crate) pcm_loop_filter_disable_flag: bool,
+
+    pub(crate) nb_st_rps: u64,
     pub(crate) short_term_ref_pic_sets: Vec<ShortTermRPS>,
 
-    long_term_ref_pics_present_flag: bool,
-    num_long_term_ref_pics_sps: u64,
-    lt_ref_pic_poc_lsb_sps: Vec<u64>,
-    used_by_curr_pic_lt_sps_flag: Vec<bool>,
+    pub(crate) long_term_ref_pics_present_flag: bool,
+    pub(crate) num_long_term_ref_pics_sps: u64,
+    pub(crate) lt_ref_pic_poc_lsb_sps: Vec<u64>,
+    pub(crate) used_by_curr_pic_lt_sps_flag: Vec<bool>,
 
-    sps_temporal_mvp_enabled_flag: bool,
-    sps_strong_intra_smoothing_enable_flag: bool,
+    pub(crate) sps_temporal_mvp_enabled_flag: bool,
+    pub(crate) sps_strong_intra_smoothing_enable_flag: bool,
 
-    vui_present: bool,
-    vui_parameters: VuiParameters,
+    pub(crate) vui_present: bool,
+    pub(crate) vui_parameters: VuiParameters,
+    /// HRD parameters of the referenced VPS, used when the VUI has none
+    pub(crate) vps_hrd_parameters: Option<HrdParameters>,
 
     sps_extension_flag: bool,
+    sps_range_extension_flag: bool,
+    sps_multilayer_extension_flag: bool,
+    sps_3d_extension_flag: bool,
+    sps_scc_extension_flag: bool,
+    pub(crate) range_extension: SpsRangeExtension,
 
     // Computed values
     pub(crate) log2_ctb_size: u64,
@@ -85,6 +93,19 @@ pub struct SPSNAL {
     pub(crate) tb_mask: u64,
 }
 
+#[derive(Default, Debug, PartialEq, Clone, Eq)]
+pub(crate) struct SpsRangeExtension {
+    pub(crate) transform_skip_rotation_enabled_flag: bool,
+    pub(crate) transform_skip_context_enabled_flag: bool,
+    pub(crate) implicit_rdpcm_enabled_flag: bool,
+    pub(crate) explicit_rdpcm_enabled_flag: bool,
+    pub(crate) extended_precision_processing_flag: bool,
+    pub(crate) intra_smoothing_disabled_flag: bool,
+    pub(crate) high_precision_offsets_enabled_flag: bool,
+    pub(crate) persistent_rice_adaptation_enabled_flag: bool,
+    pub(crate) cabac_bypass_alignment_enabled_flag: bool,
+}
+
 impl SPSNAL {
     pub fn parse(bs: &mut BsIoVecReader) -> Result<SPSNAL> {
         let mut sps = SPSNAL {
@@ -203,6 +224,18 @@ impl SPSNAL {
 
         sps.sps_extension_flag = bs.read_bit()?;
 
+        if sps.sps_extension_flag {
+            sps.sps_range_extension_flag = bs.read_bit()?;
+            sps.sps_multilayer_extension_flag = bs.read_bit()?;
+            sps.sps_3d_extension_flag = bs.read_bit()?;
+            sps.sps_scc_extension_flag = bs.read_bit()?;
+            bs.skip_n(4)?; // sps_extension_4bits
+
+            if sps.sps_range_extension_flag {
+                sps.range_extension = SpsRangeExtension::parse(bs)?;
+            }
+        }
+
         // Computed values
         sps.log2_ctb_size = sps.log2_min_cb_size + sps.log2_diff_max_min_coding_block_size;
         sps.log2_min_pu_size = sps.log2_min_cb_size - 1;
@@ -221,4 +254,34 @@ impl SPSNAL {
 
         Ok(sps)
     }
+
+    /// `ChromaArrayType`, `chroma_format_idc` is already 0 for separate colour planes
+    pub(crate) fn chroma_array_type(&self) -> u64 {
+        self.chroma_format_idc
+    }
+
+    /// HRD parameters of the VUI, otherwise of the VPS for the base layer
+    pub(crate) fn hrd_parameters(&self) -> Option<&HrdParameters> {
+        self.vui_parameters
+            .hrd_parameters
+            .as_ref()
+            .filter(|_| self.vui_present)
+            .or(self.vps_hrd_parameters.as_ref())
+    }
+}
+
+impl SpsRangeExtension {
+    pub fn parse(bs: &mut BsIoVecReader) -> Result<SpsRangeExtension> {
+        Ok(SpsRangeExtension {
+            transform_skip_rotation_enabled_flag: bs.read_bit()?,
+            transform_skip_context_enabled_flag: bs.read_bit()?,
+            implicit_rdpcm_enabled_flag: bs.read_bit()?,
+            explicit_rdpcm_enabled_flag: bs.read_bit()?,
+            extended_precision_processing_flag: bs.read_bit()?,
+            intra_smoothing_disabled_flag: bs.read_bit()?,
+            high_precision_offsets_enabled_flag: bs.read_bit()?,
+            persistent_rice_adaptation_enabled_flag: bs.read_bit()?,
+            cabac_bypass_alignment_enabled_flag: bs.read_bit()?,
+        })
+    }
 }
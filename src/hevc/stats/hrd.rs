@@ -0,0 +1,445 @@
+use std::io::Write;
+
+use anyhow::{Result, bail};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use super::FrameStats;
+use crate::hevc::Frame;
+use crate::hevc::sei::{BufferingPeriod, SeiPayload};
+use crate::hevc::sps::SPSNAL;
+use crate::hevc::timing::TimingInfo;
+
+/// Clock of the initial CPB removal delays
+const INITIAL_DELAY_CLOCK: f64 = 90_000.0;
+
+/// Annex C hypothetical reference decoder simulation, at the access unit level.
+/// Uses the NAL HRD parameters when present, otherwise the VCL ones, for the first CPB of the highest sub-layer.
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct HrdReport {
+    /// Whether the NAL HRD was used, the coded sizes then include every base layer NAL unit and start code
+    pub nal_hrd: bool,
+    /// Bits per second
+    pub bit_rate: u64,
+    /// In bits
+    pub cpb_size: u64,
+    pub cbr: bool,
+    pub low_delay: bool,
+
+    /// Per frame buffer levels, in decoding order
+    pub frames: Vec<HrdFrame>,
+    pub violations: Vec<HrdViolation>,
+}
+
+/// Times are in seconds from the arrival of the first access unit
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct HrdFrame {
+    pub decoded_number: u64,
+    pub presentation_number: u64,
+    /// In bits
+    pub size: u64,
+
+    pub initial_arrival_time: f64,
+    pub final_arrival_time: f64,
+    pub removal_time: f64,
+    pub output_time: f64,
+
+    /// CPB fullness in bits, just before and after the removal of the access unit
+    pub cpb_level_before_removal: u64,
+    pub cpb_level_after_removal: u64,
+
+    /// Decoded pictures waiting for output, after the decoding of this one
+    pub dpb_pictures_waiting: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum HrdViolationKind {
+    /// The access unit is not entirely in the CPB at its removal time
+    CpbUnderflow,
+    /// The CPB fullness exceeds the CPB size
+    CpbOverflow,
+    /// The picture is output before being decoded
+    OutputBeforeRemoval,
+    /// The output times are not increasing in presentation order
+    OutputOrder,
+    /// More pictures waiting for output than `sps_max_num_reorder_pics`
+    DpbReorder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct HrdViolation {
+    pub decoded_number: u64,
+    pub kind: HrdViolationKind,
+}
+
+impl HrdReport {
+    /// `frames` can be in any order, they are simulated in decoding order
+    pub(crate) fn new(frames: &[Frame], sps: &SPSNAL, timing: &TimingInfo) -> Result<Self> {
+        let Some(hrd) = sps.hrd_parameters() else {
+            bail!("No HRD parameters in the SPS VUI or the VPS");
+        };
+
+        let nal_hrd = hrd.nal_hrd_parameters_present_flag;
+        let Some(sub_layer) = hrd.sub_layers.last() else {
+            bail!("No sub-layer HRD parameters");
+        };
+
+        let Some(params) = (if nal_hrd {
+            sub_layer.nal.first()
+        } else {
+            sub_layer.vcl.first()
+        }) else {
+            bail!("No CPB parameters in the HRD");
+        };
+
+        let mut report = HrdReport {
+            nal_hrd,
+            bit_rate: (params.bit_rate_value_minus1 + 1) << (6 + hrd.bit_rate_scale),
+            cpb_size: (params.cpb_size_value_minus1 + 1) << (4 + hrd.cpb_size_scale),
+            cbr: params.cbr_flag,
+            low_delay: sub_layer.low_delay_hrd_flag,
+            ..Default::default()
+        };
+
+        let clock_tick = timing.num_units_in_tick as f64 / timing.time_scale as f64;
+        let bit_rate = report.bit_rate as f64;
+
+        let mut decoding_order: Vec<&Frame> = frames.iter().collect();
+        decoding_order.sort_by_key(|f| f.decoded_number);
+
+        // Removal time and initial delays of the last access unit with a buffering period
+        let mut buffering_period: Option<(f64, f64)> = None;
+        let mut previous_final_arrival = 0.0;
+
+        for frame in decoding_order {
+            let bp = frame.prefix_sei.iter().find_map(|sei| match sei {
+                SeiPayload::BufferingPeriod(bp) => Some(bp),
+                _ => None,
+            });
+            let Some(delays) = frame.prefix_sei.iter().find_map(|sei| match sei {
+                SeiPayload::PicTiming(pt) => pt.hrd.as_ref(),
+                _ => None,
+            }) else {
+                bail!(
+                    "Frame {} has no pic_timing CPB delays",
+                    frame.decoded_number
+                );
+            };
+
+            let stats = FrameStats::new(frame);
+            // Operation point of the base layer
+            let size = 8 * if nal_hrd {
+                stats.base_layer_size()
+            } else {
+                stats.slice_size
+            };
+
+            let au_cpb_removal_delay =
+                (delays.au_cpb_removal_delay_minus1 as f64 + 1.0) * clock_tick;
+
+            let removal_time = match (buffering_period, bp) {
+                (None, None) => bail!("The first frame has no buffering period SEI"),
+                (None, Some(bp)) => initial_cpb_removal(bp, nal_hrd).0,
+                (Some((base, _)), _) => base + au_cpb_removal_delay,
+            };
+
+            if let Some(bp) = bp {
+                buffering_period = Some((removal_time, initial_cpb_removal(bp, nal_hrd).1));
+            }
+
+            let initial_delay = buffering_period.map_or(0.0, |(_, delay)| delay);
+
+            let initial_arrival_time = if report.cbr || report.frames.is_empty() {
+                previous_final_arrival
+            } else {
+                f64::max(previous_final_arrival, removal_time - initial_delay)
+            };
+            let final_arrival_time = initial_arrival_time + size as f64 / bit_rate;
+            previous_final_arrival = final_arrival_time;
+
+            report.frames.push(HrdFrame {
+                decoded_number: frame.decoded_number,
+                presentation_number: frame.presentation_number,
+                size,
+                initial_arrival_time,
+                final_arrival_time,
+                removal_time,
+                output_time: removal_time + delays.pic_dpb_output_delay as f64 * clock_tick,
+                ..Default::default()
+            });
+        }
+
+        report.compute_cpb_levels();
+        report.check_output(sps.num_reorder_pics.last().copied().unwrap_or(0));
+
+        Ok(report)
+    }
+
+    fn compute_cpb_levels(&mut self) {
+        let bit_rate = self.bit_rate as f64;
+        let mut removed_bits = 0;
+
+        // Access units entirely in the CPB
+        let mut arrived_count = 0;
+        let mut arrived_bits = 0;
+
+        for i in 0..self.frames.len() {
+            let removal_time = self.frames[i].removal_time;
+
+            while let Some(f) = self.frames.get(arrived_count)
+                && f.final_arrival_time <= removal_time
+            {
+                arrived_bits += f.size;
+                arrived_count += 1;
+            }
+
+            let partial_bits: u64 = self.frames[arrived_count..]
+                .iter()
+                .take_while(|f| f.initial_arrival_time < removal_time)
+                .map(|f| {
+                    f.size
+                        .min(((removal_time - f.initial_arrival_time) * bit_rate) as u64)
+                })
+                .sum();
+
+            let frame = &mut self.frames[i];
+            frame.cpb_level_before_removal =
+                (arrived_bits + partial_bits).saturating_sub(removed_bits);
+
+            // Low delay HRD allows removing once the access unit has arrived
+            if frame.final_arrival_time > removal_time + 1e-9 && !self.low_delay {
+                self.violations.push(HrdViolation {
+                    decoded_number: frame.decoded_number,
+                    kind: HrdViolationKind::CpbUnderflow,
+                });
+            }
+
+            if frame.cpb_level_before_removal > self.cpb_size {
+                self.violations.push(HrdViolation {
+                    decoded_number: frame.decoded_number,
+                    kind: HrdViolationKind::CpbOverflow,
+                });
+            }
+
+            removed_bits += frame.size;
+            frame.cpb_level_after_removal =
+                frame.cpb_level_before_removal.saturating_sub(frame.size);
+        }
+    }
+
+    fn check_output(&mut self, max_num_reorder: u64) {
+        // Output times of the decoded pictures not output yet
+        let mut pending: Vec<f64> = Vec::new();
+
+        for frame in self.frames.iter_mut() {
+            pending.push(frame.output_time);
+            pending.retain(|&t| t > frame.removal_time);
+
+            let waiting = pending.len() as u64;
+            frame.dpb_pictures_waiting = waiting;
+
+            if frame.output_time < frame.removal_time {
+                self.violations.push(HrdViolation {
+                    decoded_number: frame.decoded_number,
+                    kind: HrdViolationKind::OutputBeforeRemoval,
+                });
+            }
+
+            if waiting > max_num_reorder {
+                self.violations.push(HrdViolation {
+                    decoded_number: frame.decoded_number,
+                    kind: HrdViolationKind::DpbReorder,
+                });
+            }
+        }
+
+        let mut output_order: Vec<&HrdFrame> = self.frames.iter().collect();
+        output_order.sort_by_key(|f| f.presentation_number);
+
+        for pair in output_order.windows(2) {
+            if pair[1].output_time <= pair[0].output_time {
+                self.violations.push(HrdViolation {
+                    decoded_number: pair[1].decoded_number,
+                    kind: HrdViolationKind::OutputOrder,
+                });
+            }
+        }
+    }
+
+    /// Whether the stream conforms to the simulated HRD
+    pub fn is_conforming(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Writes one line per frame, in decoding order
+    pub fn write_csv(&self, writer: &mut dyn Write) -> Result<()> {
+        writeln!(
+            writer,
+            "decoded_number,presentation_number,size,initial_arrival_time,final_arrival_time,removal_time,output_time,cpb_level_before_removal,cpb_level_after_removal,dpb_pictures_waiting"
+        )?;
+
+        for f in &self.frames {
+            writeln!(
+                writer,
+                "{},{},{},{:.6},{:.6},{:.6},{:.6},{},{},{}",
+                f.decoded_number,
+                f.presentation_number,
+                f.size,
+                f.initial_arrival_time,
+                f.final_arrival_time,
+                f.removal_time,
+                f.output_time,
+                f.cpb_level_before_removal,
+                f.cpb_level_after_removal,
+                f.dpb_pictures_waiting
+            )?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn write_json(&self, writer: &mut dyn Write) -> Result<()> {
+        serde_json::to_writer_pretty(writer, self)?;
+
+        Ok(())
+    }
+}
+
+/// Removal time of the first access unit and initial delay, in seconds
+fn initial_cpb_removal(bp: &BufferingPeriod, nal_hrd: bool) -> (f64, f64) {
+    let removal = if nal_hrd {
+        bp.nal_initial_cpb_removal.first()
+    } else {
+        bp.vcl_initial_cpb_removal.first()
+    };
+
+    removal.map_or((0.0, 0.0), |r| {
+        let delay = r.initial_cpb_removal_delay as f64 / INITIAL_DELAY_CLOCK;
+        let offset = r.initial_cpb_removal_offset as f64 / INITIAL_DELAY_CLOCK;
+
+        (delay, delay + offset)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hevc::NALUnit;
+    use crate::hevc::hrd_parameters::{HrdParameters, SubLayerHrd, SubLayerHrdParameter};
+    use crate::hevc::sei::buffering_period::InitialCpbRemoval;
+    use crate::hevc::sei::pic_timing::{PicTiming, PicTimingHrd};
+
+    fn frame(decoded_number: u64, slice_size: usize, removal_delay_minus1: u32) -> Frame {
+        let mut prefix_sei = Vec::new();
+
+        if decoded_number == 0 {
+            // 0.25 s initial delay
+            prefix_sei.push(SeiPayload::BufferingPeriod(BufferingPeriod {
+                vcl_initial_cpb_removal: vec![InitialCpbRemoval {
+                    initial_cpb_removal_delay: 22500,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }));
+        }
+
+        prefix_sei.push(SeiPayload::PicTiming(PicTiming {
+            hrd: Some(PicTimingHrd {
+                au_cpb_removal_delay_minus1: removal_delay_minus1,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }));
+
+        Frame {
+            decoded_number,
+            presentation_number: decoded_number,
+            nals: vec![NALUnit {
+                nal_type: 1,
+                end: slice_size,
+                ..Default::default()
+            }],
+            prefix_sei,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn vbr_buffer_levels() -> Result<()> {
+        // Only in the VPS: 64 kbps, 12800 bits CPB
+        let mut sps = SPSNAL::default();
+        sps.vps_hrd_parameters = Some(HrdParameters {
+            vcl_hrd_parameters_present_flag: true,
+            sub_layers: vec![SubLayerHrd {
+                vcl: vec![SubLayerHrdParameter {
+                    bit_rate_value_minus1: 999,
+                    cpb_size_value_minus1: 799,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+
+        let timing = TimingInfo {
+            num_units_in_tick: 1,
+            time_scale: 32,
+            num_ticks_poc_diff_one: None,
+            fixed_frame_ticks: None,
+        };
+
+        let frames = [frame(0, 1000, 0), frame(1, 1000, 0), frame(2, 2000, 1)];
+        let report = HrdReport::new(&frames, &sps, &timing)?;
+
+        assert!(!report.nal_hrd);
+        assert_eq!(report.bit_rate, 64000);
+        assert_eq!(report.cpb_size, 12800);
+
+        let levels: Vec<_> = report
+            .frames
+            .iter()
+            .map(|f| {
+                (
+                    f.initial_arrival_time,
+                    f.final_arrival_time,
+                    f.removal_time,
+                    f.cpb_level_before_removal,
+                    f.cpb_level_after_removal,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            levels,
+            [
+                (0.0, 0.125, 0.25, 16000, 8000),
+                // Removal delay of one tick, arrival right after the previous frame
+                (0.125, 0.25, 0.28125, 10000, 2000),
+                // Two ticks, the 16000 bits only arrive at 0.5 s
+                (0.25, 0.5, 0.3125, 4000, 0),
+            ]
+        );
+
+        assert_eq!(
+            report.violations,
+            [
+                HrdViolation {
+                    decoded_number: 0,
+                    kind: HrdViolationKind::CpbOverflow,
+                },
+                HrdViolation {
+                    decoded_number: 2,
+                    kind: HrdViolationKind::CpbUnderflow,
+                },
+            ]
+        );
+
+        Ok(())
+    }
+}
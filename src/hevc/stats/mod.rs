@@ -0,0 +1,456 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+use anyhow::Result;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use super::*;
+
+pub mod hrd;
+pub mod qp;
+
+pub use hrd::HrdReport;
+pub use qp::QpReport;
+
+/// Size, bitrate and GOP structure statistics of a stream
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct StreamStats {
+    /// Per frame statistics, in presentation order
+    pub frames: Vec<FrameStats>,
+    /// GOPs in decoding order
+    pub gops: Vec<GopStats>,
+
+    /// Total coded size in bytes, including start codes
+    pub total_size: u64,
+    /// Duration in seconds, when the frames have timestamps
+    pub duration: Option<f64>,
+
+    /// Bits per second
+    pub average_bitrate: Option<f64>,
+    /// Highest bitrate over the sliding window
+    pub peak_bitrate: Option<f64>,
+
+    /// Number of GOPs for each GOP length, in frames
+    pub gop_length_distribution: BTreeMap<u64, u64>,
+    /// Highest B-frame pyramid depth, 0 without B-frames
+    pub max_pyramid_depth: u32,
+    /// Number of frames for each temporal ID
+    pub temporal_layers: BTreeMap<u8, u64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct FrameStats {
+    pub decoded_number: u64,
+    pub presentation_number: u64,
+    pub frame_type: u64,
+    /// IRAP picture or recovery point
+    pub key_frame: bool,
+    pub poc: i32,
+    pub temporal_id: u8,
+
+    /// Sizes in bytes, the NAL start codes are counted as other
+    pub slice_size: u64,
+    pub sei_size: u64,
+    pub parameter_set_size: u64,
+    pub other_size: u64,
+    /// NAL units of the layers above the base layer, with their start codes
+    pub layer_size: u64,
+
+    /// PTS and duration in seconds
+    pub pts: Option<f64>,
+    pub duration: Option<f64>,
+    /// Bits per second over the sliding window ending with this frame
+    pub bitrate: Option<f64>,
+
+    /// Level in the B-frame hierarchy, 0 for frames without future references
+    pub pyramid_depth: u32,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct GopStats {
+    /// Decoded number of the key frame
+    pub start: u64,
+    /// Length in frames
+    pub length: u64,
+    /// No RASL pictures reference the previous GOP
+    pub closed: bool,
+    /// Starts at a recovery point SEI instead of an IRAP picture, as with gradual decoding refresh
+    pub recovery_point: bool,
+    /// Size in bytes
+    pub size: u64,
+    /// Duration in seconds, when the frames have timestamps
+    pub duration: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatsOptions {
+    /// Length of the bitrate window, in seconds
+    pub bitrate_window: f64,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self {
+            bitrate_window: 1.0,
+        }
+    }
+}
+
+impl StreamStats {
+    /// Collects the statistics of frames in presentation order, usually `HevcParser::ordered_frames`
+    pub fn new(frames: &[Frame], opts: &StatsOptions) -> Self {
+        let mut stats = StreamStats {
+            frames: frames.iter().map(FrameStats::new).collect(),
+            ..Default::default()
+        };
+
+        stats.frames.sort_by_key(|f| f.presentation_number);
+        stats.total_size = stats.frames.iter().map(|f| f.size()).sum();
+
+        for f in &stats.frames {
+            *stats.temporal_layers.entry(f.temporal_id).or_default() += 1;
+        }
+
+        stats.compute_pyramid_depths(frames);
+        stats.compute_gops(frames);
+        stats.compute_bitrates(opts.bitrate_window);
+
+        stats.max_pyramid_depth = stats
+            .frames
+            .iter()
+            .map(|f| f.pyramid_depth)
+            .max()
+            .unwrap_or(0);
+
+        for gop in &stats.gops {
+            *stats.gop_length_distribution.entry(gop.length).or_default() += 1;
+        }
+
+        stats
+    }
+
+    pub fn closed_gop_count(&self) -> usize {
+        self.gops.iter().filter(|gop| gop.closed).count()
+    }
+
+    pub fn open_gop_count(&self) -> usize {
+        self.gops.len() - self.closed_gop_count()
+    }
+
+    /// Average key frame interval, in seconds when the frames have timestamps
+    pub fn average_keyframe_interval(&self) -> Option<f64> {
+        let durations: Vec<f64> = self.gops.iter().filter_map(|gop| gop.duration).collect();
+
+        if durations.is_empty() {
+            None
+        } else {
+            Some(durations.iter().sum::<f64>() / durations.len() as f64)
+        }
+    }
+
+    /// Writes one line per frame, in presentation order
+    pub fn write_csv(&self, writer: &mut dyn Write) -> Result<()> {
+        writeln!(
+            writer,
+            "decoded_number,presentation_number,frame_type,key_frame,poc,temporal_id,size,slice_size,sei_size,parameter_set_size,other_size,layer_size,pts,duration,bitrate,pyramid_depth"
+        )?;
+
+        let opt = |v: Option<f64>| v.map(|v| format!("{v:.6}")).unwrap_or_default();
+
+        for f in &self.frames {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                f.decoded_number,
+                f.presentation_number,
+                frame_type_char(f.frame_type),
+                f.key_frame,
+                f.poc,
+                f.temporal_id,
+                f.size(),
+                f.slice_size,
+                f.sei_size,
+                f.parameter_set_size,
+                f.other_size,
+                f.layer_size,
+                opt(f.pts),
+                opt(f.duration),
+                opt(f.bitrate),
+                f.pyramid_depth,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn write_json(&self, writer: &mut dyn Write) -> Result<()> {
+        serde_json::to_writer_pretty(writer, self)?;
+
+        Ok(())
+    }
+
+    /// Depth of each picture with future references, in decoding order.
+    /// Anchor pictures are at depth 0, and B-frames are one level below their deepest reference.
+    fn compute_pyramid_depths(&mut self, frames: &[Frame]) {
+        let mut decode_order: Vec<&Frame> = frames.iter().collect();
+        decode_order.sort_by_key(|f| f.decoded_number);
+
+        let mut depths: HashMap<i32, u32> = HashMap::new();
+        let mut frame_depths: HashMap<u64, u32> = HashMap::new();
+
+        for frame in decode_order {
+            let slice = &frame.first_slice;
+
+            if frame.starts_clvs() {
+                depths.clear();
+            }
+
+            let rps = &slice.rps;
+            let depth = if rps.st_curr_after.is_empty() {
+                0
+            } else {
+                1 + rps
+                    .st_curr_before
+                    .iter()
+                    .chain(rps.st_curr_after.iter())
+                    .filter_map(|poc| depths.get(poc))
+                    .max()
+                    .copied()
+                    .unwrap_or(0)
+            };
+
+            depths.insert(slice.pic_order_cnt_val, depth);
+            frame_depths.insert(frame.decoded_number, depth);
+        }
+
+        for f in self.frames.iter_mut() {
+            f.pyramid_depth = frame_depths.get(&f.decoded_number).copied().unwrap_or(0);
+        }
+    }
+
+    fn compute_gops(&mut self, frames: &[Frame]) {
+        let mut decode_order: Vec<&Frame> = frames.iter().collect();
+        decode_order.sort_by_key(|f| f.decoded_number);
+
+        let frame_stats: HashMap<u64, &FrameStats> =
+            self.frames.iter().map(|f| (f.decoded_number, f)).collect();
+
+        for frame in decode_order {
+            let Some(stats) = frame_stats.get(&frame.decoded_number) else {
+                continue;
+            };
+
+            if frame.is_random_access_point() || self.gops.is_empty() {
+                self.gops.push(GopStats {
+                    start: frame.decoded_number,
+                    closed: true,
+                    recovery_point: !frame.first_slice.key_frame
+                        && frame.recovery_point().is_some(),
+                    duration: Some(0.0),
+                    ..Default::default()
+                });
+            }
+
+            let Some(gop) = self.gops.last_mut() else {
+                continue;
+            };
+
+            gop.length += 1;
+            gop.size += stats.size();
+            gop.duration = gop.duration.zip(stats.duration).map(|(a, b)| a + b);
+
+            if first_slice_nal(frame)
+                .is_some_and(|nal| matches!(nal.nal_type, NAL_RASL_N | NAL_RASL_R))
+            {
+                gop.closed = false;
+            }
+        }
+    }
+
+    fn compute_bitrates(&mut self, window: f64) {
+        let frames = &mut self.frames;
+
+        let (Some(first), Some(last)) = (frames.first(), frames.last()) else {
+            return;
+        };
+
+        if let (Some(start), Some(end), Some(last_duration)) = (first.pts, last.pts, last.duration)
+        {
+            let duration = end + last_duration - start;
+
+            if duration > 0.0 {
+                self.duration = Some(duration);
+                self.average_bitrate = Some(self.total_size as f64 * 8.0 / duration);
+            }
+        }
+
+        let mut window_start = 0;
+        let mut window_bits = 0;
+
+        for i in 0..frames.len() {
+            let (Some(pts), Some(duration)) = (frames[i].pts, frames[i].duration) else {
+                continue;
+            };
+
+            let window_end = pts + duration;
+            window_bits += frames[i].size() * 8;
+
+            // The current frame stays in the window, even when it lasts longer than it
+            while window_start < i
+                && frames[window_start]
+                    .pts
+                    .is_some_and(|start_pts| start_pts < window_end - window)
+            {
+                window_bits -= frames[window_start].size() * 8;
+                window_start += 1;
+            }
+
+            let Some(elapsed) = frames[0].pts.map(|start| window_end - start) else {
+                continue;
+            };
+
+            let window_span = frames[window_start]
+                .pts
+                .map_or(0.0, |start_pts| window_end - start_pts);
+            let bitrate = window_bits as f64 / elapsed.min(window).max(window_span);
+            frames[i].bitrate = Some(bitrate);
+
+            // Only full windows count for the peak
+            if elapsed >= window {
+                self.peak_bitrate = Some(self.peak_bitrate.map_or(bitrate, |p| p.max(bitrate)));
+            }
+        }
+
+        // Stream shorter than the window
+        if self.peak_bitrate.is_none() {
+            self.peak_bitrate = self.average_bitrate;
+        }
+    }
+}
+
+impl FrameStats {
+    pub fn new(frame: &Frame) -> Self {
+        let mut stats = FrameStats {
+            decoded_number: frame.decoded_number,
+            presentation_number: frame.presentation_number,
+            frame_type: frame.frame_type,
+            key_frame: frame.is_random_access_point(),
+            poc: frame.first_slice.pic_order_cnt_val,
+            temporal_id: first_slice_nal(frame).map_or(0, |nal| nal.temporal_id),
+            pts: frame
+                .timestamp
+                .map(|ts| ts.pts as f64 / ts.time_scale as f64),
+            duration: frame
+                .timestamp
+                .map(|ts| ts.duration as f64 / ts.time_scale as f64),
+            ..Default::default()
+        };
+
+        for nal in &frame.nals {
+            let size = (nal.end - nal.start) as u64;
+
+            match nal.nal_type {
+                NAL_VPS | NAL_SPS | NAL_PPS => stats.parameter_set_size += size,
+                NAL_SEI_PREFIX | NAL_SEI_SUFFIX => stats.sei_size += size,
+                _ if nal.is_slice() => stats.slice_size += size,
+                _ => stats.other_size += size,
+            }
+
+            stats.other_size += nal.start_code.size() as u64;
+        }
+
+        stats.layer_size = frame
+            .layer_nals
+            .iter()
+            .map(|nal| (nal.end - nal.start + nal.start_code.size()) as u64)
+            .sum();
+
+        stats
+    }
+
+    /// Total coded size in bytes, all layers included
+    pub fn size(&self) -> u64 {
+        self.base_layer_size() + self.layer_size
+    }
+
+    /// Coded size of the base layer in bytes
+    pub fn base_layer_size(&self) -> u64 {
+        self.slice_size + self.sei_size + self.parameter_set_size + self.other_size
+    }
+
+    /// Size of everything but the base layer slices, in bytes
+    pub fn overhead(&self) -> u64 {
+        self.size() - self.slice_size
+    }
+}
+
+fn first_slice_nal(frame: &Frame) -> Option<&NALUnit> {
+    frame.nals.iter().find(|nal| nal.is_slice())
+}
+
+fn frame_type_char(frame_type: u64) -> &'static str {
+    match frame_type {
+        SLICE_TYPE_I => "I",
+        SLICE_TYPE_P => "P",
+        SLICE_TYPE_B => "B",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hevc::NALUnit;
+    use crate::hevc::timing::FrameTimestamp;
+
+    /// Slice of 1000 bytes, 1003 with the start code
+    fn frame(pts: i64, duration: u64) -> Frame {
+        Frame {
+            nals: vec![NALUnit {
+                nal_type: 1,
+                end: 1000,
+                ..Default::default()
+            }],
+            timestamp: Some(FrameTimestamp {
+                pts,
+                dts: pts,
+                duration,
+                time_scale: 1,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn bitrate_of_frames_longer_than_the_window() {
+        // 0.5 fps
+        let frames = [frame(0, 2), frame(2, 2), frame(4, 2)];
+        let stats = StreamStats::new(&frames, &StatsOptions::default());
+
+        assert!(stats.frames.iter().all(|f| f.bitrate == Some(4012.0)));
+        assert_eq!(stats.peak_bitrate, Some(4012.0));
+        assert_eq!(stats.average_bitrate, Some(4012.0));
+    }
+
+    #[test]
+    fn layer_nal_sizes() {
+        let mut frame = frame(0, 1);
+        frame.layer_nals.push(NALUnit {
+            nal_type: 1,
+            nuh_layer_id: 1,
+            end: 500,
+            ..Default::default()
+        });
+
+        let stats = FrameStats::new(&frame);
+        assert_eq!(stats.layer_size, 503);
+        assert_eq!(stats.base_layer_size(), 1003);
+        assert_eq!(stats.size(), 1506);
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use anyhow::Result;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use super::{first_slice_nal, frame_type_char};
+use crate::hevc::{Frame, SliceInfo};
+
+/// Slice QP and encoder decisions, from the slice headers only
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct QpReport {
+    /// Per frame QP, in presentation order
+    pub frames: Vec<FrameQp>,
+
+    /// Averages for each frame type, I/P/B as `SLICE_TYPE_*`
+    pub by_frame_type: BTreeMap<u64, QpSummary>,
+    /// Averages for each temporal ID
+    pub by_temporal_layer: BTreeMap<u8, QpSummary>,
+    pub overall: QpSummary,
+
+    pub slice_count: u64,
+    pub weighted_prediction_slices: u64,
+    pub deblocking_disabled_slices: u64,
+    pub sao_luma_slices: u64,
+    pub sao_chroma_slices: u64,
+    /// Number of P/B slices for each `MaxNumMergeCand`
+    pub max_merge_candidates: BTreeMap<u64, u64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct FrameQp {
+    pub decoded_number: u64,
+    pub presentation_number: u64,
+    pub frame_type: u64,
+    pub temporal_id: u8,
+
+    /// Mean of the slice QPs
+    pub average_qp: f64,
+    pub min_qp: i64,
+    pub max_qp: i64,
+
+    /// Independent slices only, dependent slice segments reuse their header
+    pub slices: Vec<SliceInfo>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct QpSummary {
+    pub frame_count: u64,
+    /// Mean of the frame average QPs
+    pub average_qp: f64,
+    pub min_qp: i64,
+    pub max_qp: i64,
+}
+
+impl QpReport {
+    /// Builds the report from frames in presentation order, usually `HevcParser::ordered_frames`
+    pub fn new(frames: &[Frame]) -> Self {
+        let mut report = QpReport::default();
+
+        for frame in frames.iter().filter(|f| !f.slices.is_empty()) {
+            let frame_qp = FrameQp::new(frame);
+
+            report
+                .by_frame_type
+                .entry(frame_qp.frame_type)
+                .or_default()
+                .add(&frame_qp);
+            report
+                .by_temporal_layer
+                .entry(frame_qp.temporal_id)
+                .or_default()
+                .add(&frame_qp);
+            report.overall.add(&frame_qp);
+
+            for slice in &frame_qp.slices {
+                report.slice_count += 1;
+
+                report.weighted_prediction_slices += slice.weighted_prediction as u64;
+                report.deblocking_disabled_slices += slice.deblocking_filter_disabled as u64;
+                report.sao_luma_slices += slice.sao_luma as u64;
+                report.sao_chroma_slices += slice.sao_chroma as u64;
+
+                if slice.max_num_merge_cand > 0 {
+                    *report
+                        .max_merge_candidates
+                        .entry(slice.max_num_merge_cand)
+                        .or_default() += 1;
+                }
+            }
+
+            report.frames.push(frame_qp);
+        }
+
+        report.frames.sort_by_key(|f| f.presentation_number);
+
+        report
+    }
+
+    /// Writes one line per frame, in presentation order
+    pub fn write_csv(&self, writer: &mut dyn Write) -> Result<()> {
+        writeln!(
+            writer,
+            "decoded_number,presentation_number,frame_type,temporal_id,slices,average_qp,min_qp,max_qp,weighted_prediction,deblocking_disabled,sao_luma,sao_chroma"
+        )?;
+
+        for f in &self.frames {
+            let any = |flag: fn(&SliceInfo) -> bool| f.slices.iter().any(flag);
+
+            writeln!(
+                writer,
+                "{},{},{},{},{},{:.2},{},{},{},{},{},{}",
+                f.decoded_number,
+                f.presentation_number,
+                frame_type_char(f.frame_type),
+                f.temporal_id,
+                f.slices.len(),
+                f.average_qp,
+                f.min_qp,
+                f.max_qp,
+                any(|s| s.weighted_prediction),
+                any(|s| s.deblocking_filter_disabled),
+                any(|s| s.sao_luma),
+                any(|s| s.sao_chroma),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn write_json(&self, writer: &mut dyn Write) -> Result<()> {
+        serde_json::to_writer_pretty(writer, self)?;
+
+        Ok(())
+    }
+}
+
+impl FrameQp {
+    pub fn new(frame: &Frame) -> Self {
+        let slices: Vec<SliceInfo> = frame
+            .slices
+            .iter()
+            .filter(|s| !s.dependent)
+            .copied()
+            .collect();
+
+        let qps = slices.iter().map(|s| s.qp);
+        let count = slices.len().max(1);
+
+        Self {
+            decoded_number: frame.decoded_number,
+            presentation_number: frame.presentation_number,
+            frame_type: frame.frame_type,
+            temporal_id: first_slice_nal(frame).map_or(0, |nal| nal.temporal_id),
+            average_qp: qps.clone().sum::<i64>() as f64 / count as f64,
+            min_qp: qps.clone().min().unwrap_or_default(),
+            max_qp: qps.max().unwrap_or_default(),
+            slices,
+        }
+    }
+}
+
+impl QpSummary {
+    fn add(&mut self, frame: &FrameQp) {
+        if self.frame_count == 0 {
+            self.min_qp = frame.min_qp;
+            self.max_qp = frame.max_qp;
+        } else {
+            self.min_qp = self.min_qp.min(frame.min_qp);
+            self.max_qp = self.max_qp.max(frame.max_qp);
+        }
+
+        let total = self.average_qp * self.frame_count as f64 + frame.average_qp;
+
+        self.frame_count += 1;
+        self.average_qp = total / self.frame_count as f64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hevc::{SLICE_TYPE_B, SLICE_TYPE_P};
+
+    fn slice(qp: i64, dependent: bool) -> SliceInfo {
+        SliceInfo {
+            slice_type: SLICE_TYPE_P,
+            dependent,
+            qp,
+            sao_luma: true,
+            max_num_merge_cand: 5,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn dependent_slice_segments() {
+        let frames = [
+            Frame {
+                frame_type: SLICE_TYPE_P,
+                slices: vec![
+                    slice(30, false),
+                    slice(30, true),
+                    slice(30, true),
+                    slice(34, false),
+                ],
+                ..Default::default()
+            },
+            Frame {
+                decoded_number: 1,
+                presentation_number: 1,
+                frame_type: SLICE_TYPE_B,
+                slices: vec![slice(36, false), slice(36, true)],
+                ..Default::default()
+            },
+        ];
+
+        let report = QpReport::new(&frames);
+
+        assert_eq!(report.frames[0].slices.len(), 2);
+        assert_eq!(report.frames[0].average_qp, 32.0);
+        assert_eq!((report.frames[0].min_qp, report.frames[0].max_qp), (30, 34));
+
+        assert_eq!(report.slice_count, 3);
+        assert_eq!(report.sao_luma_slices, 3);
+        assert_eq!(report.max_merge_candidates.get(&5), Some(&3));
+
+        assert_eq!(report.overall.frame_count, 2);
+        assert_eq!(report.overall.average_qp, 34.0);
+        assert_eq!(report.by_frame_type[&SLICE_TYPE_B].average_qp, 36.0);
+    }
+}
@@ -0,0 +1,245 @@
+use super::Frame;
+use super::sei::AlphaChannelInfo;
+use super::sei::a53::BarSizes;
+use super::sei::frame_packing::{Stereo3d, frames_frame_packing};
+use super::sei::omnidirectional::{SphericalVideo, frames_spherical_video};
+use super::sei::{PicTiming, SeiPayload};
+use super::sps::SPSNAL;
+use super::timing::{FrameRate, TimingInfo};
+use super::vps::VPSNAL;
+
+/// Summary of the active parameter sets of a stream
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub struct StreamInfo {
+    /// Coded picture size, in luma samples
+    pub width: u64,
+    pub height: u64,
+
+    /// Conformance window, in luma samples
+    pub conformance_window: ConformanceWindow,
+
+    pub chroma_format_idc: u64,
+    pub bit_depth_luma: u64,
+    pub bit_depth_chroma: u64,
+
+    pub colour_primaries: u8,
+    pub transfer_characteristics: u8,
+    /// From the alternative transfer characteristics SEI, otherwise `transfer_characteristics`
+    pub preferred_transfer_characteristics: u8,
+    pub matrix_coefficients: u8,
+    pub full_range: bool,
+
+    /// Frame rate, fields are counted as half frames
+    pub frame_rate: Option<FrameRate>,
+
+    pub coding: PictureCoding,
+    pub field_order: FieldOrder,
+
+    /// From the frame packing arrangement SEI, `None` for 2D content
+    pub stereo: Option<Stereo3d>,
+    /// From the omnidirectional projection SEIs, `None` for regular video
+    pub spherical: Option<SphericalVideo>,
+
+    /// The VPS signals layers above the base layer, such as an alpha auxiliary layer
+    pub multi_layer: bool,
+    /// From the alpha channel info SEI
+    pub alpha_channel: Option<AlphaChannelInfo>,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct ConformanceWindow {
+    pub left: u64,
+    pub right: u64,
+    pub top: u64,
+    pub bottom: u64,
+}
+
+/// Active video area of a frame, as signalled by the stream
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct ActiveArea {
+    /// From the AFD SEI
+    pub active_format: Option<u8>,
+    /// From the bar data SEI, relative to the decoded picture
+    pub bars: Option<BarSizes>,
+    pub conformance_window: ConformanceWindow,
+}
+
+/// Whether each coded picture is a frame or a single field
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub enum PictureCoding {
+    #[default]
+    Frame,
+    Field,
+}
+
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub enum FieldOrder {
+    #[default]
+    Progressive,
+    TopFieldFirst,
+    BottomFieldFirst,
+    /// Interlaced, but the order is not signalled
+    Unknown,
+}
+
+impl StreamInfo {
+    /// `pic_timing` is the first pic_timing SEI of the stream, if any.
+    /// The stereo, spherical, alpha and alternative transfer signalling
+    /// is the first effective one of `frames`, in presentation order.
+    pub(crate) fn new(
+        sps: &SPSNAL,
+        vps: Option<&VPSNAL>,
+        pic_timing: Option<&PicTiming>,
+        frames: &[Frame],
+    ) -> Self {
+        let vui = &sps.vui_parameters;
+
+        let (colour_primaries, transfer_characteristics, matrix_coefficients) =
+            if sps.vui_present && vui.colour_description_present_flag {
+                (
+                    vui.colour_primaries,
+                    vui.transfer_characteristic,
+                    vui.matrix_coeffs,
+                )
+            } else {
+                // Unspecified
+                (2, 2, 2)
+            };
+
+        let (sub_width_c, sub_height_c) = match sps.chroma_format_idc {
+            1 => (2, 2),
+            2 => (2, 1),
+            _ => (1, 1),
+        };
+
+        let field_seq = sps.vui_present && vui.field_seq_flag;
+
+        let coding = if field_seq || pic_timing.is_some_and(|pt| pt.is_field()) {
+            PictureCoding::Field
+        } else {
+            PictureCoding::Frame
+        };
+
+        let field_order = match (pic_timing.and_then(|pt| pt.field_order()), coding) {
+            (Some(order), _) => order,
+            (None, PictureCoding::Field) => FieldOrder::Unknown,
+            (None, PictureCoding::Frame) => {
+                if pic_timing.is_some_and(|pt| pt.source_scan_type == 0) {
+                    FieldOrder::Unknown
+                } else {
+                    FieldOrder::Progressive
+                }
+            }
+        };
+
+        let stereo = frames_frame_packing(frames)
+            .into_iter()
+            .flatten()
+            .find_map(|fpa| fpa.stereo_3d());
+        let spherical = frames_spherical_video(frames).into_iter().flatten().next();
+        let alpha_channel = frames
+            .iter()
+            .filter_map(|f| f.alpha_channel_info())
+            .find(|info| !info.alpha_channel_cancel_flag)
+            .copied();
+        let alternative_transfer = frames.iter().find_map(|f| {
+            f.prefix_sei.iter().find_map(|sei| match sei {
+                SeiPayload::AlternativeTransferCharacteristics(alt) => Some(alt),
+                _ => None,
+            })
+        });
+
+        let frame_rate = TimingInfo::from_parameter_sets(sps, vps)
+            .map(|timing| timing.frame_rate())
+            .map(|fr| match coding {
+                PictureCoding::Frame => fr,
+                PictureCoding::Field => FrameRate::new(fr.num, fr.den * 2).reduced(),
+            });
+
+        Self {
+            width: sps.width,
+            height: sps.height,
+            conformance_window: ConformanceWindow {
+                left: sps.conf_win_left_offset * sub_width_c,
+                right: sps.conf_win_right_offset * sub_width_c,
+                top: sps.conf_win_top_offset * sub_height_c,
+                bottom: sps.conf_win_bottom_offset * sub_height_c,
+            },
+            chroma_format_idc: sps.chroma_format_idc,
+            bit_depth_luma: sps.bit_depth,
+            bit_depth_chroma: sps.bit_depth_chroma,
+            colour_primaries,
+            transfer_characteristics,
+            preferred_transfer_characteristics: alternative_transfer
+                .map_or(transfer_characteristics, |alt| {
+                    alt.preferred_transfer_characteristics
+                }),
+            matrix_coefficients,
+            full_range: sps.vui_present
+                && vui.video_signal_type_present_flag
+                && vui.video_full_range_flag,
+            frame_rate,
+            coding,
+            field_order,
+            stereo,
+            spherical,
+            multi_layer: vps.is_some_and(|vps| vps.vps_max_layers > 1),
+            alpha_channel,
+        }
+    }
+
+    /// Picture size after cropping to the conformance window
+    pub fn cropped_width(&self) -> u64 {
+        self.width
+            .saturating_sub(self.conformance_window.left + self.conformance_window.right)
+    }
+
+    pub fn cropped_height(&self) -> u64 {
+        self.height
+            .saturating_sub(self.conformance_window.top + self.conformance_window.bottom)
+    }
+
+    pub fn is_interlaced(&self) -> bool {
+        self.field_order != FieldOrder::Progressive
+    }
+
+    /// Active area signalled for a frame, by the AFD and bar data SEIs
+    pub fn active_area(&self, frame: &Frame) -> ActiveArea {
+        let mut area = ActiveArea {
+            conformance_window: self.conformance_window,
+            ..Default::default()
+        };
+
+        for sei in &frame.prefix_sei {
+            match sei {
+                SeiPayload::AfdData(afd) => area.active_format = afd.active_format,
+                SeiPayload::BarData(bar_data) => {
+                    area.bars = Some(bar_data.bar_sizes(self.width, self.height))
+                }
+                _ => (),
+            }
+        }
+
+        area
+    }
+}
+
+impl ActiveArea {
+    /// Bars remaining after cropping to the conformance window
+    pub fn cropped_bars(&self) -> Option<BarSizes> {
+        let window = &self.conformance_window;
+
+        self.bars.map(|bars| BarSizes {
+            top: bars.top.saturating_sub(window.top),
+            bottom: bars.bottom.saturating_sub(window.bottom),
+            left: bars.left.saturating_sub(window.left),
+            right: bars.right.saturating_sub(window.right),
+        })
+    }
+
+    /// Whether the picture has letterboxing or pillarboxing signalled in metadata
+    pub fn has_bars(&self) -> bool {
+        self.cropped_bars()
+            .is_some_and(|bars| bars != BarSizes::default())
+    }
+}
@@ -0,0 +1,415 @@
+use std::io::Write;
+
+use anyhow::Result;
+
+use super::Frame;
+use super::sps::SPSNAL;
+use super::vps::VPSNAL;
+
+/// Frame rate in frames per second, as a fraction
+#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+pub struct FrameRate {
+    pub num: u32,
+    pub den: u32,
+}
+
+/// Timestamps of a frame, in `time_scale` units per second
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub struct FrameTimestamp {
+    pub pts: i64,
+    pub dts: i64,
+    pub duration: u64,
+    pub time_scale: u32,
+}
+
+/// Clock used to synthesise the timestamps
+#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+pub(crate) struct TimingInfo {
+    pub(crate) num_units_in_tick: u32,
+    pub(crate) time_scale: u32,
+    /// Clock ticks for a POC difference of one
+    pub(crate) num_ticks_poc_diff_one: Option<u64>,
+    /// Clock ticks of a frame, when the HRD signals a fixed picture rate
+    pub(crate) fixed_frame_ticks: Option<u64>,
+}
+
+/// Smallest POC step between consecutive frames in presentation order,
+/// tracked across the reorder batches
+#[derive(Default, Debug, PartialEq, Clone, Copy, Eq)]
+pub(crate) struct PocStep {
+    step: Option<i64>,
+    /// POC of the last frame of the previous batch
+    last_poc: Option<i32>,
+}
+
+impl FrameRate {
+    pub fn new(num: u32, den: u32) -> Self {
+        Self { num, den }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    /// Reduces the fraction, e.g. 48000/2002 to 24000/1001
+    pub fn reduced(&self) -> Self {
+        let mut a = self.num;
+        let mut b = self.den;
+
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+
+        let gcd = a.max(1);
+
+        Self {
+            num: self.num / gcd,
+            den: self.den / gcd,
+        }
+    }
+}
+
+impl std::fmt::Display for FrameRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.num, self.den)
+    }
+}
+
+impl FrameTimestamp {
+    pub fn pts_ms(&self) -> f64 {
+        self.pts as f64 * 1000.0 / self.time_scale as f64
+    }
+
+    pub fn dts_ms(&self) -> f64 {
+        self.dts as f64 * 1000.0 / self.time_scale as f64
+    }
+
+    pub fn duration_ms(&self) -> f64 {
+        self.duration as f64 * 1000.0 / self.time_scale as f64
+    }
+}
+
+impl PocStep {
+    /// Updates the step with a batch of frames sorted in presentation order.
+    /// The POC difference with the previous batch counts, unless the POC was reset.
+    pub(crate) fn update(&mut self, frames: &[Frame]) {
+        let Some(last) = frames.last() else {
+            return;
+        };
+
+        let previous = self
+            .last_poc
+            .filter(|_| frames.first().is_some_and(|f| !f.starts_clvs()));
+        let pocs: Vec<i32> = previous
+            .into_iter()
+            .chain(frames.iter().map(|f| f.first_slice.pic_order_cnt_val))
+            .collect();
+
+        let batch_step = pocs
+            .windows(2)
+            .map(|w| w[1] as i64 - w[0] as i64)
+            .filter(|diff| *diff > 0)
+            .min();
+
+        if let Some(batch_step) = batch_step {
+            self.step = Some(self.step.map_or(batch_step, |step| step.min(batch_step)));
+        }
+
+        self.last_poc = Some(last.first_slice.pic_order_cnt_val);
+    }
+
+    /// Defaults to 1 until two consecutive frames were seen
+    pub(crate) fn get(&self) -> i64 {
+        self.step.unwrap_or(1)
+    }
+}
+
+impl TimingInfo {
+    /// Timing from the SPS VUI, falling back to the VPS
+    pub(crate) fn from_parameter_sets(sps: &SPSNAL, vps: Option<&VPSNAL>) -> Option<Self> {
+        let vui = &sps.vui_parameters;
+
+        if sps.vui_present && vui.vui_timing_info_present_flag {
+            let fixed_frame_ticks = sps
+                .hrd_parameters()
+                .and_then(|hrd| hrd.sub_layers.last())
+                .filter(|sub_layer| sub_layer.fixed_pic_rate_within_cvs_flag)
+                .map(|sub_layer| sub_layer.elemental_duration_in_tc_minus1 + 1);
+
+            return Self::new(
+                vui.vui_num_units_in_tick,
+                vui.vui_time_scale,
+                vui.vui_poc_proportional_to_timing_flag
+                    .then_some(vui.vui_num_ticks_poc_diff_one_minus1 + 1),
+            )
+            .map(|timing| Self {
+                fixed_frame_ticks,
+                ..timing
+            });
+        }
+
+        vps.filter(|vps| vps.vps_timing_info_present_flag)
+            .and_then(|vps| {
+                Self::new(
+                    vps.vps_num_units_in_tick,
+                    vps.vps_time_scale,
+                    vps.vps_poc_proportional_to_timing_flag
+                        .then_some(vps.vps_num_ticks_poc_diff_one),
+                )
+            })
+    }
+
+    pub(crate) fn from_frame_rate(frame_rate: FrameRate) -> Self {
+        Self {
+            num_units_in_tick: frame_rate.den,
+            time_scale: frame_rate.num,
+            num_ticks_poc_diff_one: None,
+            fixed_frame_ticks: None,
+        }
+    }
+
+    fn new(
+        num_units_in_tick: u32,
+        time_scale: u32,
+        num_ticks_poc_diff_one: Option<u64>,
+    ) -> Option<Self> {
+        (num_units_in_tick > 0 && time_scale > 0).then_some(Self {
+            num_units_in_tick,
+            time_scale,
+            num_ticks_poc_diff_one,
+            fixed_frame_ticks: None,
+        })
+    }
+
+    /// Picture rate of the clock
+    pub(crate) fn frame_rate(&self) -> FrameRate {
+        FrameRate::new(self.time_scale, self.num_units_in_tick).reduced()
+    }
+
+    /// Sets the timestamps of the frames, which must be sorted in presentation order.
+    /// `poc_step` is the POC difference between consecutive frames, from `PocStep`.
+    /// It is only used for the frame duration without a fixed picture rate.
+    /// `next_pts` is the PTS of the first frame, and is updated for the next call.
+    ///
+    /// The n-th decoded frame gets the n-th smallest PTS as DTS, offset by the reorder delay.
+    /// The DTS never exceed the PTS.
+    pub(crate) fn assign_timestamps(
+        &self,
+        frames: &mut [Frame],
+        reorder_delay: u64,
+        poc_step: i64,
+        next_pts: &mut i64,
+    ) {
+        let Some(first_poc) = frames.first().map(|f| f.first_slice.pic_order_cnt_val) else {
+            return;
+        };
+
+        let tick = self.num_units_in_tick as i64;
+
+        let (poc_duration, duration) = match self.num_ticks_poc_diff_one {
+            Some(ticks) => {
+                let poc_duration = ticks as i64 * tick;
+                let duration = match self.fixed_frame_ticks {
+                    Some(frame_ticks) => frame_ticks as i64 * tick,
+                    None => poc_step * poc_duration,
+                };
+
+                (Some(poc_duration), duration)
+            }
+            None => (None, tick),
+        };
+
+        let start_pts = *next_pts;
+
+        let pts_values: Vec<i64> = frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                if let Some(poc_duration) = poc_duration {
+                    let poc_diff = (frame.first_slice.pic_order_cnt_val - first_poc) as i64;
+                    start_pts + poc_diff * poc_duration
+                } else {
+                    start_pts + i as i64 * duration
+                }
+            })
+            .collect();
+
+        let mut sorted_pts = pts_values.clone();
+        sorted_pts.sort_unstable();
+
+        let mut decoding_order: Vec<usize> = (0..frames.len()).collect();
+        decoding_order.sort_by_key(|&i| frames[i].decoded_number);
+
+        let delay = reorder_delay as i64 * duration;
+        let mut dts_values = vec![0; frames.len()];
+
+        for (&i, pts) in decoding_order.iter().zip(sorted_pts) {
+            dts_values[i] = (pts - delay).min(pts_values[i]);
+        }
+
+        for ((frame, pts), dts) in frames.iter_mut().zip(pts_values).zip(dts_values) {
+            frame.timestamp = Some(FrameTimestamp {
+                pts,
+                dts,
+                duration: duration as u64,
+                time_scale: self.time_scale,
+            });
+
+            *next_pts = pts + duration;
+        }
+    }
+}
+
+/// Writes a mkvmerge v2 timestamps file, in presentation order.
+/// Frames without timestamps are skipped.
+pub fn write_timestamps_v2(frames: &[Frame], writer: &mut dyn Write) -> Result<()> {
+    let mut timestamps: Vec<_> = frames.iter().filter_map(|f| f.timestamp).collect();
+    timestamps.sort_by_key(|ts| ts.pts);
+
+    write_timestamps(2, &timestamps, writer)
+}
+
+/// Writes a mkvmerge v4 timestamps file, in decoding order.
+/// Frames without timestamps are skipped.
+pub fn write_timestamps_v4(frames: &[Frame], writer: &mut dyn Write) -> Result<()> {
+    let mut frames: Vec<_> = frames.iter().filter(|f| f.timestamp.is_some()).collect();
+    frames.sort_by_key(|f| f.decoded_number);
+
+    let timestamps: Vec<_> = frames.iter().filter_map(|f| f.timestamp).collect();
+
+    write_timestamps(4, &timestamps, writer)
+}
+
+fn write_timestamps(
+    version: u8,
+    timestamps: &[FrameTimestamp],
+    writer: &mut dyn Write,
+) -> Result<()> {
+    writeln!(writer, "# timestamp format v{version}")?;
+
+    for ts in timestamps {
+        writeln!(writer, "{:.3}", ts.pts_ms())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(decoded_number: u64, poc: i32) -> Frame {
+        let mut frame = Frame {
+            decoded_number,
+            ..Default::default()
+        };
+        frame.first_slice.pic_order_cnt_val = poc;
+
+        frame
+    }
+
+    fn timestamps(frames: &[Frame]) -> Vec<(i64, i64)> {
+        frames
+            .iter()
+            .map(|f| f.timestamp.map(|ts| (ts.pts, ts.dts)).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn dts_with_reorder_delay() {
+        let timing = TimingInfo::from_frame_rate(FrameRate::new(25, 1));
+        let mut next_pts = 0;
+
+        // I0 P4 B2 b1 b3, in presentation order
+        let mut frames = vec![
+            frame(0, 0),
+            frame(3, 1),
+            frame(2, 2),
+            frame(4, 3),
+            frame(1, 4),
+        ];
+        timing.assign_timestamps(&mut frames, 2, 1, &mut next_pts);
+
+        assert_eq!(
+            timestamps(&frames),
+            [(0, -2), (1, 1), (2, 0), (3, 2), (4, -1)]
+        );
+        assert_eq!(next_pts, 5);
+
+        // The next batch starts from the previous PTS
+        let mut frames = vec![frame(5, 0), frame(7, 1), frame(6, 2)];
+        timing.assign_timestamps(&mut frames, 2, 1, &mut next_pts);
+
+        assert_eq!(timestamps(&frames), [(5, 3), (6, 5), (7, 4)]);
+    }
+
+    #[test]
+    fn dts_with_poc_step() {
+        let timing = TimingInfo {
+            num_units_in_tick: 1,
+            time_scale: 50,
+            num_ticks_poc_diff_one: Some(1),
+            fixed_frame_ticks: None,
+        };
+        let mut next_pts = 100;
+
+        // POC step of 2, one B frame reordered
+        let mut frames = vec![frame(0, 0), frame(2, 2), frame(1, 4)];
+        let mut poc_step = PocStep::default();
+        poc_step.update(&frames);
+        timing.assign_timestamps(&mut frames, 1, poc_step.get(), &mut next_pts);
+
+        assert_eq!(timestamps(&frames), [(100, 98), (102, 102), (104, 100)]);
+        assert!(frames.iter().all(|f| {
+            let ts = f.timestamp.unwrap();
+            ts.dts <= ts.pts && ts.duration == 2
+        }));
+        assert_eq!(next_pts, 106);
+    }
+
+    #[test]
+    fn single_frame_batches_with_poc_step() {
+        let timing = TimingInfo {
+            num_units_in_tick: 1,
+            time_scale: 50,
+            num_ticks_poc_diff_one: Some(1),
+            fixed_frame_ticks: None,
+        };
+        let mut next_pts = 0;
+        let mut poc_step = PocStep::default();
+
+        // Intra frames with a POC step of 2, each flushed on its own
+        let mut pts = Vec::new();
+        for (i, poc) in [0, 2, 4].into_iter().enumerate() {
+            let mut frames = vec![frame(i as u64, poc)];
+
+            poc_step.update(&frames);
+            timing.assign_timestamps(&mut frames, 0, poc_step.get(), &mut next_pts);
+
+            pts.push(frames[0].timestamp.unwrap());
+        }
+
+        // The step is only known from the second frame
+        assert_eq!(pts[0].duration, 1);
+        assert!(pts[1..].iter().all(|ts| ts.duration == 2));
+        assert_eq!(pts.iter().map(|ts| ts.pts).collect::<Vec<_>>(), [0, 1, 3]);
+        assert_eq!(poc_step.get(), 2);
+    }
+
+    #[test]
+    fn fixed_picture_rate_duration() {
+        let timing = TimingInfo {
+            num_units_in_tick: 1,
+            time_scale: 50,
+            num_ticks_poc_diff_one: Some(1),
+            fixed_frame_ticks: Some(2),
+        };
+        let mut next_pts = 0;
+
+        let mut frames = vec![frame(0, 0)];
+        timing.assign_timestamps(&mut frames, 0, 1, &mut next_pts);
+
+        assert_eq!(frames[0].timestamp.unwrap().duration, 2);
+        assert_eq!(next_pts, 2);
+    }
+}
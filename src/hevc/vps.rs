@@ -8,7 +8,7 @@ use super::profile_tier_level::ProfileTierLevel;
 #[derive(Default, Debug, PartialEq, Eq)]
 pub struct VPSNAL {
     pub(crate) vps_id: u8,
-    vps_max_layers: u8,
+    pub(crate) vps_max_layers: u8,
     vps_max_sub_layers: u8,
     vps_temporal_id_nesting_flag: bool,
     ptl: ProfileTierLevel,
@@ -18,12 +18,15 @@ pub struct VPSNAL {
     vps_max_latency_increase: Vec<u64>,
     vps_max_layer_id: u8,
     vps_num_layer_sets: u64,
-    vps_timing_info_present_flag: bool,
-    vps_num_units_in_tick: u32,
-    vps_time_scale: u32,
-    vps_poc_proportional_to_timing_flag: bool,
-    vps_num_ticks_poc_diff_one: u64,
+    pub(crate) vps_timing_info_present_flag: bool,
+    pub(crate) vps_num_units_in_tick: u32,
+    pub(crate) vps_time_scale: u32,
+    pub(crate) vps_poc_proportional_to_timing_flag: bool,
+    pub(crate) vps_num_ticks_poc_diff_one: u64,
     vps_num_hrd_parameters: u64,
+    pub(crate) hrd_parameters: Vec<HrdParameters>,
+    /// Layer set of each `hrd_parameters`
+    hrd_layer_set_idx: Vec<u64>,
 }
 
 impl VPSNAL {
@@ -86,14 +89,16 @@ impl VPSNAL {
             vps.vps_num_hrd_parameters = bs.read_ue()?;
 
             for i in 0..vps.vps_num_hrd_parameters {
-                let mut common_inf_present = false;
-                bs.read_ue()?; // hrd_layer_set_idx
+                // cprms_present_flag, inferred for the first parameters
+                let mut common_inf_present = true;
+                vps.hrd_layer_set_idx.push(bs.read_ue()?);
 
                 if i > 0 {
                     common_inf_present = bs.read_bit()?;
                 }
 
-                HrdParameters::parse(bs, common_inf_present, vps.vps_max_sub_layers)?;
+                let hrd = HrdParameters::parse(bs, common_inf_present, vps.vps_max_sub_layers)?;
+                vps.hrd_parameters.push(hrd);
             }
         }
 
@@ -101,4 +106,12 @@ impl VPSNAL {
 
         Ok(vps)
     }
+
+    /// HRD parameters of the operation point containing only the base layer
+    pub(crate) fn base_layer_hrd_parameters(&self) -> Option<&HrdParameters> {
+        self.hrd_layer_set_idx
+            .iter()
+            .position(|&idx| idx == 0)
+            .and_then(|i| self.hrd_parameters.get(i))
+    }
 }
@@ -11,21 +11,21 @@ pub struct VuiParameters {
     sar_den: u16,
     overscan_info_present_flag: bool,
     overscan_appropriate_flag: bool,
-    video_signal_type_present_flag: bool,
+    pub(crate) video_signal_type_present_flag: bool,
 
     video_format: u8,
-    video_full_range_flag: bool,
-    colour_description_present_flag: bool,
-    colour_primaries: u8,
-    transfer_characteristic: u8,
-    matrix_coeffs: u8,
+    pub(crate) video_full_range_flag: bool,
+    pub(crate) colour_description_present_flag: bool,
+    pub(crate) colour_primaries: u8,
+    pub(crate) transfer_characteristic: u8,
+    pub(crate) matrix_coeffs: u8,
 
     chroma_loc_info_present_flag: bool,
     chroma_sample_loc_type_top_field: u64,
     chroma_sample_loc_type_bottom_field: u64,
     neutral_chroma_indication_flag: bool,
-    field_seq_flag: bool,
-    frame_field_info_present_flag: bool,
+    pub(crate) field_seq_flag: bool,
+    pub(crate) frame_field_info_present_flag: bool,
 
     default_display_window_flag: bool,
     def_disp_win_left_offset: u64,
@@ -33,12 +33,13 @@ pub struct VuiParameters {
     def_disp_win_top_offset: u64,
     def_disp_win_bottom_offset: u64,
 
-    vui_timing_info_present_flag: bool,
-    vui_num_units_in_tick: u32,
-    vui_time_scale: u32,
-    vui_poc_proportional_to_timing_flag: bool,
-    vui_num_ticks_poc_diff_one_minus1: u64,
+    pub(crate) vui_timing_info_present_flag: bool,
+    pub(crate) vui_num_units_in_tick: u32,
+    pub(crate) vui_time_scale: u32,
+    pub(crate) vui_poc_proportional_to_timing_flag: bool,
+    pub(crate) vui_num_ticks_poc_diff_one_minus1: u64,
     vui_hrd_parameters_present_flag: bool,
+    pub(crate) hrd_parameters: Option<HrdParameters>,
 
     bitstream_restriction_flag: bool,
     tiles_fixed_structure_flag: bool,
@@ -116,7 +117,7 @@ impl VuiParameters {
 
             vui.vui_hrd_parameters_present_flag = bs.read_bit()?;
             if vui.vui_hrd_parameters_present_flag {
-                HrdParameters::parse(bs, true, max_sub_layers)?;
+                vui.hrd_parameters = Some(HrdParameters::parse(bs, true, max_sub_layers)?);
             }
         }
 
@@ -0,0 +1,307 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+
+use super::{FrameBuffer, HevcParser, IoProcessor, NalBuffer, StartCodePreset};
+use crate::hevc::{NAL_UNSPEC62, NAL_UNSPEC63, NALUnit};
+
+/// NAL header of the units wrapping the enhancement layer NALs
+const NAL_UNSPEC63_HEADER: [u8; 2] = [NAL_UNSPEC63 << 1, 1];
+
+/// Splits a single track Dolby Vision dual layer stream into base layer and enhancement layer streams.
+/// The EL NALs are unwrapped from the `NAL_UNSPEC63` units, and the RPUs are written to the EL.
+pub struct DualLayerDemuxer {
+    input: PathBuf,
+
+    bl_writer: Box<dyn Write>,
+    el_writer: Box<dyn Write>,
+
+    bl_started: bool,
+    el_started: bool,
+}
+
+impl DualLayerDemuxer {
+    pub fn new(input: PathBuf, bl_writer: Box<dyn Write>, el_writer: Box<dyn Write>) -> Self {
+        Self {
+            input,
+            bl_writer,
+            el_writer,
+            bl_started: false,
+            el_started: false,
+        }
+    }
+
+    /// Writes a NAL to its layer, `chunk` being the data the NAL was split from
+    pub fn demux_nal(&mut self, nal: &NALUnit, chunk: &[u8]) -> Result<()> {
+        let data = &chunk[nal.start..nal.end];
+
+        match nal.nal_type {
+            NAL_UNSPEC63 => {
+                let Some(el_nal) = data
+                    .get(NAL_UNSPEC63_HEADER.len()..)
+                    .filter(|d| !d.is_empty())
+                else {
+                    bail!("Empty enhancement layer NAL");
+                };
+
+                NALUnit::write_with_preset(
+                    &mut self.el_writer,
+                    el_nal,
+                    StartCodePreset::AnnexB,
+                    el_nal[0] >> 1,
+                    !self.el_started,
+                )?;
+                self.el_started = true;
+            }
+            NAL_UNSPEC62 => {
+                NALUnit::write_with_preset(
+                    &mut self.el_writer,
+                    data,
+                    StartCodePreset::AnnexB,
+                    nal.nal_type,
+                    !self.el_started,
+                )?;
+                self.el_started = true;
+            }
+            _ => {
+                NALUnit::write_with_preset(
+                    &mut self.bl_writer,
+                    data,
+                    StartCodePreset::AnnexB,
+                    nal.nal_type,
+                    !self.bl_started,
+                )?;
+                self.bl_started = true;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl IoProcessor for DualLayerDemuxer {
+    fn input(&self) -> &PathBuf {
+        &self.input
+    }
+
+    fn update_progress(&mut self, _delta: u64) {}
+
+    fn process_nals(&mut self, _parser: &HevcParser, nals: &[NALUnit], chunk: &[u8]) -> Result<()> {
+        for nal in nals {
+            self.demux_nal(nal, chunk)?;
+        }
+
+        Ok(())
+    }
+
+    fn finalize(&mut self, _parser: &HevcParser) -> Result<()> {
+        self.bl_writer.flush()?;
+        self.el_writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Appends the NALs to the frames they belong to, by decoded frame index.
+/// A frame split across chunks is continued in the last buffered frame.
+///
+/// Requires the NALs to be parsed, for `NALUnit::decoded_frame_index`.
+pub fn buffer_frames(frames: &mut Vec<FrameBuffer>, nals: &[NALUnit], chunk: &[u8]) {
+    for nal in nals {
+        let nal_buffer = NalBuffer {
+            nal_type: nal.nal_type,
+            start_code: nal.start_code,
+            data: chunk[nal.start..nal.end].to_vec(),
+        };
+
+        match frames.last_mut() {
+            Some(frame) if frame.frame_number == nal.decoded_frame_index => {
+                frame.nals.push(nal_buffer)
+            }
+            _ => frames.push(FrameBuffer {
+                frame_number: nal.decoded_frame_index,
+                nals: vec![nal_buffer],
+            }),
+        }
+    }
+}
+
+/// Writes a single track Dolby Vision frame, from the frames of separate base and enhancement layers.
+/// The EL NALs are wrapped in `NAL_UNSPEC63` units after the BL ones, the RPU is written last.
+///
+/// `first_frame` is used for the start code of the first NAL.
+pub fn write_interleaved_frame(
+    writer: &mut dyn Write,
+    bl: &FrameBuffer,
+    el: &FrameBuffer,
+    first_frame: bool,
+) -> Result<()> {
+    if bl.frame_number != el.frame_number {
+        bail!(
+            "Mismatched frames, BL {} and EL {}",
+            bl.frame_number,
+            el.frame_number
+        );
+    }
+
+    for (i, nal) in bl.nals.iter().enumerate() {
+        NALUnit::write_with_preset(
+            writer,
+            &nal.data,
+            StartCodePreset::AnnexB,
+            nal.nal_type,
+            first_frame && i == 0,
+        )?;
+    }
+
+    let first_nal = first_frame && bl.nals.is_empty();
+
+    for (i, nal) in el
+        .nals
+        .iter()
+        .filter(|nal| nal.nal_type != NAL_UNSPEC62)
+        .enumerate()
+    {
+        let mut data = Vec::with_capacity(nal.data.len() + NAL_UNSPEC63_HEADER.len());
+        data.extend_from_slice(&NAL_UNSPEC63_HEADER);
+        data.extend_from_slice(&nal.data);
+
+        NALUnit::write_with_preset(
+            writer,
+            &data,
+            StartCodePreset::AnnexB,
+            NAL_UNSPEC63,
+            first_nal && i == 0,
+        )?;
+    }
+
+    for nal in el.nals.iter().filter(|nal| nal.nal_type == NAL_UNSPEC62) {
+        NALUnit::write_with_preset(
+            writer,
+            &nal.data,
+            StartCodePreset::AnnexB,
+            nal.nal_type,
+            false,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::NALUStartCode;
+    use crate::hevc::{NAL_IDR_W_RADL, NAL_PPS, NAL_SPS, NAL_TRAIL_R, NAL_VPS};
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn frame(frame_number: u64, nals: &[&[u8]]) -> FrameBuffer {
+        FrameBuffer {
+            frame_number,
+            nals: nals
+                .iter()
+                .map(|data| NalBuffer {
+                    nal_type: data[0] >> 1,
+                    start_code: NALUStartCode::Length3,
+                    data: data.to_vec(),
+                })
+                .collect(),
+        }
+    }
+
+    fn layer_stream(frames: &[FrameBuffer]) -> Vec<u8> {
+        let mut out = Vec::new();
+
+        for (i, nal) in frames.iter().flat_map(|f| &f.nals).enumerate() {
+            NALUnit::write_with_preset(
+                &mut out,
+                &nal.data,
+                StartCodePreset::AnnexB,
+                nal.nal_type,
+                i == 0,
+            )
+            .unwrap();
+        }
+
+        out
+    }
+
+    #[test]
+    fn interleave_demux_roundtrip() {
+        let bl = [
+            frame(
+                0,
+                &[
+                    &[NAL_VPS << 1, 1, 0x0C, 0x01],
+                    &[NAL_SPS << 1, 1, 0x01, 0x60],
+                    &[NAL_PPS << 1, 1, 0xC1, 0x72],
+                    &[NAL_IDR_W_RADL << 1, 1, 0xAF, 0x08, 0x3C],
+                ],
+            ),
+            frame(1, &[&[NAL_TRAIL_R << 1, 1, 0xD0, 0x91]]),
+        ];
+        let el = [
+            frame(
+                0,
+                &[
+                    &[NAL_VPS << 1, 1, 0x0C, 0x02],
+                    &[NAL_IDR_W_RADL << 1, 1, 0xAF, 0x21],
+                    &[NAL_UNSPEC62 << 1, 1, 0x19, 0x08, 0x09],
+                ],
+            ),
+            frame(
+                1,
+                &[
+                    &[NAL_TRAIL_R << 1, 1, 0xD0, 0x44],
+                    &[NAL_UNSPEC62 << 1, 1, 0x19, 0x08, 0x0A],
+                ],
+            ),
+        ];
+
+        let mut interleaved = Vec::new();
+        for (i, (bl, el)) in bl.iter().zip(&el).enumerate() {
+            write_interleaved_frame(&mut interleaved, bl, el, i == 0).unwrap();
+        }
+
+        let mut parser = HevcParser::default();
+        let mut offsets = Vec::new();
+        parser.get_offsets(&interleaved, &mut offsets);
+
+        let last = *offsets.last().unwrap();
+        let nals = parser
+            .split_nals(&interleaved, &offsets, last, false)
+            .unwrap();
+        assert_eq!(nals.len(), 10);
+
+        let bl_out = SharedBuffer::default();
+        let el_out = SharedBuffer::default();
+        let mut demuxer = DualLayerDemuxer::new(
+            PathBuf::new(),
+            Box::new(bl_out.clone()),
+            Box::new(el_out.clone()),
+        );
+
+        for nal in &nals {
+            demuxer.demux_nal(nal, &interleaved).unwrap();
+        }
+
+        assert_eq!(*bl_out.0.borrow(), layer_stream(&bl));
+        assert_eq!(*el_out.0.borrow(), layer_stream(&el));
+    }
+}
@@ -8,7 +8,11 @@ use anyhow::{Result, bail, ensure};
 use bitvec_helpers::bitstream_io_reader::BsIoSliceReader;
 use matroska_demuxer::{MatroskaFile, TrackType};
 
-use crate::{MAX_PARSE_SIZE, NALUStartCode, config::HEVCDecoderConfigurationRecord, hevc::NALUnit};
+use crate::{
+    MAX_PARSE_SIZE, NALUStartCode,
+    config::HEVCDecoderConfigurationRecord,
+    hevc::{NALUnit, sei::SeiRegistry, timing::FrameRate},
+};
 
 use super::{HevcParser, IoFormat, IoProcessor};
 
@@ -44,6 +48,12 @@ pub struct HevcProcessorOpts {
     /// Stop reading the stream after N frames
     /// The number of processed frames may differ.
     pub limit: Option<u64>,
+
+    /// Frame rate used for the timestamps, instead of the stream timing info
+    pub frame_rate: Option<FrameRate>,
+
+    /// Application decoders for SEI payloads
+    pub sei_registry: SeiRegistry,
 }
 
 impl HevcProcessor {
@@ -55,10 +65,16 @@ impl HevcProcessor {
             Vec::new()
         };
 
+        let parser = HevcParser {
+            frame_rate: opts.frame_rate,
+            sei_registry: opts.sei_registry.clone(),
+            ..Default::default()
+        };
+
         Self {
             opts,
             format,
-            parser: HevcParser::default(),
+            parser,
 
             chunk_size,
             main_buf: vec![0; chunk_size],
@@ -325,6 +341,8 @@ impl Default for HevcProcessorOpts {
             buffer_frame: false,
             parse_nals: true,
             limit: Default::default(),
+            frame_rate: Default::default(),
+            sei_registry: Default::default(),
         }
     }
 }
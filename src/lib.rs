@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 use nom::{IResult, bytes::complete::take_until};
 
 use bitvec_helpers::bitstream_io_reader::BsIoVecReader;
@@ -9,13 +9,19 @@ pub mod utils;
 #[cfg(feature = "hevc_io")]
 pub mod io;
 
+use dpb::DecodedPictureBuffer;
+use hdr_format::HdrClassification;
 use hevc::*;
+use hwaccel::{HwAccelParameters, PictureParameters, SliceParameters};
 use pps::PPSNAL;
+use sei::{SeiContext, SeiPayload, SeiRegistry};
 use slice::SliceNAL;
 use sps::SPSNAL;
+use stats::HrdReport;
+use timing::{FrameRate, PocStep, TimingInfo};
 use vps::VPSNAL;
 
-use utils::clear_start_code_emulation_prevention_3_byte;
+use utils::{clear_start_code_emulation_prevention_3_byte, rbsp_offset_to_nal_offset};
 
 // We don't want to parse large slices because the memory is copied
 const MAX_PARSE_SIZE: usize = 2048;
@@ -36,6 +42,12 @@ pub enum NALUStartCode {
 pub struct HevcParser {
     reader: BsIoVecReader,
     pub nalu_start_code: NALUStartCode,
+    /// Build the `HwAccelParameters` of every frame
+    pub export_hwaccel_params: bool,
+    /// Frame rate used for the timestamps, instead of the stream timing info
+    pub frame_rate: Option<FrameRate>,
+    /// Application decoders for SEI payloads
+    pub sei_registry: SeiRegistry,
 
     nals: Vec<NALUnit>,
     vps: Vec<VPSNAL>,
@@ -50,6 +62,12 @@ pub struct HevcParser {
     current_frame: Frame,
     decoded_index: u64,
     presentation_index: u64,
+
+    dpb: DecodedPictureBuffer,
+    independent_slice: SliceNAL,
+
+    next_pts: i64,
+    poc_step: PocStep,
 }
 
 impl HevcParser {
@@ -136,7 +154,12 @@ impl HevcParser {
         let pos = offset + HEADER_LEN_3;
         let end = offset + size;
 
-        let parsing_end = if size > MAX_PARSE_SIZE {
+        // SEI payloads are always parsed entirely
+        let is_sei = data
+            .get(pos)
+            .is_some_and(|b| matches!(b >> 1, NAL_SEI_PREFIX | NAL_SEI_SUFFIX));
+
+        let parsing_end = if size > MAX_PARSE_SIZE && !is_sei {
             offset + MAX_PARSE_SIZE
         } else {
             end
@@ -198,18 +221,22 @@ impl HevcParser {
         }
 
         if nal.nuh_layer_id > 0 {
+            if parse_nal {
+                self.parse_layer_nal(&nal, data);
+            }
+
             return Ok(nal);
         }
 
         if parse_nal {
-            self.parse_nal_internal(&mut nal)?;
+            self.parse_nal_internal(&mut nal, data)?;
             self.nals.push(nal.clone());
         }
 
         Ok(nal)
     }
 
-    fn parse_nal_internal(&mut self, nal: &mut NALUnit) -> Result<()> {
+    fn parse_nal_internal(&mut self, nal: &mut NALUnit, data: &[u8]) -> Result<()> {
         match nal.nal_type {
             NAL_VPS => self.parse_vps()?,
             NAL_SPS => self.parse_sps()?,
@@ -218,16 +245,30 @@ impl HevcParser {
             NAL_TRAIL_R | NAL_TRAIL_N | NAL_TSA_N | NAL_TSA_R | NAL_STSA_N | NAL_STSA_R
             | NAL_BLA_W_LP | NAL_BLA_W_RADL | NAL_BLA_N_LP | NAL_IDR_W_RADL | NAL_IDR_N_LP
             | NAL_CRA_NUT | NAL_RADL_N | NAL_RADL_R | NAL_RASL_N | NAL_RASL_R => {
-                self.parse_slice(nal)?;
+                self.parse_slice(nal, data)?;
 
                 self.current_frame.nals.push(nal.clone());
             }
             NAL_SEI_SUFFIX | NAL_UNSPEC62 | NAL_UNSPEC63 | NAL_EOS_NUT | NAL_EOB_NUT
             | NAL_FD_NUT => {
+                if matches!(nal.nal_type, NAL_EOS_NUT | NAL_EOB_NUT) {
+                    self.dpb.end_of_sequence();
+                } else if nal.nal_type == NAL_SEI_SUFFIX {
+                    self.parse_sei(nal, data);
+                }
+
                 // Dolby NALs are suffixed to the slices
                 // And EOS, EOB, FD should be contained within the current AU
                 self.current_frame.nals.push(nal.clone());
             }
+            NAL_SEI_PREFIX => {
+                self.add_current_frame();
+
+                nal.decoded_frame_index = self.decoded_index;
+                self.current_frame.nals.push(nal.clone());
+
+                self.parse_sei(nal, data);
+            }
             _ => {
                 self.add_current_frame();
 
@@ -250,6 +291,27 @@ impl HevcParser {
         Ok(())
     }
 
+    /// NALs of the layers above the base layer are kept in the current frame.
+    /// Their parameter sets are not parsed, so the SEI messages are decoded without SPS.
+    fn parse_layer_nal(&mut self, nal: &NALUnit, data: &[u8]) {
+        if matches!(nal.nal_type, NAL_SEI_PREFIX | NAL_SEI_SUFFIX) {
+            let ctx = SeiContext {
+                sps: None,
+                sps_list: &[],
+                registry: Some(&self.sei_registry),
+            };
+
+            let bytes = clear_start_code_emulation_prevention_3_byte(data);
+
+            // Malformed SEI NALs are still kept, without decoded payloads
+            if let Ok(payloads) = SeiPayload::parse_rbsp(&bytes, true, &ctx) {
+                self.current_frame.layer_sei.extend(payloads);
+            }
+        }
+
+        self.current_frame.layer_nals.push(nal.clone());
+    }
+
     fn parse_vps(&mut self) -> Result<()> {
         let vps = VPSNAL::parse(&mut self.reader)?;
 
@@ -261,7 +323,13 @@ impl HevcParser {
     }
 
     fn parse_sps(&mut self) -> Result<()> {
-        let sps = SPSNAL::parse(&mut self.reader)?;
+        let mut sps = SPSNAL::parse(&mut self.reader)?;
+        sps.vps_hrd_parameters = self
+            .vps
+            .get(sps.vps_id as usize)
+            .and_then(VPSNAL::base_layer_hrd_parameters)
+            .cloned();
+
         self.remove_sps(&sps);
 
         self.sps.push(sps);
@@ -279,8 +347,8 @@ impl HevcParser {
         Ok(())
     }
 
-    fn parse_slice(&mut self, nal: &mut NALUnit) -> Result<()> {
-        let slice = SliceNAL::parse(
+    fn parse_slice(&mut self, nal: &mut NALUnit, data: &[u8]) -> Result<()> {
+        let mut slice = SliceNAL::parse(
             &mut self.reader,
             &self.sps,
             &self.pps,
@@ -289,6 +357,12 @@ impl HevcParser {
             &mut self.poc,
         )?;
 
+        if slice.dependent_slice_segment_flag {
+            slice.inherit_from(&self.independent_slice);
+        } else {
+            self.independent_slice = slice.clone();
+        }
+
         // Consecutive slice NALs cases
         if self.current_frame.first_slice.first_slice_in_pic_flag && slice.first_slice_in_pic_flag {
             nal.decoded_frame_index = self.decoded_index + 1;
@@ -297,17 +371,106 @@ impl HevcParser {
 
         if slice.key_frame {
             self.reorder_frames();
+        } else if slice.first_slice_in_pic_flag && self.current_frame.recovery_point().is_some() {
+            self.reorder_frames_before(slice.output_picture_number);
         }
 
         if slice.first_slice_in_pic_flag {
-            self.current_frame.first_slice = slice;
-
             self.current_frame.decoded_number = self.decoded_index;
+
+            self.decode_picture(&slice, nal);
+
+            self.current_frame.first_slice = slice.clone();
+        }
+
+        self.current_frame.slices.push(SliceInfo::from(&slice));
+
+        // The parameters of a picture with an incomplete slice header would be wrong
+        if slice.header_error.is_some() {
+            self.current_frame.hwaccel_params = None;
+        }
+
+        if let Some(params) = self.current_frame.hwaccel_params.as_mut() {
+            let mut slice_params = SliceParameters::new(&slice, nal, &params.picture);
+            slice_params.slice_data_byte_offset =
+                rbsp_offset_to_nal_offset(data, slice.header_size as usize);
+
+            if slice.slice_type != SLICE_TYPE_I {
+                params.picture.intra_pic_flag = false;
+            }
+
+            params.slices.push(slice_params);
         }
 
         Ok(())
     }
 
+    /// Malformed SEI NALs don't abort the parsing, the raw NAL is still kept in the frame
+    fn parse_sei(&mut self, nal: &NALUnit, data: &[u8]) {
+        let ctx = SeiContext {
+            sps: self.sps.get(self.independent_slice.sps_id as usize),
+            sps_list: &self.sps,
+            registry: Some(&self.sei_registry),
+        };
+
+        let bytes = clear_start_code_emulation_prevention_3_byte(data);
+        let Ok(payloads) = SeiPayload::parse_rbsp(&bytes, true, &ctx) else {
+            return;
+        };
+
+        if nal.nal_type == NAL_SEI_PREFIX {
+            for payload in &payloads {
+                if let SeiPayload::PicTiming(pic_timing) = payload
+                    && pic_timing.frame_field_info_present_flag
+                {
+                    self.current_frame.pic_timing = Some(pic_timing.clone());
+                }
+            }
+
+            self.current_frame.prefix_sei.extend(payloads);
+        } else {
+            self.current_frame.suffix_sei.extend(payloads);
+        }
+    }
+
+    /// Applies the RPS of the picture and builds the picture parameters, if enabled
+    fn decode_picture(&mut self, slice: &SliceNAL, nal: &NALUnit) {
+        let (Some(sps), Some(pps)) = (
+            self.sps.get(slice.sps_id as usize),
+            self.pps.get(slice.pps_id as usize),
+        ) else {
+            return;
+        };
+
+        // The RPS is unknown without the complete header, so the references are left as they are
+        if slice.header_error.is_some() {
+            self.dpb
+                .add_picture(slice.pic_order_cnt_val, self.decoded_index);
+            return;
+        }
+
+        let max_poc_lsb = 1 << sps.log2_max_poc_lsb;
+        let (no_rasl_output_flag, rps) = self.dpb.apply_rps(slice, nal, max_poc_lsb);
+
+        if self.export_hwaccel_params {
+            let mut picture = PictureParameters::new(sps, pps, slice, nal, self.decoded_index);
+
+            picture.no_rasl_output_flag = no_rasl_output_flag;
+            picture.reference_frames = self.dpb.pictures.clone();
+            picture.ref_pic_set_st_curr_before = rps.st_curr_before;
+            picture.ref_pic_set_st_curr_after = rps.st_curr_after;
+            picture.ref_pic_set_lt_curr = rps.lt_curr.iter().map(|lt| lt.poc).collect();
+
+            self.current_frame.hwaccel_params = Some(Box::new(HwAccelParameters {
+                picture,
+                slices: Vec::new(),
+            }));
+        }
+
+        self.dpb
+            .add_picture(slice.pic_order_cnt_val, self.decoded_index);
+    }
+
     fn remove_vps(&mut self, vps: &VPSNAL) {
         let id = vps.vps_id as usize;
 
@@ -359,6 +522,15 @@ impl HevcParser {
 
             self.current_frame.frame_type = self.current_frame.first_slice.slice_type;
 
+            if let Some(last_slice) = self
+                .current_frame
+                .hwaccel_params
+                .as_mut()
+                .and_then(|params| params.slices.last_mut())
+            {
+                last_slice.last_slice_of_pic = true;
+            }
+
             self.frames.push(self.current_frame.clone());
 
             self.current_frame = Frame::default();
@@ -374,11 +546,39 @@ impl HevcParser {
             offset += 1;
         });
 
+        self.poc_step.update(&self.frames);
+
+        if let Some(timing) = self.timing_info() {
+            let reorder_delay = self
+                .active_sps()
+                .and_then(|sps| sps.num_reorder_pics.last().copied())
+                .unwrap_or(0);
+
+            timing.assign_timestamps(
+                &mut self.frames,
+                reorder_delay,
+                self.poc_step.get(),
+                &mut self.next_pts,
+            );
+        }
+
         self.presentation_index = offset;
         self.ordered_frames.extend_from_slice(&self.frames);
         self.frames.clear();
     }
 
+    /// Outputs the frames preceding `poc` in output order.
+    /// Used at recovery points, where following frames may still be output before the previous ones.
+    fn reorder_frames_before(&mut self, poc: u64) {
+        let (pending, ready) = std::mem::take(&mut self.frames)
+            .into_iter()
+            .partition(|f| f.presentation_number >= poc);
+
+        self.frames = ready;
+        self.reorder_frames();
+        self.frames = pending;
+    }
+
     pub fn display(&self) {
         println!("{} frames", &self.ordered_frames.len());
         for frame in &self.ordered_frames {
@@ -414,9 +614,107 @@ impl HevcParser {
         &self.ordered_frames
     }
 
+    /// Frames where decoding can start, IRAP pictures or with a recovery point SEI.
+    /// In decoding order, usable as a seek index.
+    pub fn random_access_points(&self) -> Vec<&Frame> {
+        let mut frames: Vec<&Frame> = self
+            .ordered_frames
+            .iter()
+            .filter(|f| f.is_random_access_point())
+            .collect();
+        frames.sort_by_key(|f| f.decoded_number);
+
+        frames
+    }
+
     pub fn get_nals(&self) -> &Vec<NALUnit> {
         &self.nals
     }
+
+    /// Ordered frames, with the field pictures paired into frames
+    pub fn paired_frames(&self) -> Vec<PairedFrame<'_>> {
+        let field_seq = self
+            .active_sps()
+            .is_some_and(|sps| sps.vui_present && sps.vui_parameters.field_seq_flag);
+
+        let mut paired = Vec::with_capacity(self.ordered_frames.len());
+        let mut frames = self.ordered_frames.iter().peekable();
+
+        while let Some(first) = frames.next() {
+            let second = match &first.pic_timing {
+                Some(pt) if pt.is_field() && !pt.is_paired_with_previous() => {
+                    frames.next_if(|next| {
+                        next.pic_timing.as_ref().is_some_and(|next_pt| {
+                            next_pt.is_field() && next_pt.is_top_field() != pt.is_top_field()
+                        })
+                    })
+                }
+                None if field_seq => frames.next(),
+                _ => None,
+            };
+
+            paired.push(PairedFrame { first, second });
+        }
+
+        paired
+    }
+
+    /// Information about the stream, from the active parameter sets
+    pub fn stream_info(&self) -> Option<StreamInfo> {
+        let sps = self.active_sps()?;
+        let vps = self.vps.get(sps.vps_id as usize);
+
+        let pic_timing = self
+            .ordered_frames
+            .iter()
+            .chain(self.frames.iter())
+            .find_map(|f| f.pic_timing.as_ref());
+
+        Some(StreamInfo::new(sps, vps, pic_timing, &self.ordered_frames))
+    }
+
+    /// HDR format of the ordered frames, `None` without active SPS
+    pub fn hdr_classification(&self) -> Option<HdrClassification> {
+        let info = self.stream_info()?;
+
+        Some(HdrClassification::new(&info, &self.ordered_frames))
+    }
+
+    /// Frame rate from the timing info of the stream, VUI first then VPS
+    pub fn detect_frame_rate(&self) -> Option<FrameRate> {
+        let sps = self.active_sps()?;
+        let vps = self.vps.get(sps.vps_id as usize);
+
+        TimingInfo::from_parameter_sets(sps, vps).map(|timing| timing.frame_rate())
+    }
+
+    /// Simulates the HRD of the active SPS over the ordered frames
+    pub fn hrd_report(&self) -> Result<HrdReport> {
+        let Some(sps) = self.active_sps() else {
+            bail!("No active SPS");
+        };
+
+        let Some(timing) = TimingInfo::from_parameter_sets(sps, self.vps.get(sps.vps_id as usize))
+        else {
+            bail!("No timing info in the parameter sets");
+        };
+
+        HrdReport::new(&self.ordered_frames, sps, &timing)
+    }
+
+    fn timing_info(&self) -> Option<TimingInfo> {
+        if let Some(frame_rate) = self.frame_rate {
+            return Some(TimingInfo::from_frame_rate(frame_rate));
+        }
+
+        let sps = self.active_sps()?;
+        TimingInfo::from_parameter_sets(sps, self.vps.get(sps.vps_id as usize))
+    }
+
+    /// SPS referred to by the last parsed slice
+    fn active_sps(&self) -> Option<&SPSNAL> {
+        self.sps.get(self.independent_slice.sps_id as usize)
+    }
 }
 
 impl NALUStartCode {
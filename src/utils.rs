@@ -23,6 +23,26 @@ pub fn clear_start_code_emulation_prevention_3_byte(data: &[u8]) -> Vec<u8> {
     }
 }
 
+/// Converts an offset in the unescaped RBSP data to an offset in the NAL data,
+/// accounting for the `emulation_prevention_three_byte`s
+pub fn rbsp_offset_to_nal_offset(data: &[u8], rbsp_offset: usize) -> usize {
+    let is_emulation_prevention_byte =
+        |i: usize| i > 1 && data[i - 2] == 0 && data[i - 1] == 0 && data[i] == 3;
+
+    let mut offset = 0;
+    let mut rbsp_pos = 0;
+
+    while offset < data.len() && (rbsp_pos < rbsp_offset || is_emulation_prevention_byte(offset)) {
+        if !is_emulation_prevention_byte(offset) {
+            rbsp_pos += 1;
+        }
+
+        offset += 1;
+    }
+
+    offset
+}
+
 /// Within the NAL unit, the following three-byte sequences shall not occur at any byte-aligned position:
 ///   - 0x000000
 ///   - 0x000001
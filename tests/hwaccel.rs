@@ -0,0 +1,166 @@
+use hevc_parser::HevcParser;
+use hevc_parser::hevc::hwaccel::{HwAccelParameters, RefPicListEntry, ReferencePicture};
+use hevc_parser::hevc::{SLICE_TYPE_B, SLICE_TYPE_I, SLICE_TYPE_P};
+
+/// IDR with two slice segments, P with weighted prediction, B,
+/// P with an explicit RPS and a long-term reference, then a second IDR.
+const SAMPLE: &[u8] = include_bytes!("assets/hwaccel.hevc");
+
+fn parse_sample() -> Vec<HwAccelParameters> {
+    let mut parser = HevcParser::default();
+    parser.export_hwaccel_params = true;
+
+    let mut offsets = Vec::new();
+    parser.get_offsets(SAMPLE, &mut offsets);
+
+    let last = *offsets.last().unwrap();
+    parser.split_nals(SAMPLE, &offsets, last, true).unwrap();
+    parser.finish();
+
+    parser
+        .ordered_frames()
+        .iter()
+        .map(|frame| *frame.hwaccel_params.clone().unwrap())
+        .collect()
+}
+
+fn short_term(poc: i32, reference_index: usize) -> RefPicListEntry {
+    RefPicListEntry {
+        poc,
+        long_term: false,
+        reference_index: Some(reference_index),
+    }
+}
+
+fn reference(poc: i32, decoded_number: u64, long_term: bool) -> ReferencePicture {
+    ReferencePicture {
+        poc,
+        decoded_number,
+        long_term,
+    }
+}
+
+#[test]
+fn picture_parameters() {
+    let frames = parse_sample();
+    assert_eq!(frames.len(), 5);
+
+    let idr = &frames[0].picture;
+    assert!(idr.idr_pic_flag && idr.irap_pic_flag && idr.intra_pic_flag);
+    assert!(idr.no_rasl_output_flag);
+    assert!(idr.reference_frames.is_empty());
+    assert_eq!(idr.column_widths, [4, 4]);
+    assert_eq!(idr.row_heights, [4]);
+    assert!(idr.scaling_lists.is_some());
+
+    let p = &frames[1].picture;
+    assert_eq!(p.pic_order_cnt_val, 1);
+    assert!(!p.intra_pic_flag);
+    assert_eq!(p.reference_frames, [reference(0, 0, false)]);
+    assert_eq!(p.ref_pic_set_st_curr_before, [0]);
+
+    let b = &frames[2].picture;
+    assert_eq!(
+        b.reference_frames,
+        [reference(0, 0, false), reference(1, 1, false)]
+    );
+    assert_eq!(b.ref_pic_set_st_curr_before, [1, 0]);
+    assert!(b.ref_pic_set_st_curr_after.is_empty());
+
+    let lt = &frames[3].picture;
+    assert_eq!(lt.st_rps_bits, 7);
+    assert_eq!(lt.num_delta_pocs_of_ref_rps_idx, 1);
+    assert_eq!(
+        lt.reference_frames,
+        [reference(0, 0, true), reference(2, 2, false)]
+    );
+    assert_eq!(lt.ref_pic_set_st_curr_before, [2]);
+    assert_eq!(lt.ref_pic_set_lt_curr, [0]);
+
+    let idr = &frames[4].picture;
+    assert_eq!(idr.pic_order_cnt_val, 0);
+    assert!(idr.reference_frames.is_empty());
+}
+
+#[test]
+fn slice_parameters() {
+    let frames = parse_sample();
+
+    let idr = &frames[0].slices;
+    assert_eq!(idr.len(), 2);
+    assert_eq!(idr[0].slice_type, SLICE_TYPE_I);
+    assert_eq!(idr[0].slice_data_byte_offset, 8);
+    assert_eq!(idr[0].slice_data_size, 16);
+    assert_eq!(idr[0].num_entry_point_offsets, 1);
+    assert!(!idr[0].last_slice_of_pic);
+    assert!(idr[1].dependent_slice_segment_flag && idr[1].last_slice_of_pic);
+    assert_eq!(idr[1].slice_segment_address, 10);
+    assert_eq!(idr[1].slice_data_byte_offset, 7);
+    assert_eq!(idr[1].slice_qp_delta, idr[0].slice_qp_delta);
+
+    let p = &frames[1].slices[0];
+    assert_eq!(p.slice_type, SLICE_TYPE_P);
+    assert_eq!(p.slice_data_byte_offset, 13);
+    assert_eq!(p.ref_pic_list0, [short_term(0, 0), short_term(0, 0)]);
+    assert!(p.ref_pic_list1.is_empty());
+    assert_eq!(p.max_num_merge_cand, 3);
+
+    let pwt = p.pred_weight_table.as_ref().unwrap();
+    assert_eq!(pwt.chroma_log2_weight_denom, 5);
+    assert_eq!(pwt.l0[0].luma_weight, 67);
+    assert_eq!(pwt.l0[0].chroma_weight, [33, 31]);
+    assert_eq!(pwt.l0[0].chroma_offset, [-2, 4]);
+    assert_eq!(pwt.l0[1].luma_weight, 64);
+
+    let b = &frames[2].slices[0];
+    assert_eq!(b.slice_type, SLICE_TYPE_B);
+    assert_eq!(b.ref_pic_list0, [short_term(0, 0)]);
+    assert_eq!(b.ref_pic_list1, [short_term(1, 1)]);
+    assert!(b.mvd_l1_zero_flag);
+
+    let lt = &frames[3].slices[0];
+    assert_eq!(lt.slice_data_byte_offset, 9);
+    assert_eq!(
+        lt.ref_pic_list0,
+        [RefPicListEntry {
+            poc: 0,
+            long_term: true,
+            reference_index: Some(0),
+        }]
+    );
+}
+
+#[test]
+fn truncated_slice_header() {
+    // Keep the P slice header up to the POC
+    let data = [&SAMPLE[..122], &SAMPLE[139..]].concat();
+
+    let mut parser = HevcParser::default();
+    parser.export_hwaccel_params = true;
+
+    let mut offsets = Vec::new();
+    parser.get_offsets(&data, &mut offsets);
+
+    let last = *offsets.last().unwrap();
+    parser.split_nals(&data, &offsets, last, true).unwrap();
+    parser.finish();
+
+    let frames = parser.ordered_frames();
+    assert_eq!(frames.len(), 5);
+    assert_eq!(frames[1].first_slice.output_picture_number, 1);
+    assert!(frames[1].hwaccel_params.is_none());
+    assert!(frames[2].hwaccel_params.is_some());
+}
+
+#[test]
+fn trailing_start_code() {
+    let data = [SAMPLE, &[0, 0, 1]].concat();
+
+    let mut parser = HevcParser::default();
+
+    let mut offsets = Vec::new();
+    parser.get_offsets(&data, &mut offsets);
+
+    let last = *offsets.last().unwrap();
+    assert!(parser.split_nals(&data, &offsets, last, true).is_err());
+}